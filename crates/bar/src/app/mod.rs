@@ -48,14 +48,20 @@ use gtk4_layer_shell::KeyboardMode;
 
 use common::{classes, config::bar::BarConfig, css::Class};
 use daemon::{
+    notifications::NotificationsProxy,
     osk::state::StateProxy,
-    system_state::{BatteryStatus, ConnectionData, SystemStateData},
+    system_state::{
+        BatteryLevel, BatteryStatus, ConnectionData, ConnectionKind, Connectivity, DiskData,
+        SystemStateData, VolumeState,
+    },
+    tray::{TrayItem, TrayProxy},
 };
 
 use crate::{
     icon,
     label_icon::LabelIcon,
     time_playing::{TimePlaying, TimePlayingInput},
+    tray::{TrayIcon, TrayInput, TrayOutput},
     workspaces::Workspaces,
 };
 
@@ -81,6 +87,18 @@ pub struct App<I: Init + 'static> {
     /// Proxy for communication with the daemon
     osk_state_proxy: StateProxy<'static>,
 
+    /// If do not disturb is active
+    dnd: bool,
+    /// Number of notifications the user hasn't seen
+    unread: u32,
+    /// Proxy for communication with the daemon
+    notifications_proxy: NotificationsProxy<'static>,
+
+    /// The tray items
+    tray: FactoryVecDeque<TrayIcon>,
+    /// Proxy for communication with the daemon
+    tray_proxy: TrayProxy<'static>,
+
     /// Marker to distinguish primary and secondary bars
     _init: PhantomData<I>,
 }
@@ -123,6 +141,20 @@ pub enum AppMsg {
     OskActive(bool),
     /// Received from the daemon when the lock state of the osk has changed
     OskLocked(bool),
+    /// Sent when pressing the notifications button
+    ToggleDnd,
+    /// Sent when right clicking the notifications button
+    MarkNotificationsRead,
+    /// Received from the daemon when do not disturb has changed
+    Dnd(bool),
+    /// Received from the daemon when the number of unread notifications has changed
+    Unread(u32),
+    /// Received from the daemon when tray items were added or removed
+    TrayUpdated(Arc<Vec<TrayItem>>),
+    /// Received from the daemon when a single tray item has changed
+    TrayItemChanged(Arc<TrayItem>),
+    /// Sent by a [`TrayIcon`]
+    Tray(TrayOutput),
 }
 
 // NOTE: Should we allow users to config the icons?
@@ -190,6 +222,8 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
                         LabelIcon {
                             #[watch]
                             set_label: &model.set_drive_label(),
+                            #[watch]
+                            set_tooltip_text: Some(&model.drive_tooltip()),
                             set_icon: icon::HARDDISK,
                         },
                     },
@@ -222,6 +256,35 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
                         connect_clicked => AppMsg::ToggleOsk,
                     },
 
+                    /// Do not disturb toggle and number of unread notifications
+                    #[name(notifications)]
+                    gtk::Button {
+                        add_css_class: Class::NotificationsButton.as_ref(),
+                        #[watch]
+                        set_class_active: (Class::Active.as_ref(), model.dnd),
+                        #[watch]
+                        set_tooltip_text: Some(&format!(
+                            "{} unread notifications\nDo not disturb: {}",
+                            model.unread,
+                            if model.dnd { "on" } else { "off" },
+                        )),
+                        connect_clicked => AppMsg::ToggleDnd,
+
+                        LabelIcon {
+                            #[watch]
+                            set_label: &(if model.unread > 0 { model.unread.to_string() } else { String::new() }),
+                            #[watch]
+                            set_icon: if model.dnd { icon::BELL_OFF_FILLED_SYMBOLIC } else { icon::BELL_FILLED_SYMBOLIC },
+                        },
+                    },
+
+                    /// Items of the system tray
+                    #[local_ref]
+                    tray_widget -> gtk::Box {
+                        add_css_class: Class::Tray.as_ref(),
+                        set_orientation: gtk::Orientation::Horizontal,
+                    },
+
                     /// Revealer to show the current network SSID
                     #[name(internet_revealer)]
                     gtk::Revealer {
@@ -229,8 +292,7 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
                         set_transition_type: gtk::RevealerTransitionType::SlideRight,
                         gtk::Label {
                             #[watch]
-                            set_label: if let ConnectionData::Wireless { ssid, .. } = &model.system_state.network {
-                                        &ssid } else { "" }
+                            set_label: model.system_state.network.uplink().map_or("", |c| &c.ssid),
 
                         }
                     },
@@ -239,24 +301,32 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
                     gtk::Image {
                         set_css_classes: &classes!(Icon, InternetIcon),
                         #[watch]
-                        set_class_active: (Class::Active.as_ref(), model.system_state.network != ConnectionData::None),
+                        set_class_active: (Class::Active.as_ref(), model.system_state.network.connected()),
+                        #[watch]
+                        set_class_active: (Class::Limited.as_ref(), matches!(model.system_state.network.connectivity, Connectivity::Portal | Connectivity::Limited)),
                         #[watch]
-                        set_icon_name: Some(match model.system_state.network {
-                                        ConnectionData::Wired => icon::LAN,
-                                        ConnectionData::Wireless {signal, ..} => match *signal {
+                        set_class_active: (Class::Vpn.as_ref(), model.system_state.network.vpn_active()),
+                        #[watch]
+                        set_icon_name: Some(match model.system_state.network.uplink() {
+                                        Some(ConnectionData { kind: ConnectionKind::Wireless, signal, .. }) => match **signal {
                                             0.75.. => icon::RADIOWAVES_1,
                                             0.50.. => icon::RADIOWAVES_2,
                                             0.35.. => icon::RADIOWAVES_3,
                                             _ => icon::RADIOWAVES_4,
                                         },
-                                        ConnectionData::None =>  icon::RADIOWAVES_5,
+                                        Some(_) => icon::LAN,
+                                        None =>  icon::RADIOWAVES_5,
                         }),
                     },
 
                     gtk::Image {
                         set_css_classes: &classes!(Icon, BluetoothIcon),
                         #[watch]
-                        set_class_active: (Class::Active.as_ref(), model.system_state.bluetooth),
+                        set_class_active: (Class::Active.as_ref(), model.system_state.bluetooth.any_connected()),
+                        #[watch]
+                        set_class_active: (Class::Disabled.as_ref(), !model.system_state.bluetooth.powered()),
+                        #[watch]
+                        set_tooltip_text: Some(&model.bluetooth_tooltip()),
                         set_icon_name: Some(icon::BLUETOOTH),
                     },
 
@@ -281,18 +351,22 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
 
                     LabelIcon {
                         #[watch]
-                        set_label: &(if *model.system_state.volume > 0.0 { model.system_state.volume.to_string() } else { String::new() }),
+                        set_visible: model.system_state.volume.available,
                         #[watch]
-                        set_class_active: (Class::Muted.as_ref(), *model.system_state.volume == -1.0),
+                        set_label: &(match model.system_state.volume {
+                                        VolumeState { muted: false, level, .. } if *level > 0.0 => level.to_string(),
+                                        VolumeState { .. } => String::new(),
+                                    }),
                         #[watch]
-                        set_icon: match *model.system_state.volume {
-                                    -1.0 => icon::SPEAKER_OFF_FILLED,
-                                    _ if model.system_state.bluetooth => icon::SPEAKER_BLUETOOTH_FILLED_SYMBOLIC,
-                                    0.0 => icon::SPEAKER_MUTE_FILLED,
-                                    0.66.. => icon::SPEAKER_2_FILLED,
-                                    0.33.. => icon::SPEAKER_1_FILLED,
-                                    0.0.. => icon::SPEAKER_0_FILLED,
-                                    _ => unreachable!(),
+                        set_class_active: (Class::Muted.as_ref(), model.system_state.volume.muted),
+                        #[watch]
+                        set_icon: match model.system_state.volume {
+                                    VolumeState { available: false, .. } | VolumeState { muted: true, .. } => icon::SPEAKER_OFF_FILLED,
+                                    VolumeState { bluetooth: true, .. } => icon::SPEAKER_BLUETOOTH_FILLED_SYMBOLIC,
+                                    VolumeState { level, .. } if *level >= 0.66 => icon::SPEAKER_2_FILLED,
+                                    VolumeState { level, .. } if *level >= 0.33 => icon::SPEAKER_1_FILLED,
+                                    VolumeState { level, .. } if *level > 0.0 => icon::SPEAKER_0_FILLED,
+                                    VolumeState { .. } => icon::SPEAKER_MUTE_FILLED,
                                   }
                     },
 
@@ -308,11 +382,13 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
                         #[watch]
                         set_visible: model.system_state.battery.is_some(),
                         #[watch]
-                        set_class_active: (Class::BatteryLow.as_ref(), model.system_state.battery.as_ref().is_some_and(|b| *b.charge <= 0.3)),
+                        set_class_active: (Class::BatteryLow.as_ref(), model.system_state.battery.as_ref().is_some_and(|b| b.level == BatteryLevel::Low)),
+                        #[watch]
+                        set_class_active: (Class::BatteryCritical.as_ref(), model.system_state.battery.as_ref().is_some_and(|b| b.level == BatteryLevel::Critical)),
                         #[watch]
                         set_icon:
-                            (*model.system_state.battery).as_ref().map_or(icon::BATTERY_MISSING, |battery| if battery.status == BatteryStatus::Charging {
-                                    if *battery.charge == 1.0 {
+                            (*model.system_state.battery).as_ref().map_or(icon::BATTERY_MISSING, |battery| if matches!(battery.status, BatteryStatus::Charging | BatteryStatus::Full) {
+                                    if battery.status == BatteryStatus::Full || *battery.charge == 1.0 {
                                         icon::BATTERY_LEVEL_100_CHARGED
                                     } else {
                                         icon::BATTERY_LEVEL_0_CHARGING
@@ -367,12 +443,20 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
             osk_active: bool::default(),
             osk_locked: bool::default(),
             osk_state_proxy: StateProxy::new(&connection).await.unwrap(),
+            dnd: bool::default(),
+            unread: u32::default(),
+            notifications_proxy: NotificationsProxy::new(&connection).await.unwrap(),
+            tray: FactoryVecDeque::builder()
+                .launch_default()
+                .forward(sender.input_sender(), AppMsg::Tray),
+            tray_proxy: TrayProxy::new(&connection).await.unwrap(),
 
             _init: PhantomData,
         };
 
         let workspaces_widget = model.workspaces.widget();
         let time_playing_widget = model.time_playing.widget();
+        let tray_widget = model.tray.widget();
         let widgets = view_output!();
 
         let internet_controller = gtk::EventControllerMotion::new();
@@ -386,6 +470,13 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
         });
         widgets.internet_icon.add_controller(internet_controller);
 
+        let mark_read_controller = gtk::GestureClick::builder().button(3).build();
+        let input_sender = sender.input_sender().clone();
+        mark_read_controller.connect_released(move |_, _, _, _| {
+            input_sender.emit(AppMsg::MarkNotificationsRead);
+        });
+        widgets.notifications.add_controller(mark_read_controller);
+
         #[cfg(debug_assertions)]
         {
             widgets.bar_main_window.set_focusable(true);
@@ -434,29 +525,167 @@ impl<I: Init + 'static> SimpleAsyncComponent for App<I> {
             },
             AppMsg::OskActive(val) => self.osk_active = val,
             AppMsg::OskLocked(val) => self.osk_locked = val,
+            AppMsg::ToggleDnd => {
+                if let Err(e) = self.notifications_proxy.set_dnd(!self.dnd).await {
+                    log::error!("Failed to set do not disturb: {e}");
+                }
+            }
+            AppMsg::MarkNotificationsRead => {
+                if let Err(e) = self.notifications_proxy.mark_all_read().await {
+                    log::error!("Failed to mark notifications as read: {e}");
+                }
+            }
+            AppMsg::Dnd(val) => self.dnd = val,
+            AppMsg::Unread(val) => self.unread = val,
+            AppMsg::TrayUpdated(items) => self.update_tray(&items),
+            AppMsg::TrayItemChanged(item) => {
+                if let Some(index) = self.tray.iter().position(|icon| icon.item().id == item.id) {
+                    self.tray.send(index, TrayInput::Update((*item).clone()));
+                }
+            }
+            AppMsg::Tray(output) => {
+                let result = match &output {
+                    TrayOutput::Activate(id) => self.tray_proxy.activate(id, 0, 0).await,
+                    TrayOutput::SecondaryActivate(id) => {
+                        self.tray_proxy.secondary_activate(id, 0, 0).await
+                    }
+                    TrayOutput::ContextMenu(id) => self.tray_proxy.context_menu(id, 0, 0).await,
+                    TrayOutput::MenuOpened(id) => self.tray_proxy.menu_about_to_show(id).await,
+                    TrayOutput::MenuClicked(id, entry) => {
+                        self.tray_proxy.menu_clicked(id, *entry).await
+                    }
+                };
+
+                if let Err(e) = result {
+                    log::error!("Failed to handle {output:?}: {e}");
+                }
+            }
         }
     }
 }
 
 impl<I: Init + 'static> App<I> {
-    /// Helper function to set the [`AppWidgets::drive`] label
-    fn set_drive_label(&self) -> String {
+    /// Helper function to list the connected bluetooth devices and their battery charge
+    fn bluetooth_tooltip(&self) -> String {
         self.system_state
+            .bluetooth
+            .connected()
+            .map(|device| {
+                (*device.battery).map_or_else(
+                    || device.alias.clone(),
+                    |battery| format!("{} ({battery})", device.alias),
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Helper function to get the disks shown in the [`AppWidgets::drive`] label
+    ///
+    /// These are the disks in [`BarConfig::disks`] followed by all removable disks, so e.g. a USB
+    /// drive shows up as soon as it is mounted.
+    fn shown_disks(&self) -> Vec<&DiskData> {
+        let mut disks: Vec<&DiskData> = self
+            .config
             .disks
             .iter()
-            .find(|d| d.name == *self.config.disk)
-            .map_or_else(
-                || {
-                    log::error!("Failed to find disk: {}", self.config.disk);
+            .filter_map(|mount_point| {
+                let disk = self.system_state.disk(mount_point);
+
+                if disk.is_none() {
+                    log::error!("Failed to find disk mounted at: {}", mount_point.display());
                     log::info!("Available disks: ");
 
                     for (pos, disk) in self.system_state.disks.iter().enumerate() {
-                        log::info!("{}. {}", pos + 1, disk.name);
+                        log::info!("{}. {} ({})", pos + 1, disk.mount_point, disk.name);
                     }
+                }
 
-                    "Err".to_string()
-                },
-                |disk| disk.used.to_string(),
-            )
+                disk
+            })
+            .collect();
+
+        let removable: Vec<&DiskData> = self
+            .system_state
+            .disks
+            .iter()
+            .filter(|d| d.removable && !disks.iter().any(|s| s.mount_point == d.mount_point))
+            .collect();
+
+        disks.extend(removable);
+
+        disks
+    }
+
+    /// Updates [`Self::tray`] according to `items`
+    ///
+    /// If the same items are shown in the same order they are updated in place, so open menus
+    /// stay open.
+    fn update_tray(&mut self, items: &[TrayItem]) {
+        let same_items = self.tray.len() == items.len()
+            && self
+                .tray
+                .iter()
+                .zip(items)
+                .all(|(icon, item)| icon.item().id == item.id);
+
+        if same_items {
+            for (index, item) in items.iter().enumerate() {
+                if self.tray.get(index).is_some_and(|icon| icon.item() != item) {
+                    self.tray.send(index, TrayInput::Update(item.clone()));
+                }
+            }
+
+            return;
+        }
+
+        let mut guard = self.tray.guard();
+
+        guard.clear();
+
+        for item in items {
+            guard.push_back(item.clone());
+        }
+    }
+
+    /// Helper function to set the [`AppWidgets::drive`] label
+    fn set_drive_label(&self) -> String {
+        let disks = self.shown_disks();
+
+        if disks.is_empty() {
+            return "Err".to_string();
+        }
+
+        disks
+            .iter()
+            .map(|disk| disk.used.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Helper function to list the shown disks with their usage and throughput
+    fn drive_tooltip(&self) -> String {
+        self.shown_disks()
+            .iter()
+            .map(|disk| {
+                format!(
+                    "{}: {} used, read {}, write {}",
+                    disk.mount_point,
+                    disk.used,
+                    format_rate(disk.read_rate),
+                    format_rate(disk.write_rate)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Helper function to format a throughput in bytes per second
+fn format_rate(rate: u64) -> String {
+    if rate < 1_048_576 {
+        format!("{} KiB/s", rate / 1024)
+    } else {
+        format!("{} MiB/s", rate / 1_048_576)
     }
 }
@@ -5,7 +5,13 @@
 //!
 //! See: [`StateBroker`]
 use crate::app::AppMsg;
-use daemon::{config::ConfigProxy, osk::state::StateProxy, system_state::SystemStateProxy};
+use daemon::{
+    config::ConfigProxy,
+    notifications::NotificationsProxy,
+    osk::state::StateProxy,
+    system_state::SystemStateProxy,
+    tray::{Tray, TrayItem, TrayProxy},
+};
 use futures_util::StreamExt;
 use std::{
     marker::PhantomData,
@@ -85,6 +91,8 @@ impl StateBroker<SendingUpdates> {
         let config_proxy = ConfigProxy::new(&self.connection).await?;
         let state_proxy = SystemStateProxy::new(&self.connection).await?;
         let osk_state_proxy = StateProxy::new(&self.connection).await?;
+        let notifications_proxy = NotificationsProxy::new(&self.connection).await?;
+        let tray_proxy = TrayProxy::new(&self.connection).await?;
 
         let mut state_stream = state_proxy.receive_state_data_changed().await.fuse();
         let mut config_stream = config_proxy.receive_config_changed().await.fuse();
@@ -92,6 +100,14 @@ impl StateBroker<SendingUpdates> {
         let mut osk_active_stream = osk_state_proxy.receive_active_changed().await.fuse();
         let mut osk_active_locked_stream =
             osk_state_proxy.receive_active_locked_changed().await.fuse();
+        let mut dnd_stream = notifications_proxy.receive_dnd_changed().await.fuse();
+        let mut unread_stream = notifications_proxy.receive_unread_changed().await.fuse();
+        let mut tray_stream = tray_proxy.receive_items_changed().await.fuse();
+        let mut tray_item_stream = tray_proxy
+            .inner()
+            .receive_signal(Tray::ITEM_CHANGED)
+            .await?
+            .fuse();
 
         loop {
             futures_util::select! {
@@ -113,6 +129,20 @@ impl StateBroker<SendingUpdates> {
                 locked = osk_active_locked_stream.select_next_some() => {
                     self.send_update(&AppMsg::OskLocked(locked.get().await?));
                 }
+                dnd = dnd_stream.select_next_some() => {
+                    self.send_update(&AppMsg::Dnd(dnd.get().await?));
+                }
+                unread = unread_stream.select_next_some() => {
+                    self.send_update(&AppMsg::Unread(unread.get().await?));
+                }
+                items = tray_stream.select_next_some() => {
+                    self.send_update(&AppMsg::TrayUpdated(Arc::new(items.get().await?)));
+                }
+                message = tray_item_stream.select_next_some() => {
+                    let item: TrayItem = message.body().deserialize()?;
+
+                    self.send_update(&AppMsg::TrayItemChanged(Arc::new(item)));
+                }
             }
         }
     }
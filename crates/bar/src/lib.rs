@@ -12,6 +12,10 @@ use relm4::{
 
 use crate::app::{App, primary::Primary};
 
+#[allow(
+    dead_code,
+    reason = "Some symbols are not used by the bar. This is ok since all components share the same icon dir."
+)]
 #[allow(
     clippy::doc_markdown,
     reason = "Upstream issue. Already fixed. Remove this when relm4-icons 10.0.1 is released."
@@ -33,6 +37,7 @@ mod icon {
 mod app;
 mod label_icon;
 mod time_playing;
+mod tray;
 mod workspaces;
 
 /// Launches the Bar on all monitors
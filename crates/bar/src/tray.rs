@@ -0,0 +1,315 @@
+//! System tray items
+//!
+//! Every [`TrayIcon`] shows one item registered with the daemon. Left clicking activates the item,
+//! middle clicking secondary activates it and right clicking opens its menu in a popover.
+//!
+//! Tray items expect to be given the position of the click on the screen, which isn't known on
+//! wayland, so all positions are sent as 0.
+use relm4::{
+    gtk::{self, gdk, gio, glib, glib::object::CastNone, prelude::*},
+    prelude::*,
+};
+
+use common::css::Class;
+use daemon::tray::{ItemStatus, MenuEntry, MenuToggle, Pixmap, TrayItem};
+
+/// Name of the action group containing the menu entries of a [`TrayIcon`]
+const ACTION_GROUP: &str = "tray";
+
+/// A single tray item
+#[derive(Debug)]
+pub struct TrayIcon {
+    /// The item shown
+    item: TrayItem,
+    /// The menu of the item
+    popover: gtk::PopoverMenu,
+}
+
+/// Input messages for [`TrayIcon`]
+#[derive(Debug)]
+pub enum TrayInput {
+    /// The item has changed
+    Update(TrayItem),
+    /// The icon was clicked with this mouse button
+    Clicked(u32),
+    /// The menu entry with this id was clicked
+    MenuClicked(i32),
+}
+
+/// Output messages of [`TrayIcon`]
+///
+/// All of these contain the id of the item.
+#[derive(Debug, Clone)]
+pub enum TrayOutput {
+    /// Activate the item
+    Activate(String),
+    /// Secondary activate the item
+    SecondaryActivate(String),
+    /// Ask the item to show its own context menu
+    ContextMenu(String),
+    /// The menu of the item was opened
+    MenuOpened(String),
+    /// The menu entry with the id was clicked
+    MenuClicked(String, i32),
+}
+
+impl TrayIcon {
+    /// The item shown
+    pub const fn item(&self) -> &TrayItem {
+        &self.item
+    }
+
+    /// Helper function to get the tooltip of the item
+    ///
+    /// Items without a tooltip use their title instead.
+    fn tooltip(&self) -> String {
+        match (&*self.item.tooltip_title, &*self.item.tooltip_body) {
+            ("", _) => self.item.title.clone(),
+            (title, "") => title.to_string(),
+            (title, body) => format!("{title}\n{body}"),
+        }
+    }
+
+    /// Helper function to show the menu of the item
+    ///
+    /// Items without a menu are asked to show their own context menu instead.
+    fn open_menu(&self) -> Option<TrayOutput> {
+        if self.item.menu.is_empty() {
+            return Some(TrayOutput::ContextMenu(self.item.id.clone()));
+        }
+
+        self.popover.popup();
+
+        Some(TrayOutput::MenuOpened(self.item.id.clone()))
+    }
+}
+
+/// Auto-generated widget for [`TrayIcon`]
+#[relm4::factory(pub)]
+impl FactoryComponent for TrayIcon {
+    type Init = TrayItem;
+    type Input = TrayInput;
+    type Output = TrayOutput;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        /// Icon of the item
+        gtk::Image {
+            add_css_class: Class::TrayItem.as_ref(),
+            #[watch]
+            set_class_active: (Class::TrayItemAttention.as_ref(), self.item.status == ItemStatus::NeedsAttention),
+            #[watch]
+            set_visible: self.item.status != ItemStatus::Passive,
+            #[watch]
+            set_tooltip_text: Some(&self.tooltip()),
+
+            add_controller = gtk::GestureClick {
+                set_button: 0,
+                connect_released[sender] => move |gesture, _, _, _| {
+                    sender.input(TrayInput::Clicked(gesture.current_button()));
+                },
+            },
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+
+        self.popover.set_parent(&root);
+        set_icon(&root, &self.item);
+        set_menu(&root, &self.popover, &self.item.menu, &sender);
+
+        widgets
+    }
+
+    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
+        let popover = gtk::PopoverMenu::from_model(None::<&gio::MenuModel>);
+        popover.set_has_arrow(false);
+
+        Self {
+            item: init,
+            popover,
+        }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        message: Self::Input,
+        sender: FactorySender<Self>,
+    ) {
+        let id = self.item.id.clone();
+
+        let output = match message {
+            TrayInput::Update(item) => {
+                if let Some(image) = self.popover.parent().and_downcast::<gtk::Image>() {
+                    set_icon(&image, &item);
+
+                    if item.menu != self.item.menu {
+                        set_menu(&image, &self.popover, &item.menu, &sender);
+                    }
+                }
+
+                self.item = item;
+                None
+            }
+            TrayInput::Clicked(1) if self.item.item_is_menu => self.open_menu(),
+            TrayInput::Clicked(1) => Some(TrayOutput::Activate(id)),
+            TrayInput::Clicked(2) => Some(TrayOutput::SecondaryActivate(id)),
+            TrayInput::Clicked(3) => self.open_menu(),
+            TrayInput::Clicked(_) => None,
+            TrayInput::MenuClicked(entry) => Some(TrayOutput::MenuClicked(id, entry)),
+        };
+
+        if let Some(output) = output
+            && sender.output(output).is_err()
+        {
+            log::error!("Failed to send output of tray item {}", self.item.id);
+        }
+
+        self.update_view(widgets, sender);
+    }
+
+    fn shutdown(&mut self, _widgets: &mut Self::Widgets, _output: relm4::Sender<Self::Output>) {
+        self.popover.unparent();
+    }
+}
+
+/// Helper function to show the icon of `item` in `image`
+///
+/// Icon names are preferred over pixmaps. While the item needs attention its attention icon is
+/// used, if it has one.
+fn set_icon(image: &gtk::Image, item: &TrayItem) {
+    let attention = item.status == ItemStatus::NeedsAttention
+        && (!item.attention_icon_name.is_empty() || !item.attention_icon.is_empty());
+
+    let (name, pixmaps) = if attention {
+        (&item.attention_icon_name, &item.attention_icon)
+    } else {
+        (&item.icon_name, &item.icon)
+    };
+
+    let theme = gtk::IconTheme::for_display(&image.display());
+
+    // Some applications ship their own icons instead of using the icon theme
+    if !item.icon_theme_path.is_empty()
+        && !theme
+            .search_path()
+            .iter()
+            .any(|p| p.as_os_str() == item.icon_theme_path.as_str())
+    {
+        theme.add_search_path(&item.icon_theme_path);
+    }
+
+    if name.starts_with('/') {
+        image.set_from_file(Some(name));
+    } else if !name.is_empty() && theme.has_icon(name) {
+        image.set_icon_name(Some(name));
+    } else if let Some(texture) = texture(pixmaps) {
+        image.set_paintable(Some(&texture));
+    } else {
+        image.set_icon_name(Some(name));
+    }
+}
+
+/// Helper function to convert the largest of `pixmaps` into a texture
+///
+/// Returns [`None`] if there are no pixmaps or the largest doesn't contain enough data.
+fn texture(pixmaps: &[Pixmap]) -> Option<gdk::MemoryTexture> {
+    let pixmap = pixmaps
+        .iter()
+        .max_by_key(|p| i64::from(p.width) * i64::from(p.height))?;
+
+    let width = usize::try_from(pixmap.width).ok()?;
+    let height = usize::try_from(pixmap.height).ok()?;
+    let stride = width.checked_mul(4)?;
+
+    if width == 0 || pixmap.data.len() < stride.checked_mul(height)? {
+        return None;
+    }
+
+    Some(gdk::MemoryTexture::new(
+        pixmap.width,
+        pixmap.height,
+        gdk::MemoryFormat::A8r8g8b8,
+        &glib::Bytes::from(pixmap.data.as_slice()),
+        stride,
+    ))
+}
+
+/// Helper function to show `entries` in `popover`
+///
+/// Every entry gets an action in the [`ACTION_GROUP`] of `widget`, which sends
+/// [`TrayInput::MenuClicked`] when activated.
+fn set_menu(
+    widget: &impl IsA<gtk::Widget>,
+    popover: &gtk::PopoverMenu,
+    entries: &[MenuEntry],
+    sender: &FactorySender<TrayIcon>,
+) {
+    let actions = gio::SimpleActionGroup::new();
+
+    for entry in entries.iter().filter(|e| !e.separator) {
+        let name = format!("entry-{}", entry.id);
+
+        let action = if entry.toggle == MenuToggle::None {
+            gio::SimpleAction::new(&name, None)
+        } else {
+            gio::SimpleAction::new_stateful(&name, None, &entry.checked.to_variant())
+        };
+
+        action.set_enabled(entry.enabled);
+
+        let id = entry.id;
+        let sender = sender.clone();
+        action.connect_activate(move |_, _| sender.input(TrayInput::MenuClicked(id)));
+
+        actions.add_action(&action);
+    }
+
+    widget.insert_action_group(ACTION_GROUP, Some(&actions));
+    popover.set_menu_model(Some(&menu_model(entries, 0)));
+}
+
+/// Helper function to create the menu of all children of the entry `parent`
+///
+/// Separators split the menu into sections. Radio entries are shown as checkmarks, since they are
+/// toggled by the application anyway.
+fn menu_model(entries: &[MenuEntry], parent: i32) -> gio::Menu {
+    let menu = gio::Menu::new();
+    let mut section = gio::Menu::new();
+
+    for entry in entries.iter().filter(|e| e.parent == parent) {
+        if entry.separator {
+            if section.n_items() > 0 {
+                menu.append_section(None, &section);
+                section = gio::Menu::new();
+            }
+
+            continue;
+        }
+
+        if entries.iter().any(|e| e.parent == entry.id) {
+            section.append_submenu(Some(&entry.label), &menu_model(entries, entry.id));
+        } else {
+            section.append(
+                Some(&entry.label),
+                Some(&format!("{ACTION_GROUP}.entry-{}", entry.id)),
+            );
+        }
+    }
+
+    if section.n_items() > 0 {
+        menu.append_section(None, &section);
+    }
+
+    menu
+}
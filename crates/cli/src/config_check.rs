@@ -0,0 +1,339 @@
+//! Validation of the config files without a running daemon
+//!
+//! See [`find_errors`]
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use common::{config::layouts::Layout, css::Class};
+use daemon::{
+    config::{
+        self,
+        error::{ConfigError, ConfigSource},
+        layers::{self, ConfigLayer, LayerScope},
+    },
+    system_state::POWER_SUPPLY_PATH,
+};
+use serde::Deserialize;
+use sysinfo::Disks;
+use toml::Spanned;
+
+/// The parts of `config.toml` which are checked beyond parsing
+///
+/// Spanned, so errors can point to the offending value.
+#[derive(Deserialize)]
+struct SpannedConfig {
+    /// See [`common::config::bar::BarConfig`]
+    bar: Option<SpannedBarConfig>,
+}
+
+/// See [`SpannedConfig`]
+#[derive(Deserialize)]
+struct SpannedBarConfig {
+    /// See [`common::config::bar::BarConfig::disks`]
+    disks: Option<Vec<Spanned<String>>>,
+    /// See [`common::config::bar::BarConfig::battery`]
+    battery: Option<Spanned<String>>,
+}
+
+/// Find all errors in the config located at `path`
+///
+/// Checks all files (including all layers of `config.toml`) the same way the daemon does when
+/// loading them and additionally:
+///
+/// - `default_layout` names an existing layout
+/// - all classes used in `style.scss` are valid [`Class`]es
+///
+/// If `system_checks` then also checks that values referring to the current system (e.g. the disks
+/// shown in the bar) exist. `scope` decides which layers of `config.toml` are checked.
+#[must_use]
+pub fn find_errors(path: &Path, system_checks: bool, scope: LayerScope) -> Vec<ConfigError> {
+    let mut errors = check_toml(path, system_checks, scope);
+
+    errors.extend(check_scss(&path.join("style.scss")));
+    errors.extend(check_layouts(&path.join("layouts.json")));
+
+    errors
+}
+
+/// Helper function to read a config file
+fn read(source: ConfigSource, path: &Path) -> Result<String, ConfigError> {
+    fs::read_to_string(path).map_err(|e| ConfigError::from_io(source, path, &e))
+}
+
+/// Check `config.toml` and all other layers in `scope` merged with it
+///
+/// See [`daemon::config::layers`]
+fn check_toml(config_dir: &Path, system_checks: bool, scope: LayerScope) -> Vec<ConfigError> {
+    let layers = match layers::read_layers(config_dir, scope) {
+        Ok(layers) => layers,
+        Err(e) => return vec![e],
+    };
+
+    // Only possible in `LayerScope::ConfigDir`, where the config may come from somewhere else
+    if layers.is_empty() {
+        return Vec::new();
+    }
+
+    if let Err(e) = layers::merge_layers(&layers) {
+        return vec![e];
+    }
+
+    if !system_checks {
+        return Vec::new();
+    }
+
+    // The last layer setting a value is the one it is taken from
+    let mut disks = None;
+    let mut battery = None;
+
+    for layer in &layers {
+        let Ok(SpannedConfig { bar: Some(bar) }) = toml::from_str(&layer.content) else {
+            continue;
+        };
+
+        disks = bar.disks.map(|d| (layer, d)).or(disks);
+        battery = bar.battery.map(|b| (layer, b)).or(battery);
+    }
+
+    let error_at = |layer: &ConfigLayer, offset: usize, message: String| {
+        ConfigError::at_offset(
+            ConfigSource::Toml,
+            &layer.path,
+            &layer.content,
+            Some(offset),
+            message,
+        )
+    };
+
+    let mut errors = Vec::new();
+
+    if let Some((layer, disks)) = disks {
+        let mounted = Disks::new_with_refreshed_list();
+
+        for disk in disks {
+            if mounted
+                .iter()
+                .any(|d| d.mount_point() == Path::new(disk.get_ref()))
+            {
+                continue;
+            }
+
+            let available = mounted
+                .iter()
+                .map(|d| d.mount_point().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            errors.push(error_at(
+                layer,
+                disk.span().start,
+                format!(
+                    "no disk is mounted at \"{}\" (available: {available})",
+                    disk.get_ref()
+                ),
+            ));
+        }
+    }
+
+    if let Some((layer, battery)) = battery {
+        let battery_path = PathBuf::from(POWER_SUPPLY_PATH).join(battery.get_ref());
+
+        if !battery_path.exists() {
+            errors.push(error_at(
+                layer,
+                battery.span().start,
+                format!("battery \"{}\" doesn't exist", battery_path.display()),
+            ));
+        }
+    }
+
+    errors
+}
+
+/// Check `style.scss` and all files it imports
+fn check_scss(path: &Path) -> Vec<ConfigError> {
+    let (css, files) = config::compile_scss(path);
+
+    let css = match css {
+        Ok(css) => css,
+        Err(e) => return vec![e],
+    };
+
+    let mut unknown: Vec<&str> = class_selectors(&css)
+        .into_iter()
+        .filter(|class| Class::from_str(class).is_err())
+        .collect();
+
+    unknown.sort_unstable();
+    unknown.dedup();
+
+    unknown
+        .into_iter()
+        .map(|class| locate_class(path, class, &files))
+        .collect()
+}
+
+/// Create an error for the unknown `class`, located at its first use in one of `files`
+///
+/// Falls back to `path` without a location if the class can't be found.
+fn locate_class(path: &Path, class: &str, files: &[PathBuf]) -> ConfigError {
+    let message = format!("unknown class \".{class}\"");
+
+    for file in files {
+        let Ok(content) = fs::read_to_string(file) else {
+            continue;
+        };
+
+        let offset = content
+            .match_indices(&format!(".{class}"))
+            .find_map(|(i, m)| {
+                let end = i + m.len();
+
+                (!content[end..].starts_with(is_ident_char)).then_some(i)
+            });
+
+        if offset.is_some() {
+            return ConfigError::at_offset(ConfigSource::Scss, file, &content, offset, message);
+        }
+    }
+
+    ConfigError::at_offset(ConfigSource::Scss, path, "", None, message)
+}
+
+/// Check `layouts.json`
+fn check_layouts(path: &Path) -> Vec<ConfigError> {
+    let content = match read(ConfigSource::Layouts, path) {
+        Ok(content) => content,
+        Err(e) => return vec![e],
+    };
+
+    let layouts = match config::parse_layouts(path, &content) {
+        Ok(layouts) => layouts,
+        Err(e) => return vec![e],
+    };
+
+    if layouts.get_default_layout().is_some() {
+        return Vec::new();
+    }
+
+    let available = layouts
+        .layouts()
+        .iter()
+        .map(Layout::name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    vec![ConfigError::at_offset(
+        ConfigSource::Layouts,
+        path,
+        &content,
+        content.find("\"default_layout\""),
+        format!(
+            "default_layout \"{}\" doesn't exist (available: {available})",
+            layouts.default_layout_name()
+        ),
+    )]
+}
+
+/// If `c` can be part of a css identifier
+const fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// Get all class names used in the selectors of `css`
+///
+/// This is only a very simple tokenizer, which relies on `css` being the output of the scss
+/// compiler. Comments, strings and at-rules are skipped.
+fn class_selectors(css: &str) -> Vec<&str> {
+    let mut classes = Vec::new();
+    // Classes found since the last `{`, `}` or `;`
+    // These are only selectors if the next of those is a `{`.
+    let mut candidates = Vec::new();
+    let mut at_rule = false;
+    let mut prelude_empty = true;
+
+    let mut chars = css.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '/' if chars.next_if(|(_, c)| *c == '*').is_some() => {
+                let end = css[i + 2..].find("*/").map_or(css.len(), |e| i + e + 4);
+
+                while chars.next_if(|(j, _)| *j < end).is_some() {}
+            }
+            '"' | '\'' => {
+                while let Some((_, s)) = chars.next() {
+                    match s {
+                        '\\' => _ = chars.next(),
+                        s if s == c => break,
+                        _ => (),
+                    }
+                }
+            }
+            '{' => {
+                if !at_rule {
+                    classes.append(&mut candidates);
+                }
+
+                candidates.clear();
+                at_rule = false;
+                prelude_empty = true;
+            }
+            '}' | ';' => {
+                candidates.clear();
+                at_rule = false;
+                prelude_empty = true;
+            }
+            '@' if prelude_empty => at_rule = true,
+            '.' => {
+                let start = i + 1;
+                let rest = &css[start..];
+
+                let starts_ident = rest.starts_with(|c: char| {
+                    c.is_ascii_alphabetic() || c == '_' || c == '-' || !c.is_ascii()
+                });
+
+                if starts_ident {
+                    let len = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+
+                    candidates.push(&rest[..len]);
+
+                    while chars.next_if(|(j, _)| *j < start + len).is_some() {}
+                }
+
+                prelude_empty = false;
+            }
+            c if c.is_whitespace() => (),
+            _ => prelude_empty = false,
+        }
+    }
+
+    classes
+}
+
+#[cfg(test)]
+mod test {
+    use super::class_selectors;
+
+    #[test]
+    fn finds_class_selectors() {
+        let css = r#"/* .comment */
+.bar, window > .launcher-entry:hover {
+  margin: .5em;
+  font-family: "a.b";
+}
+@media (min-width: 1.5px) {
+  .nested.other {}
+}
+[title=".quoted"] .x_y {}
+"#;
+
+        assert_eq!(
+            class_selectors(css),
+            ["bar", "launcher-entry", "nested", "other", "x_y"]
+        );
+    }
+}
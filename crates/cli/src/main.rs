@@ -3,6 +3,13 @@
 //! This CLI is used to interact with the different components of the shell.
 use clap::{Parser, Subcommand, ValueEnum};
 use common::{config, logger};
+use daemon::{
+    audio::AudioProxy,
+    brightness::BrightnessProxy,
+    config::layers::{self, LayerScope},
+    diagnostics::DiagnosticsProxy,
+    logging::LoggingProxy,
+};
 use prettytable::{Table, row};
 use strum::{Display, IntoEnumIterator};
 use sysinfo::{Process, ProcessRefreshKind, System};
@@ -12,9 +19,11 @@ use std::{
     fmt::Write,
     fs,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, ExitCode},
 };
 
+pub mod config_check;
+
 #[derive(Parser, Debug)]
 /// The CLI for the dod-shell
 ///
@@ -24,6 +33,11 @@ struct Cli {
     /// The [Action] to perform
     #[command(subcommand)]
     action: Action,
+    /// Use this directory for the config instead of the default
+    ///
+    /// Can also be set via `DOD_SHELL_CONFIG_DIR`. Components launched by the CLI use it as well.
+    #[arg(long, global = true)]
+    config_dir: Option<PathBuf>,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -47,15 +61,116 @@ enum Action {
     /// Generate the default config. See [`generate_config`]
     #[command(about = "Generate the default config")]
     GenerateConfig {
-        /// Generate only `layouts.json.schema`
+        /// Generate only `layouts.schema.json` & `config.schema.json`
         #[arg(short, long)]
         schema_only: bool,
         /// Overwrite files if they already exist
         #[arg(short, long)]
         overwrite: bool,
-        /// Where to put the generated files
-        #[arg(default_value=common::CONFIG_PATH.clone().into_os_string())]
-        path: PathBuf,
+        /// Where to put the generated files [default: the config dir]
+        path: Option<PathBuf>,
+    },
+    /// Print the config after merging all layers of `config.toml`. See [`print_config`]
+    #[command(about = "Print the config after merging all layers of config.toml")]
+    PrintConfig {
+        /// The directory containing the config [default: the config dir]
+        path: Option<PathBuf>,
+    },
+    /// Change the log level of a part of the daemon at runtime. See [`log_level`]
+    #[command(about = "Change the log level of a part of the daemon at runtime")]
+    LogLevel {
+        /// The log target (e.g. `daemon::playback`). An empty string targets everything
+        component: String,
+        /// The new log level. If not passed all levels set at runtime are printed
+        level: Option<LogLevel>,
+    },
+    /// Show how long different tasks of the daemon take. See [`stats`]
+    #[command(about = "Show how long different tasks of the daemon take")]
+    Stats,
+    /// Check the config for errors. See [`check_config`]
+    #[command(about = "Check the config for errors")]
+    CheckConfig {
+        /// Skip checks depending on the current system (e.g. if the configured disk exists)
+        #[arg(short, long)]
+        skip_system_checks: bool,
+        /// Only check `config.toml` and `config.d` in the directory, ignoring the system wide and
+        /// per-host layers. A missing `config.toml` isn't an error then.
+        #[arg(long)]
+        only_dir: bool,
+        /// The directory containing the config [default: the config dir]
+        path: Option<PathBuf>,
+    },
+    /// Control the volume of the default audio output. See [`volume`]
+    #[command(about = "Control the volume of the default audio output")]
+    Volume {
+        /// What to change
+        #[command(subcommand)]
+        action: VolumeAction,
+    },
+    /// Control the brightness of the screen or keyboard backlight. See [`brightness`]
+    #[command(about = "Control the brightness of the screen or keyboard backlight")]
+    Brightness {
+        /// Change the keyboard backlight instead of the screen
+        #[arg(short, long, global = true)]
+        keyboard: bool,
+        /// What to change
+        #[command(subcommand)]
+        action: BrightnessAction,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+/// Changes which can be made using [`volume`]
+enum VolumeAction {
+    /// Set the volume
+    Set {
+        /// The new volume in percent (0 - 100)
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+    },
+    /// Raise the volume
+    Up {
+        /// How much to raise the volume by in percent
+        #[arg(default_value_t = 5)]
+        percent: u8,
+    },
+    /// Lower the volume
+    Down {
+        /// How much to lower the volume by in percent
+        #[arg(default_value_t = 5)]
+        percent: u8,
+    },
+    /// Mute or unmute
+    ToggleMute,
+    /// Mute or unmute the default audio input (e.g. the microphone)
+    ToggleMicMute,
+    /// Make an audio output the default
+    SetDefaultSink {
+        /// The name of the `PulseAudio` sink
+        sink: String,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+/// Changes which can be made using [`brightness`]
+enum BrightnessAction {
+    /// Set the brightness
+    Set {
+        /// The new brightness in percent (0 - 100)
+        #[arg(value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+    },
+    /// Raise the brightness
+    Up {
+        /// How much to raise the brightness by in percent
+        #[arg(default_value_t = 5)]
+        percent: u8,
+    },
+    /// Lower the brightness
+    Down {
+        /// How much to lower the brightness by in percent
+        #[arg(default_value_t = 5)]
+        percent: u8,
     },
 }
 
@@ -71,6 +186,40 @@ enum Component {
     Daemon,
     /// The osk component. See`osk` crate.
     Osk,
+    /// The osd component. See `osd` crate.
+    Osd,
+    /// The notifications component. See `notifications` crate.
+    Notifications,
+}
+
+#[derive(Clone, ValueEnum, Debug, Display)]
+/// Log levels which can be set using [`log_level`]
+#[strum(serialize_all = "lowercase")]
+enum LogLevel {
+    /// Disable logging
+    Off,
+    /// See [`log::Level::Error`]
+    Error,
+    /// See [`log::Level::Warn`]
+    Warn,
+    /// See [`log::Level::Info`]
+    Info,
+    /// See [`log::Level::Debug`]
+    Debug,
+    /// See [`log::Level::Trace`]
+    Trace,
+    /// Go back to the level set via `DOD_SHELL_LOG`
+    Default,
+}
+
+/// Helper function to run `f` with a connection to the session bus
+///
+/// Used by all actions talking to the daemon.
+fn with_dbus<T>(f: impl AsyncFnOnce(&zbus::Connection) -> zbus::Result<T>) -> zbus::Result<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(async { f(&zbus::Connection::session().await?).await })
 }
 
 /// Launch a specific component of the shell
@@ -78,13 +227,21 @@ enum Component {
 /// ## Output
 ///
 /// None of it's own, but the output of the launched component is passed.
-fn launch(component: &str) {
-    let cmd = if cfg!(debug_assertions) {
-        Command::new("cargo").args(["run", "-p", component]).spawn()
+fn launch(component: &str, config_dir: Option<&Path>) {
+    let mut cmd = if cfg!(debug_assertions) {
+        let mut cmd = Command::new("cargo");
+        cmd.args(["run", "-p", component]);
+        cmd
     } else {
-        Command::new("dod-shell-".to_string() + component).spawn()
+        Command::new("dod-shell-".to_string() + component)
     };
 
+    if let Some(config_dir) = config_dir {
+        cmd.env(common::CONFIG_DIR_ENV, config_dir);
+    }
+
+    let cmd = cmd.spawn();
+
     if let Err(e) = cmd {
         log::error!("Failed to launch {component}. Error: {e}");
     }
@@ -264,7 +421,8 @@ fn print_installed(component: &str, path: Option<String>) {
 ///
 /// The files will be written to the passed `path`.
 ///
-/// If `schema_only` then only `layouts.schema.json` will be generated and written.
+/// If `schema_only` then only `layouts.schema.json` & `config.schema.json` will be generated and
+/// written.
 ///
 /// ## Output
 ///
@@ -280,6 +438,12 @@ fn generate_config(schema_only: bool, path: &Path, overwrite: bool) {
 
     write_file(schema_path, schema, overwrite);
 
+    let config_schema_path = path.join("config.schema.json");
+    let config_schema = serde_json::to_string_pretty(&schemars::schema_for!(config::Config))
+        .expect("Should never fail to serialize json schema as json.");
+
+    write_file(config_schema_path, config_schema, overwrite);
+
     if schema_only {
         return;
     }
@@ -293,9 +457,12 @@ fn generate_config(schema_only: bool, path: &Path, overwrite: bool) {
     write_file(layouts_path, layouts, overwrite);
 
     let config_path = path.join("config.toml");
-    let config = toml::to_string_pretty(&config::Config::default())
+    let mut config = toml::to_string_pretty(&config::Config::default())
         .expect("Config should always be valid toml.");
 
+    // See: https://taplo.tamasfe.dev/configuration/directives.html#the-schema-directive
+    config.insert_str(0, "#:schema ./config.schema.json\n\n");
+
     write_file(config_path, config, overwrite);
 
     let css_path = path.join("style.scss");
@@ -311,6 +478,236 @@ fn generate_config(schema_only: bool, path: &Path, overwrite: bool) {
     write_file(css_path, css, overwrite);
 }
 
+/// Prints the result of merging all layers of `config.toml` in `path`
+///
+/// See [`daemon::config::layers`]
+///
+/// ## Output
+///
+/// The merged config as toml, preceded by a comment listing all merged files. Fails if the
+/// config can't be loaded.
+fn print_config(path: &Path) -> ExitCode {
+    let merged = layers::read_layers(path, LayerScope::All)
+        .and_then(|layers| Ok((layers::merge_layers(&layers)?, layers)));
+
+    let (merged, layers) = match merged {
+        Ok(merged) => merged,
+        Err(e) => {
+            eprintln!("{e}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("# Merged from:");
+
+    for layer in layers {
+        println!("#   {}", layer.path.to_string_lossy());
+    }
+
+    println!(
+        "\n{}",
+        toml::to_string_pretty(&merged).expect("Should never fail to serialize a toml table.")
+    );
+
+    ExitCode::SUCCESS
+}
+
+/// Sets the log level of `component` in the daemon
+///
+/// See [`daemon::logging::Logging`]
+///
+/// ## Output
+///
+/// If no `level` is passed all levels set at runtime, each on a new line. Otherwise none other
+/// than errors.
+fn log_level(component: &str, level: Option<LogLevel>) -> ExitCode {
+    let result = with_dbus(async |connection| {
+        let proxy = LoggingProxy::new(connection).await?;
+
+        if let Some(level) = level {
+            Ok(proxy.set_log_level(component, &level.to_string()).await?)
+        } else {
+            for (target, level) in proxy.log_levels().await? {
+                println!("{target}: {level}");
+            }
+
+            Ok(())
+        }
+    });
+
+    if let Err(e) = result {
+        log::error!("Failed to set log level: {e}");
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Change the volume of the default audio output using the daemon
+///
+/// See [`daemon::audio::Audio`]
+fn volume(action: VolumeAction) -> ExitCode {
+    let level = |percent: u8| f64::from(percent) / 100.0;
+
+    let result = with_dbus(async |connection| {
+        let proxy = AudioProxy::new(connection).await?;
+
+        match action {
+            VolumeAction::Set { percent } => Ok(proxy.set_volume(level(percent)).await?),
+            VolumeAction::Up { percent } => Ok(proxy.change_volume(level(percent)).await?),
+            VolumeAction::Down { percent } => Ok(proxy.change_volume(-level(percent)).await?),
+            VolumeAction::ToggleMute => Ok(proxy.toggle_mute().await?),
+            VolumeAction::ToggleMicMute => Ok(proxy.toggle_source_mute().await?),
+            VolumeAction::SetDefaultSink { sink } => Ok(proxy.set_default_sink(&sink).await?),
+        }
+    });
+
+    if let Err(e) = result {
+        log::error!("Failed to change volume: {e}");
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Change the brightness of the screen or, if `keyboard`, the keyboard backlight using the daemon
+///
+/// See [`daemon::brightness::Brightness`]
+fn brightness(action: BrightnessAction, keyboard: bool) -> ExitCode {
+    let level = |percent: u8| f64::from(percent) / 100.0;
+
+    let result = with_dbus(async |connection| {
+        let proxy = BrightnessProxy::new(connection).await?;
+
+        match (action, keyboard) {
+            (BrightnessAction::Set { percent }, false) => {
+                Ok(proxy.set_brightness(level(percent)).await?)
+            }
+            (BrightnessAction::Up { percent }, false) => {
+                Ok(proxy.change_brightness(level(percent)).await?)
+            }
+            (BrightnessAction::Down { percent }, false) => {
+                Ok(proxy.change_brightness(-level(percent)).await?)
+            }
+            (BrightnessAction::Set { percent }, true) => {
+                Ok(proxy.set_keyboard_brightness(level(percent)).await?)
+            }
+            (BrightnessAction::Up { percent }, true) => {
+                Ok(proxy.change_keyboard_brightness(level(percent)).await?)
+            }
+            (BrightnessAction::Down { percent }, true) => {
+                Ok(proxy.change_keyboard_brightness(-level(percent)).await?)
+            }
+        }
+    });
+
+    if let Err(e) = result {
+        log::error!("Failed to change brightness: {e}");
+
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Show the timing statistics of the daemon
+///
+/// See [`daemon::diagnostics::Diagnostics`]
+///
+/// ## Output
+///
+/// The output is a table with the following columns:
+/// - Name: The name of the timer
+/// - Count: How often it finished
+/// - p50, p95, Max: The median, 95th percentile & longest duration
+/// - Target: The target duration
+/// - Misses: How often the target was missed
+fn stats() -> ExitCode {
+    let result =
+        with_dbus(async |connection| DiagnosticsProxy::new(connection).await?.diagnostics().await);
+
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(e) => {
+            log::error!("Failed to get stats from daemon: {e}");
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut table = Table::new();
+
+    table.add_row(row![
+        "Name", "Count", "p50", "p95", "Max", "Target", "Misses"
+    ]);
+
+    for timer in stats {
+        let target = if timer.target == 0 {
+            "-".to_string()
+        } else {
+            format_micros(timer.target)
+        };
+
+        table.add_row(row![
+            timer.name,
+            timer.count,
+            format_micros(timer.p50),
+            format_micros(timer.p95),
+            format_micros(timer.max),
+            target,
+            timer.target_misses,
+        ]);
+    }
+
+    table.printstd();
+
+    ExitCode::SUCCESS
+}
+
+/// Helper function to format a duration in microseconds
+fn format_micros(micros: u64) -> String {
+    if micros >= 1000 {
+        format!("{}ms", micros / 1000)
+    } else {
+        format!("{micros}μs")
+    }
+}
+
+/// Checks the config in `path` for errors
+///
+/// See [`config_check::find_errors`] for what is checked.
+///
+/// ## Output
+///
+/// Each error on a new line followed by the number of errors found. Fails if any errors were
+/// found.
+fn check_config(path: &Path, skip_system_checks: bool, only_dir: bool) -> ExitCode {
+    let scope = if only_dir {
+        LayerScope::ConfigDir
+    } else {
+        LayerScope::All
+    };
+
+    let errors = config_check::find_errors(path, !skip_system_checks, scope);
+
+    if errors.is_empty() {
+        println!("No errors found in \"{}\"", path.to_string_lossy());
+
+        return ExitCode::SUCCESS;
+    }
+
+    for error in &errors {
+        eprintln!("{error}");
+    }
+
+    eprintln!("\nFound {} error(s)", errors.len());
+
+    ExitCode::FAILURE
+}
+
 /// Wrapper function around [`fs::write`]
 ///
 /// 1. Logs the returned error
@@ -345,18 +742,45 @@ fn write_file<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C, overwrite: b
     }
 }
 
-fn main() {
+fn main() -> ExitCode {
     logger!();
     let args = Cli::parse();
 
+    if let Some(config_dir) = &args.config_dir
+        && !common::set_config_path(config_dir.clone())
+    {
+        log::error!(
+            "Failed to set config dir to \"{}\"",
+            config_dir.to_string_lossy()
+        );
+
+        return ExitCode::FAILURE;
+    }
+
+    let config_path = |path: Option<PathBuf>| path.unwrap_or_else(|| common::CONFIG_PATH.clone());
+
     match args.action {
-        Action::Launch { component } => launch(&component.to_string()),
+        Action::Launch { component } => {
+            launch(&component.to_string(), args.config_dir.as_deref());
+        }
         Action::List => list(),
         Action::Installed { component } => installed(component),
         Action::GenerateConfig {
             schema_only,
             path,
             overwrite,
-        } => generate_config(schema_only, &path, overwrite),
+        } => generate_config(schema_only, &config_path(path), overwrite),
+        Action::PrintConfig { path } => return print_config(&config_path(path)),
+        Action::LogLevel { component, level } => return log_level(&component, level),
+        Action::Stats => return stats(),
+        Action::CheckConfig {
+            skip_system_checks,
+            only_dir,
+            path,
+        } => return check_config(&config_path(path), skip_system_checks, only_dir),
+        Action::Volume { action } => return volume(action),
+        Action::Brightness { keyboard, action } => return brightness(action, keyboard),
     }
+
+    ExitCode::SUCCESS
 }
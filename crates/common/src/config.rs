@@ -1,10 +1,15 @@
 //! Config related items
 //!
-//! The primary config file is located at <code>[crate::CONFIG_PATH]/config.toml</code>
+//! The primary config file is located at <code>[crate::CONFIG_PATH]/config.toml</code>. It may be
+//! split across multiple files, which the daemon merges. See `daemon::config::layers`.
 //!
 //! Layouts for the osk are located at <code>[crate::CONFIG_PATH]/layouts.json</code>
 pub mod bar;
+pub mod battery;
 pub mod launcher;
+pub mod metrics;
+pub mod notifications;
+pub mod osd;
 
 pub mod layouts;
 
@@ -12,9 +17,22 @@ use serde::{Deserialize, Serialize};
 
 /// Toml format of `config.toml`
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Config {
     /// Config options relating to the bar component
     pub bar: bar::BarConfig,
     /// Config options relating to the launcher component
     pub launcher: launcher::LauncherConfig,
+    /// Config options relating to low and critical battery levels
+    #[serde(default)]
+    pub battery: battery::BatteryConfig,
+    /// Config options relating to system metrics like temperatures
+    #[serde(default)]
+    pub metrics: metrics::MetricsConfig,
+    /// Config options relating to the osd component
+    #[serde(default)]
+    pub osd: osd::OsdConfig,
+    /// Config options relating to desktop notifications
+    #[serde(default)]
+    pub notifications: notifications::NotificationsConfig,
 }
@@ -5,14 +5,19 @@ use serde::{Deserialize, Serialize};
 
 /// See module level documentation
 #[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct BarConfig {
-    /// Path to main disk you want information displayed about
+    /// Mount points of the disks to show (e.g. `/` or `/home`)
     ///
-    /// This should be a path in the format: `/dev/sda1`
-    pub disk: String,
-    /// Path to the device battery
+    /// Removable disks (e.g. USB drives) are shown as well while they are mounted.
     ///
-    /// If this option is not set no information about the device's battery will be shown
+    /// Replaces the deprecated `disk` option (a device like `/dev/sda1`), which is still accepted
+    /// and mapped to the mount points of that device.
+    #[serde(default = "disks_default")]
+    pub disks: Vec<PathBuf>,
+    /// Name of the battery to show (e.g. `BAT0` or `/sys/class/power_supply/BAT0`)
+    ///
+    /// If this option is not set the first battery powering the system will be shown
     pub battery: Option<PathBuf>,
     /// Show if caps lock is enabled or not
     #[serde(default)]
@@ -33,6 +38,12 @@ pub struct BarConfig {
     pub date_time_playing_format: String,
 }
 
+/// Default for [`BarConfig::disks`]
+#[must_use]
+pub fn disks_default() -> Vec<PathBuf> {
+    vec![PathBuf::from("/")]
+}
+
 /// Default for [`BarConfig::date_time_format`]
 #[must_use]
 pub fn date_time_default() -> String {
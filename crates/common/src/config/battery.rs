@@ -0,0 +1,47 @@
+//! Config options relating to what happens when the battery runs low
+use serde::{Deserialize, Serialize};
+
+/// See module level documentation
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BatteryConfig {
+    /// Charge (0.0 - 1.0) at or below which the battery is considered low
+    #[serde(default = "low_default")]
+    pub low: f64,
+    /// Charge (0.0 - 1.0) at or below which the battery is considered critical
+    #[serde(default = "critical_default")]
+    pub critical: f64,
+    /// Send a desktop notification when the battery becomes low or critical while discharging
+    #[serde(default = "notify_default")]
+    pub notify: bool,
+    /// Command run (using `sh -c`) when the battery becomes critical while discharging
+    ///
+    /// E.g. `systemctl suspend`
+    pub critical_command: Option<String>,
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            low: low_default(),
+            critical: critical_default(),
+            notify: notify_default(),
+            critical_command: None,
+        }
+    }
+}
+
+/// Default for [`BatteryConfig::low`]
+const fn low_default() -> f64 {
+    0.3
+}
+
+/// Default for [`BatteryConfig::critical`]
+const fn critical_default() -> f64 {
+    0.1
+}
+
+/// Default for [`BatteryConfig::notify`]
+const fn notify_default() -> bool {
+    true
+}
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 /// See module level documentation
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LauncherConfig {
     /// Max height for the list of results
     #[serde(default = "results_height_default")]
@@ -17,6 +18,7 @@ pub struct LauncherConfig {
 
 /// Config relating to the `Launch` mode of the launcher
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LaunchModeConfig {
     /// All apps the launcher will show
     pub apps: Vec<LaunchApp>,
@@ -24,6 +26,7 @@ pub struct LaunchModeConfig {
 
 /// Format for each app, that can be launched
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct LaunchApp {
     /// Name of the app
     ///
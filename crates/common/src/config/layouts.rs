@@ -72,6 +72,14 @@ impl Layouts {
     pub fn layouts(&self) -> &[Layout] {
         &self.layouts
     }
+
+    /// Returns the name of the default layout
+    ///
+    /// This doesn't have to be the name of an existing layout. See [`Self::get_default_layout`].
+    #[must_use]
+    pub fn default_layout_name(&self) -> &str {
+        &self.default_layout
+    }
 }
 
 /// Format for an individual layout
@@ -0,0 +1,37 @@
+//! Config options relating to the system metrics gathered by the daemon (e.g. temperatures)
+use serde::{Deserialize, Serialize};
+
+/// See module level documentation
+///
+/// Sensors are either the name of a hwmon device (e.g. `coretemp`), optionally followed by `/` and
+/// the label of one of its temperatures (e.g. `coretemp/Package id 0`), or the type of a thermal
+/// zone (e.g. `x86_pkg_temp`). If a sensor isn't set, common sensors are tried.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MetricsConfig {
+    /// Sensor for the temperature of the CPU
+    pub cpu_sensor: Option<String>,
+    /// Sensor for the temperature of the GPU
+    pub gpu_sensor: Option<String>,
+    /// Sensor for the temperature of the `NVMe` drive
+    pub nvme_sensor: Option<String>,
+    /// How many samples of the history are kept. One sample is taken every second
+    #[serde(default = "history_length_default")]
+    pub history_length: usize,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            cpu_sensor: None,
+            gpu_sensor: None,
+            nvme_sensor: None,
+            history_length: history_length_default(),
+        }
+    }
+}
+
+/// Default for [`MetricsConfig::history_length`]
+const fn history_length_default() -> usize {
+    60
+}
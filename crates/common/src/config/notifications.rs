@@ -0,0 +1,44 @@
+//! Config options relating to desktop notifications and the notifications component of the shell
+use serde::{Deserialize, Serialize};
+
+/// See module level documentation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct NotificationsConfig {
+    /// How long a popup is shown in milliseconds, if the sender doesn't set a timeout
+    ///
+    /// Critical notifications are shown until they are dismissed.
+    #[serde(default = "timeout_default")]
+    pub timeout: u64,
+    /// How many notifications are kept in the history
+    #[serde(default = "history_default")]
+    pub history: usize,
+    /// How many popups are shown at once
+    #[serde(default = "max_popups_default")]
+    pub max_popups: usize,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            timeout: timeout_default(),
+            history: history_default(),
+            max_popups: max_popups_default(),
+        }
+    }
+}
+
+/// Default for [`NotificationsConfig::timeout`]
+const fn timeout_default() -> u64 {
+    5000
+}
+
+/// Default for [`NotificationsConfig::history`]
+const fn history_default() -> usize {
+    100
+}
+
+/// Default for [`NotificationsConfig::max_popups`]
+const fn max_popups_default() -> usize {
+    5
+}
@@ -0,0 +1,55 @@
+//! Config options relating to the osd (on-screen display) component of the shell
+use serde::{Deserialize, Serialize};
+
+/// See module level documentation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OsdConfig {
+    /// How long the osd is shown after the last change in milliseconds
+    #[serde(default = "timeout_default")]
+    pub timeout: u64,
+    /// Where on the screen the osd is shown
+    #[serde(default)]
+    pub position: OsdPosition,
+    /// Distance to the edge of the screen in pixels
+    ///
+    /// Ignored if [`Self::position`] is [`OsdPosition::Center`].
+    #[serde(default = "margin_default")]
+    pub margin: i32,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            timeout: timeout_default(),
+            position: OsdPosition::default(),
+            margin: margin_default(),
+        }
+    }
+}
+
+/// Positions the osd can be shown at
+///
+/// The osd is always centered horizontally.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum OsdPosition {
+    /// At the top edge of the screen
+    Top,
+    /// In the center of the screen
+    Center,
+    /// At the bottom edge of the screen
+    #[default]
+    Bottom,
+}
+
+/// Default for [`OsdConfig::timeout`]
+const fn timeout_default() -> u64 {
+    1500
+}
+
+/// Default for [`OsdConfig::margin`]
+const fn margin_default() -> i32 {
+    100
+}
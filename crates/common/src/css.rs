@@ -42,6 +42,7 @@ pub enum Class {
     Active,
     Disabled,
     Muted,
+    Limited,
 
     // Bar
     BarMainWindow,
@@ -49,12 +50,14 @@ pub enum Class {
 
     Battery,
     BatteryLow,
+    BatteryCritical,
     HardwareInfo,
     Cpu,
     Ram,
     InternetNameRevealer,
 
     InternetIcon,
+    Vpn,
     BluetoothIcon,
     CapsLockIcon,
     NumLockIcon,
@@ -71,6 +74,11 @@ pub enum Class {
     TimePlayingProgressbar,
 
     OskButton,
+    NotificationsButton,
+
+    Tray,
+    TrayItem,
+    TrayItemAttention,
 
     // Launcher
     LauncherMainWindow,
@@ -114,6 +122,28 @@ pub enum Class {
 
     OskCloseButton,
     OskLockButton,
+
+    // Osd
+    OsdMainWindow,
+    OsdBox,
+    OsdIcon,
+    OsdLabel,
+    OsdProgressbar,
+
+    // Notifications
+    NotificationsMainWindow,
+    NotificationsBox,
+    NotificationPopup,
+    NotificationHeader,
+    NotificationIcon,
+    NotificationAppName,
+    NotificationSummary,
+    NotificationBody,
+    NotificationActions,
+    NotificationAction,
+    NotificationCloseButton,
+    UrgencyLow,
+    UrgencyCritical,
 }
 
 /// A list of [`Class`]es
@@ -1,19 +1,43 @@
 //! Common functionality shared among all components of the shell
-use std::{io::Write, path::PathBuf, sync::LazyLock};
-
-use env_logger::fmt::style::{AnsiColor, Style};
+use std::{
+    env,
+    path::PathBuf,
+    sync::{LazyLock, OnceLock},
+};
 
 pub mod config;
 pub mod css;
 pub mod err;
+pub mod logging;
+pub mod metrics;
 pub mod types;
 
 pub use config::{Config, layouts::Layouts};
+pub use logging::logger;
+
+/// Environment variable overriding [`CONFIG_PATH`]
+pub const CONFIG_DIR_ENV: &str = "DOD_SHELL_CONFIG_DIR";
+
+/// Value set by [`set_config_path`]
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
 
 /// The path to the config dir
 ///
-/// Changes depending on weather the build is for release or debug.
+/// In order of precedence this is:
+///
+/// 1. The path passed to [`set_config_path`] (e.g. via `--config-dir`)
+/// 2. The value of [`CONFIG_DIR_ENV`]
+/// 3. Depending on weather the build is for release or debug `$XDG_CONFIG_HOME/dod-shell` or
+///    `test`
 pub static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return path.clone();
+    }
+
+    if let Some(path) = env::var_os(CONFIG_DIR_ENV).filter(|p| !p.is_empty()) {
+        return PathBuf::from(path);
+    }
+
     if cfg!(debug_assertions) {
         return PathBuf::from("test");
     }
@@ -23,45 +47,17 @@ pub static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
         .join("dod-shell")
 });
 
-/// Calls [`function@logger`] with `CARGO_PKG_NAME`
-#[macro_export]
-macro_rules! logger {
-    () => {
-        $crate::logger(env!("CARGO_PKG_NAME"));
-    };
-}
-
-/// Init the logger for a crate
+/// Override [`CONFIG_PATH`] with `path`
 ///
-/// Used to ensure consistent formatting across the project
+/// This has to be called before [`CONFIG_PATH`] is first used.
 ///
-/// Do not use this function directly use [`macro@logger`].
-pub fn logger(target: &'static str) {
-    env_logger::Builder::new()
-        .filter_level(if cfg!(debug_assertions) {
-            log::LevelFilter::Trace
-        } else {
-            log::LevelFilter::Info
-        })
-        .parse_env("DOD_SHELL_LOG")
-        .default_format()
-        .format(move |formatter, record| {
-            let time = formatter.timestamp();
-            let level = record.level();
-
-            let style = Style::new().fg_color(match level {
-                log::Level::Error => Some(AnsiColor::Red.into()),
-                log::Level::Warn => Some(AnsiColor::Yellow.into()),
-                log::Level::Info => Some(AnsiColor::Cyan.into()),
-                log::Level::Debug | log::Level::Trace => None,
-            });
-
-            writeln!(
-                formatter,
-                "{time} [{target}] {style}{level}{style:#} {}",
-                record.args()
-            )
-        })
-        .write_style(env_logger::WriteStyle::Always)
-        .init();
+/// Returns if [`CONFIG_PATH`] is now `path`.
+#[must_use]
+pub fn set_config_path(path: PathBuf) -> bool {
+    CONFIG_PATH_OVERRIDE.set(path).is_ok() && Some(&*CONFIG_PATH) == CONFIG_PATH_OVERRIDE.get()
 }
+
+/// The path to the system wide config dir
+///
+/// Only `config.toml` is read from here. Values in [`CONFIG_PATH`] take precedence.
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/dod-shell";
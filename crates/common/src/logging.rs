@@ -0,0 +1,349 @@
+//! Logging shared by all components of the shell
+//!
+//! Logs are filtered using [`LOG_ENV`], but the level of individual targets (e.g.
+//! `daemon::playback`) can also be changed at runtime. See [`set_level`]
+//!
+//! Optionally logs are also written to a rotating log file. See [`LOG_FILE_ENV`]
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{OnceLock, PoisonError, RwLock},
+};
+
+use env_logger::{
+    Target, WriteStyle,
+    fmt::style::{AnsiColor, Style},
+};
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Environment variable used to filter logs
+///
+/// See: <https://docs.rs/env_logger/latest/env_logger/#enabling-logging>
+pub const LOG_ENV: &str = "DOD_SHELL_LOG";
+
+/// Environment variable enabling the log file
+///
+/// If set to a non-empty value logs are also written to
+/// `$XDG_STATE_HOME/dod-shell/<component>.log`.
+pub const LOG_FILE_ENV: &str = "DOD_SHELL_LOG_FILE";
+
+/// Size in bytes after which the log file is rotated
+const MAX_LOG_FILE_SIZE: u64 = 5 * 1024 * 1024;
+
+/// Number of rotated log files kept around (`<component>.log.1` being the newest)
+const ROTATED_LOG_FILES: u32 = 3;
+
+/// The logger set by [`logger`]
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Calls [`function@logger`] with `CARGO_PKG_NAME`
+#[macro_export]
+macro_rules! logger {
+    () => {
+        $crate::logger(env!("CARGO_PKG_NAME"));
+    };
+}
+
+/// Init the logger for a component
+///
+/// Used to ensure consistent formatting across the project
+///
+/// Do not use this function directly use [`macro@logger`].
+///
+/// # Panics
+///
+/// If a logger has already been set.
+pub fn logger(component: &'static str) {
+    let default_level = if cfg!(debug_assertions) {
+        LevelFilter::Trace
+    } else {
+        LevelFilter::Info
+    };
+
+    let logger = Logger {
+        filter: env_logger::Builder::new()
+            .filter_level(default_level)
+            .parse_env(LOG_ENV)
+            .build(),
+        stderr: output_builder(component)
+            .write_style(WriteStyle::Always)
+            .build(),
+        file: log_file(component).map(|file| {
+            output_builder(component)
+                .target(Target::Pipe(Box::new(file)))
+                .write_style(WriteStyle::Never)
+                .build()
+        }),
+        levels: RwLock::default(),
+    };
+
+    log::set_logger(LOGGER.get_or_init(|| logger)).expect("Logger should only be set once.");
+
+    update_max_level();
+}
+
+/// Set the log level of `target` and all targets below it at runtime
+///
+/// E.g. `daemon::osk` also includes `daemon::osk::wayland`. An empty `target` sets the level for
+/// all targets. The most specific matching target takes precedence.
+///
+/// Passing [`None`] as the `level` removes a level previously set for `target`.
+///
+/// Returns `false` if the logger hasn't been set up using [`function@logger`].
+pub fn set_level(target: &str, level: Option<LevelFilter>) -> bool {
+    let Some(logger) = LOGGER.get() else {
+        return false;
+    };
+
+    {
+        let mut levels = logger
+            .levels
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        levels.retain(|(t, _)| t != target);
+
+        if let Some(level) = level {
+            levels.push((target.to_string(), level));
+        }
+    }
+
+    update_max_level();
+
+    true
+}
+
+/// All log levels set using [`set_level`]
+#[must_use]
+pub fn levels() -> Vec<(String, LevelFilter)> {
+    LOGGER.get().map_or_else(Vec::new, |logger| {
+        logger
+            .levels
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    })
+}
+
+/// Helper function to update [`log::max_level`] after the levels changed
+fn update_max_level() {
+    if let Some(logger) = LOGGER.get() {
+        let levels = logger.levels.read().unwrap_or_else(PoisonError::into_inner);
+
+        log::set_max_level(
+            levels
+                .iter()
+                .map(|(_, level)| *level)
+                .fold(logger.filter.filter(), Ord::max),
+        );
+    }
+}
+
+/// Builder for the [`env_logger::Logger`]s actually writing the logs
+///
+/// These don't do any filtering themselves, that is done by [`Logger`].
+///
+/// Every line is prefixed with `component`. Debug and trace lines also contain the target (e.g.
+/// `daemon::playback`), which can be passed to [`set_level`].
+fn output_builder(component: &'static str) -> env_logger::Builder {
+    let mut builder = env_logger::Builder::new();
+
+    builder
+        .filter_level(LevelFilter::Trace)
+        .default_format()
+        .format(move |formatter, record| {
+            let time = formatter.timestamp();
+            let level = record.level();
+
+            let style = Style::new().fg_color(match level {
+                log::Level::Error => Some(AnsiColor::Red.into()),
+                log::Level::Warn => Some(AnsiColor::Yellow.into()),
+                log::Level::Info => Some(AnsiColor::Cyan.into()),
+                log::Level::Debug | log::Level::Trace => None,
+            });
+
+            if level >= log::Level::Debug {
+                return writeln!(
+                    formatter,
+                    "{time} [{component}] {style}{level}{style:#} ({}) {}",
+                    record.target(),
+                    record.args()
+                );
+            }
+
+            writeln!(
+                formatter,
+                "{time} [{component}] {style}{level}{style:#} {}",
+                record.args()
+            )
+        });
+
+    builder
+}
+
+/// Helper function to open the log file for `component`, if it is enabled
+///
+/// See [`LOG_FILE_ENV`]
+fn log_file(component: &str) -> Option<LogFile> {
+    std::env::var_os(LOG_FILE_ENV).filter(|v| !v.is_empty())?;
+
+    let path = dirs::state_dir()?
+        .join("dod-shell")
+        .join(format!("{component}.log"));
+
+    LogFile::open(path)
+        .inspect_err(|e| eprintln!("Failed to open log file: {e}"))
+        .ok()
+}
+
+/// Logger combining the filtering of [`LOG_ENV`] with the levels set at runtime
+struct Logger {
+    /// Decides which records are logged, if no level was set for their target
+    filter: env_logger::Logger,
+    /// Writes records to stderr
+    stderr: env_logger::Logger,
+    /// Writes records to the log file
+    file: Option<env_logger::Logger>,
+    /// Levels set at runtime. See [`set_level`]
+    levels: RwLock<Vec<(String, LevelFilter)>>,
+}
+
+impl Logger {
+    /// The level set at runtime for the most specific match of `target`
+    fn level_for(&self, target: &str) -> Option<LevelFilter> {
+        self.levels
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|(t, _)| target_matches(t, target))
+            .max_by_key(|(t, _)| t.len())
+            .map(|(_, level)| *level)
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.level_for(metadata.target()).map_or_else(
+            || self.filter.enabled(metadata),
+            |level| metadata.level() <= level,
+        )
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        self.stderr.log(record);
+
+        if let Some(file) = &self.file {
+            file.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+
+        if let Some(file) = &self.file {
+            file.flush();
+        }
+    }
+}
+
+/// If `filter` (e.g. `daemon::osk`) includes `target` (e.g. `daemon::osk::wayland`)
+fn target_matches(filter: &str, target: &str) -> bool {
+    filter.is_empty()
+        || target
+            .strip_prefix(filter)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+/// Log file, which is rotated once it exceeds [`MAX_LOG_FILE_SIZE`]
+struct LogFile {
+    /// Path to the current log file
+    path: PathBuf,
+    /// The current log file
+    file: File,
+    /// Size of the current log file
+    size: u64,
+}
+
+impl LogFile {
+    /// Open the log file at `path`, appending to it if it exists
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = Self::open_file(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self { path, file, size })
+    }
+
+    /// Helper function to open `path` for appending
+    fn open_file(path: &Path) -> io::Result<File> {
+        File::options().create(true).append(true).open(path)
+    }
+
+    /// Path of the `n`th rotated log file
+    fn rotated_path(&self, n: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{n}"));
+
+        path.into()
+    }
+
+    /// Move the current log file to `<component>.log.1`, shifting all older ones
+    fn rotate(&mut self) -> io::Result<()> {
+        for n in (1..ROTATED_LOG_FILES).rev() {
+            let from = self.rotated_path(n);
+
+            if from.exists() {
+                fs::rename(from, self.rotated_path(n + 1))?;
+            }
+        }
+
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = Self::open_file(&self.path)?;
+        self.size = 0;
+
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+
+        if self.size > 0 && self.size.saturating_add(len) > MAX_LOG_FILE_SIZE {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += u64::try_from(written).unwrap_or(u64::MAX);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::target_matches;
+
+    #[test]
+    fn target_matching() {
+        assert!(target_matches("", "daemon::playback"));
+        assert!(target_matches("daemon", "daemon::playback"));
+        assert!(target_matches("daemon::osk", "daemon::osk::wayland"));
+        assert!(target_matches("daemon::osk", "daemon::osk"));
+        assert!(!target_matches("daemon::osk", "daemon::osk_state"));
+        assert!(!target_matches("daemon::playback", "daemon"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Timing metrics collected from [`crate::types::Timer`]s
+//!
+//! Every finished timer is recorded under its name, so how long something takes can be watched
+//! over time instead of only showing up as individual log messages. See [`timer_stats`]
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{LazyLock, Mutex, PoisonError},
+    time::Duration,
+};
+
+/// How many of the most recent samples of each timer are used for percentiles
+const MAX_SAMPLES: usize = 1024;
+
+/// All timings recorded so far, keyed by the name of the timer
+///
+/// Timer names are literals, so recording a run doesn't allocate.
+static TIMINGS: LazyLock<Mutex<HashMap<&'static str, Timings>>> = LazyLock::new(Mutex::default);
+
+/// Statistics about all runs of a timer with the same name
+///
+/// All durations are in microseconds.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct TimerStats {
+    /// Name of the timer
+    pub name: String,
+    /// How often the timer has finished
+    pub count: u64,
+    /// Median duration
+    pub p50: u64,
+    /// 95th percentile of the durations
+    pub p95: u64,
+    /// Longest duration
+    pub max: u64,
+    /// The target duration of the timer
+    ///
+    /// Is 0 if the timer has no target.
+    pub target: u64,
+    /// How often the target was missed
+    pub target_misses: u64,
+}
+
+/// Recorded timings of a single timer
+#[derive(Debug, Default)]
+struct Timings {
+    /// The most recent durations (at most [`MAX_SAMPLES`])
+    samples: VecDeque<Duration>,
+    /// See [`TimerStats::count`]
+    count: u64,
+    /// See [`TimerStats::max`]
+    max: Duration,
+    /// The most recent target
+    target: Option<Duration>,
+    /// See [`TimerStats::target_misses`]
+    target_misses: u64,
+}
+
+/// Record a single run of the timer `name`
+pub fn record(name: &'static str, elapsed: Duration, target: Option<Duration>) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let timing = timings.entry(name).or_default();
+
+    if timing.samples.len() == MAX_SAMPLES {
+        timing.samples.pop_front();
+    }
+
+    timing.samples.push_back(elapsed);
+    timing.count += 1;
+    timing.max = timing.max.max(elapsed);
+    timing.target = target;
+
+    if target.is_some_and(|target| target < elapsed) {
+        timing.target_misses += 1;
+    }
+}
+
+/// Statistics for all timers recorded so far, sorted by name
+#[must_use]
+pub fn timer_stats() -> Vec<TimerStats> {
+    let timings = TIMINGS.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut stats: Vec<TimerStats> = timings
+        .iter()
+        .map(|(name, timing)| {
+            let mut samples: Vec<Duration> = timing.samples.iter().copied().collect();
+            samples.sort_unstable();
+
+            TimerStats {
+                name: (*name).to_string(),
+                count: timing.count,
+                p50: micros(percentile(&samples, 50)),
+                p95: micros(percentile(&samples, 95)),
+                max: micros(timing.max),
+                target: timing.target.map_or(0, micros),
+                target_misses: timing.target_misses,
+            }
+        })
+        .collect();
+
+    stats.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+    stats
+}
+
+/// Helper function to get the `pct`th percentile of the already sorted `samples`
+fn percentile(samples: &[Duration], pct: usize) -> Duration {
+    if samples.is_empty() {
+        return Duration::ZERO;
+    }
+
+    samples[(samples.len() - 1) * pct / 100]
+}
+
+/// Helper function to convert a [`Duration`] to microseconds for [`TimerStats`]
+fn micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::percentile;
+
+    #[test]
+    fn percentiles() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+
+        assert_eq!(percentile(&samples, 50), Duration::from_millis(50));
+        assert_eq!(percentile(&samples, 95), Duration::from_millis(95));
+        assert_eq!(percentile(&samples, 100), Duration::from_millis(100));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+}
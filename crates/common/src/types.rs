@@ -3,6 +3,7 @@ use std::time::{Duration, Instant};
 use std::{cell::UnsafeCell, fmt::Debug, fmt::Display, ops::Deref, sync::Once};
 
 use log::Level;
+use serde::{Deserialize, Serialize};
 
 /// Type representing a percentage
 ///
@@ -17,6 +18,8 @@ use log::Level;
     zvariant::Value,
     zvariant::OwnedValue,
     zvariant::Type,
+    Serialize,
+    Deserialize,
 )]
 pub struct Percentage {
     /// The value
@@ -213,14 +216,14 @@ impl<T> DeferredInit<T> {
 /// A Timer implemented using RAII
 ///
 /// The timer is started once it is created and finished when dropped, where it will log the time
-/// taken using the log crate.
-pub struct Timer<'a> {
+/// taken using the log crate and record it in [`crate::metrics`].
+pub struct Timer {
     /// When the timer was created / started
     start: Instant,
     /// Name of the timer
     ///
-    /// Used for logging when finished
-    name: &'a str,
+    /// Used for logging when finished and as the key in [`crate::metrics`]
+    name: &'static str,
     /// At what level to log when the timer is finished / dropped
     level: Level,
     /// An optional target for how long the timer should take
@@ -229,18 +232,18 @@ pub struct Timer<'a> {
     target: Option<Duration>,
 }
 
-impl<'a> Timer<'a> {
+impl Timer {
     /// Creates a new timer
     ///
     /// By default the log level is [`log::Level::Trace`]
     #[must_use]
-    pub fn new(name: &'a str, target: Option<Duration>) -> Self {
+    pub fn new(name: &'static str, target: Option<Duration>) -> Self {
         Self::new_with_level(name, Level::Trace, target)
     }
 
     /// Creates a new timer with a custom log level
     #[must_use]
-    pub fn new_with_level(name: &'a str, level: Level, target: Option<Duration>) -> Self {
+    pub fn new_with_level(name: &'static str, level: Level, target: Option<Duration>) -> Self {
         Self {
             start: Instant::now(),
             name,
@@ -250,7 +253,7 @@ impl<'a> Timer<'a> {
     }
 }
 
-impl Drop for Timer<'_> {
+impl Drop for Timer {
     fn drop(&mut self) {
         let elapsed = self.start.elapsed();
 
@@ -260,6 +263,8 @@ impl Drop for Timer<'_> {
             format!("{}μs", elapsed.as_micros())
         };
 
+        crate::metrics::record(self.name, elapsed, self.target);
+
         let target_missed = self.target.is_some_and(|duration| duration < elapsed);
 
         let level = if target_missed {
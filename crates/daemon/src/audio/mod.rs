@@ -0,0 +1,96 @@
+//! This module contains items relating to controlling the audio of the system
+//!
+//! This works by talking to `PulseAudio` (or `PipeWire` through `pipewire-pulse`).
+//!
+//! The main type is [``Audio``]
+use tokio::sync::watch;
+use zbus::{fdo, interface};
+
+use crate::{system_state::AudioData, util::finite};
+
+use pulse_ref::{Control, PulseRef};
+
+mod pulse_ref;
+
+/// Allows changing the volume and the default audio output
+///
+/// The current audio setup is part of [``crate::system_state::SystemStateData``]. All volumes
+/// are clamped to 0% - 100%.
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``AudioProxy``]
+#[derive(Debug, Default)]
+pub struct Audio {
+    /// Connection to `PulseAudio`
+    pulse: PulseRef,
+}
+
+impl Audio {
+    /// Get a receiver for the current audio setup
+    #[must_use]
+    pub fn audio(&self) -> watch::Receiver<AudioData> {
+        self.pulse.audio()
+    }
+
+    /// Helper function to pass `control` to [``PulseRef::control``]
+    async fn control(&self, control: Control) -> fdo::Result<()> {
+        self.pulse
+            .control(control)
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Audio",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Audio {
+    /// Set the volume of the default audio output to `level` (0.0 - 1.0)
+    async fn set_volume(&self, level: f64) -> fdo::Result<()> {
+        self.control(Control::SetVolume(finite(level, "volume")?))
+            .await
+    }
+
+    /// Change the volume of the default audio output by `delta` (-1.0 - 1.0)
+    async fn change_volume(&self, delta: f64) -> fdo::Result<()> {
+        self.control(Control::ChangeVolume(finite(delta, "volume")?))
+            .await
+    }
+
+    /// Mute the default audio output if it isn't muted and unmute it otherwise
+    async fn toggle_mute(&self) -> fdo::Result<()> {
+        self.control(Control::ToggleMute).await
+    }
+
+    /// Make the audio output (aka. sink) called `name` the default
+    async fn set_default_sink(&self, name: &str) -> fdo::Result<()> {
+        self.control(Control::SetDefaultSink(name.to_string()))
+            .await
+    }
+
+    /// Set the volume of the application audio stream (aka. sink input) with `index` to `level`
+    /// (0.0 - 1.0)
+    async fn set_sink_input_volume(&self, index: u32, level: f64) -> fdo::Result<()> {
+        self.control(Control::SetSinkInputVolume(index, finite(level, "volume")?))
+            .await
+    }
+
+    /// Mute the application audio stream (aka. sink input) with `index` if it isn't muted and
+    /// unmute it otherwise
+    async fn toggle_sink_input_mute(&self, index: u32) -> fdo::Result<()> {
+        self.control(Control::ToggleSinkInputMute(index)).await
+    }
+
+    /// Mute the default audio input (e.g. the microphone) if it isn't muted and unmute it
+    /// otherwise
+    async fn toggle_source_mute(&self) -> fdo::Result<()> {
+        self.control(Control::ToggleSourceMute).await
+    }
+}
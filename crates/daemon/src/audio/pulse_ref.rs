@@ -0,0 +1,768 @@
+//! See [`PulseRef`]
+use anyhow::{Result, anyhow, bail};
+use libpulse_binding::{
+    callbacks::ListResult,
+    context::{
+        Context, FlagSet, State,
+        introspect::{ServerInfo, SinkInfo, SinkInputInfo, SourceInfo},
+        subscribe::{Facility, InterestMaskSet},
+    },
+    mainloop::threaded::Mainloop,
+    proplist::{Proplist, properties},
+    volume::{ChannelVolumes, Volume},
+};
+use std::{
+    sync::mpsc,
+    thread::{JoinHandle, spawn},
+    time::{Duration, Instant},
+};
+use tokio::sync::{oneshot, watch};
+
+use common::types::Percentage;
+
+use crate::system_state::{AudioData, AudioDevice, AudioStream};
+
+/// Name `PulseAudio` resolves to the current default sink
+const DEFAULT_SINK: &str = "@DEFAULT_SINK@";
+
+/// Name `PulseAudio` resolves to the current default source
+const DEFAULT_SOURCE: &str = "@DEFAULT_SOURCE@";
+
+/// The highest volume which can be set using [`Control`]
+///
+/// Going above 100% distorts the audio, so this is also the limit.
+const MAX_VOLUME: f64 = 1.0;
+
+/// How long to wait for `PulseAudio` to answer a query
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long to wait before the first attempt to reconnect to `PulseAudio`
+///
+/// The wait is doubled after every failed attempt, up to [`MAX_RECONNECT_DELAY`].
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest wait between attempts to reconnect to `PulseAudio`
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// `PulseAudio` tracker and controller.
+///
+/// Spawns a background thread that owns the `!Send` [`Mainloop`]. The thread subscribes to
+/// events of sinks, sources, sink inputs and the server, so changes (e.g. to the volume or to
+/// which sink is the default) are pushed to [`Self::audio`] as soon as they happen.
+///
+/// If the connection is lost (e.g. because `pipewire-pulse` restarted) an empty [`AudioData`] is
+/// pushed and the thread reconnects, waiting longer after every failed attempt.
+#[derive(Debug)]
+pub struct PulseRef {
+    /// Used to send messages to background thread
+    sender: mpsc::Sender<Command>,
+    /// Used to stop background thread on [`Drop`]
+    join_handle: Option<JoinHandle<()>>,
+    /// The current audio setup, as sent by the background thread
+    audio: watch::Receiver<AudioData>,
+}
+
+/// Changes which can be made to the audio setup using [`PulseRef::control`]
+#[derive(Debug, Clone)]
+pub enum Control {
+    /// Set the volume of the default sink to the given level (0.0 - 1.0)
+    ///
+    /// The loudest channel is set to the level and the others are scaled along, to keep the
+    /// balance. The same goes for all other volume changes.
+    SetVolume(f64),
+    /// Change the volume of the default sink by the given amount (-1.0 - 1.0)
+    ChangeVolume(f64),
+    /// Mute the default sink if it isn't muted and unmute it otherwise
+    ToggleMute,
+    /// Make the sink with the given name the default sink
+    SetDefaultSink(String),
+    /// Set the volume of the sink input with the given index to the given level (0.0 - 1.0)
+    SetSinkInputVolume(u32, f64),
+    /// Toggle the mute of the sink input with the given index
+    ToggleSinkInputMute(u32),
+    /// Toggle the mute of the default source (e.g. the microphone)
+    ToggleSourceMute,
+}
+
+/// Commands sent to the background thread
+enum Command {
+    /// Query the current audio setup and send it to [`PulseRef::audio`]
+    ///
+    /// Sent by the subscription whenever something changed.
+    Refresh,
+    /// See [`PulseRef::control`]
+    Control {
+        /// What to change
+        control: Control,
+        /// Used to send back if the change succeeded
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Check if the connection was lost
+    ///
+    /// Sent by the context whenever the state of the connection changed.
+    StateChanged,
+    /// Used to stop the background thread before dropping
+    Shutdown,
+}
+
+impl Default for PulseRef {
+    fn default() -> Self {
+        let (tx, rx) = mpsc::channel::<Command>();
+        let (audio_tx, audio_rx) = watch::channel(AudioData::default());
+
+        let connection_tx = tx.clone();
+
+        let handle = spawn(move || {
+            let mut delay = MIN_RECONNECT_DELAY;
+
+            loop {
+                match Connection::new(&connection_tx) {
+                    Ok(mut connection) => {
+                        delay = MIN_RECONNECT_DELAY;
+
+                        if connection.handle_commands(&rx, &audio_tx) {
+                            return;
+                        }
+
+                        log::warn!("Lost connection to PulseAudio");
+
+                        // Nothing can be controlled until reconnected
+                        audio_tx.send_replace(AudioData::default());
+                    }
+                    Err(e) => log::error!("{e}"),
+                }
+
+                log::info!("Reconnecting to PulseAudio in {}s", delay.as_secs());
+
+                if !wait(&rx, delay) {
+                    return;
+                }
+
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        });
+
+        Self {
+            sender: tx,
+            join_handle: Some(handle),
+            audio: audio_rx,
+        }
+    }
+}
+
+impl PulseRef {
+    /// Get a receiver for the current audio setup
+    ///
+    /// The sender is dropped if the background thread stopped.
+    pub fn audio(&self) -> watch::Receiver<AudioData> {
+        self.audio.clone()
+    }
+
+    /// Change the audio setup
+    ///
+    /// Volumes are clamped to 0% - 100%.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the background thread stopped or `PulseAudio` failed
+    /// to make the change.
+    pub async fn control(&self, control: Control) -> Result<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender
+            .send(Command::Control {
+                control,
+                respond_to: tx,
+            })
+            .map_err(|_| anyhow!("PulseAudio thread died"))?;
+
+        rx.await.map_err(|_| anyhow!("PulseAudio thread died"))?
+    }
+}
+
+impl Drop for PulseRef {
+    fn drop(&mut self) {
+        let _ = self.sender.send(Command::Shutdown);
+        if let Some(handle) = self.join_handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A connection to `PulseAudio`, which is closed on [`Drop`]
+///
+/// The context is declared first, so it is dropped before the mainloop it uses.
+struct Connection {
+    /// The connection itself
+    context: Context,
+    /// Runs the callbacks of [`Self::context`] on its own thread
+    mainloop: Mainloop,
+}
+
+impl Connection {
+    /// Connect to `PulseAudio` and subscribe to all events (see [`subscribe`])
+    ///
+    /// Whenever the state of the connection changes [`Command::StateChanged`] is sent using `tx`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `PulseAudio` can't be reached.
+    fn new(tx: &mpsc::Sender<Command>) -> Result<Self> {
+        let mut mainloop =
+            Mainloop::new().ok_or_else(|| anyhow!("Failed to create PulseAudio mainloop"))?;
+        let mut context = Context::new(&mainloop, "dod-shell-daemon")
+            .ok_or_else(|| anyhow!("Failed to create PulseAudio context"))?;
+
+        context
+            .connect(None, FlagSet::NOFLAGS, None)
+            .map_err(|e| anyhow!("Failed to connect to PulseAudio: {e:?}"))?;
+        mainloop
+            .start()
+            .map_err(|e| anyhow!("Failed to start PulseAudio mainloop: {e:?}"))?;
+
+        let mut connection = Self { context, mainloop };
+
+        connection.wait_ready()?;
+
+        connection.mainloop.lock();
+
+        let state_tx = tx.clone();
+        connection
+            .context
+            .set_state_callback(Some(Box::new(move || {
+                let _ = state_tx.send(Command::StateChanged);
+            })));
+
+        // The connection could have been lost while not locked
+        let ready = connection.ready();
+        if ready {
+            subscribe(&mut connection.context, tx.clone());
+        }
+
+        connection.mainloop.unlock();
+
+        if !ready {
+            bail!("PulseAudio connection failed");
+        }
+
+        // Get the initial state
+        let _ = tx.send(Command::Refresh);
+
+        Ok(connection)
+    }
+
+    /// Helper function to wait until the connection is ready
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the connection failed or took too long.
+    fn wait_ready(&self) -> Result<()> {
+        for _ in 0..200 {
+            match self.context.get_state() {
+                State::Ready => return Ok(()),
+                State::Failed | State::Terminated => bail!("PulseAudio connection failed"),
+                _ => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+
+        bail!("Timed out waiting for PulseAudio connection")
+    }
+
+    /// If the connection can be used
+    fn ready(&self) -> bool {
+        self.context.get_state() == State::Ready
+    }
+
+    /// Handle all commands received using `rx` and send the resulting audio setup using `audio_tx`
+    ///
+    /// Returns `true` once [`Command::Shutdown`] was received and `false` if the connection was
+    /// lost (e.g. because `PulseAudio` restarted).
+    fn handle_commands(
+        &mut self,
+        rx: &mpsc::Receiver<Command>,
+        audio_tx: &watch::Sender<AudioData>,
+    ) -> bool {
+        while let Ok(cmd) = rx.recv() {
+            // A single change often causes multiple events, so all pending commands are
+            // handled at once and the state is only queried once
+            let mut refresh = false;
+
+            for cmd in std::iter::once(cmd).chain(rx.try_iter()) {
+                match cmd {
+                    Command::Refresh => refresh = true,
+                    Command::StateChanged => {}
+                    Command::Control {
+                        control,
+                        respond_to,
+                    } => apply_control(self, control, respond_to),
+                    Command::Shutdown => return true,
+                }
+            }
+
+            if matches!(self.context.get_state(), State::Failed | State::Terminated) {
+                return false;
+            }
+
+            if refresh {
+                match query_audio(self) {
+                    Ok(audio) => {
+                        audio_tx.send_replace(audio);
+                    }
+                    Err(e) => log::error!("Failed to query the audio setup: {e}"),
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.mainloop.lock();
+        self.context.disconnect();
+        self.mainloop.unlock();
+        self.mainloop.stop();
+    }
+}
+
+/// Helper function to wait `delay` before reconnecting to `PulseAudio`
+///
+/// Changes requested in the meantime fail, since there is no connection to make them.
+///
+/// Returns `false` once [`Command::Shutdown`] was received.
+fn wait(rx: &mpsc::Receiver<Command>, delay: Duration) -> bool {
+    let deadline = Instant::now() + delay;
+
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(Command::Control { respond_to, .. }) => {
+                let _ = respond_to.send(Err(anyhow!("Not connected to PulseAudio")));
+            }
+            Ok(Command::Refresh | Command::StateChanged) => {}
+            Ok(Command::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => return false,
+            Err(mpsc::RecvTimeoutError::Timeout) => return true,
+        }
+    }
+}
+
+/// Helper function to subscribe to all events which could change the [`AudioData`]
+///
+/// For every event a [`Command::Refresh`] is sent using `tx`.
+///
+/// The [`Mainloop`] has to be locked while calling this.
+fn subscribe(context: &mut Context, tx: mpsc::Sender<Command>) {
+    context.set_subscribe_callback(Some(Box::new(move |facility, _operation, _index| {
+        // Server events include changes of the default sink & source
+        if matches!(
+            facility,
+            Some(Facility::Sink | Facility::Source | Facility::SinkInput | Facility::Server)
+        ) {
+            let _ = tx.send(Command::Refresh);
+        }
+    })));
+
+    context.subscribe(
+        InterestMaskSet::SINK
+            | InterestMaskSet::SOURCE
+            | InterestMaskSet::SINK_INPUT
+            | InterestMaskSet::SERVER,
+        |success| {
+            if !success {
+                log::error!("Failed to subscribe to PulseAudio events");
+            }
+        },
+    );
+}
+
+/// Helper function to wait for the results of a query
+///
+/// `query` starts the query, sending every result using the given sender followed by
+/// [`ListResult::End`] once all results were sent (see [`list_item`]).
+///
+/// # Errors
+///
+/// This function will return an error if the query failed, timed out or there is no connection.
+fn collect<T>(
+    connection: &mut Connection,
+    what: &str,
+    query: impl FnOnce(&Context, mpsc::Sender<ListResult<T>>),
+) -> Result<Vec<T>> {
+    let (tx, rx) = mpsc::channel();
+
+    connection.mainloop.lock();
+
+    // Queries can't be started without a connection
+    let ready = connection.ready();
+    if ready {
+        query(&connection.context, tx);
+    }
+
+    connection.mainloop.unlock();
+
+    if !ready {
+        bail!("PulseAudio {what} query failed, not connected");
+    }
+
+    let mut items = Vec::new();
+
+    loop {
+        match rx.recv_timeout(QUERY_TIMEOUT) {
+            Ok(ListResult::Item(item)) => items.push(item),
+            Ok(ListResult::End) | Err(mpsc::RecvTimeoutError::Disconnected) => return Ok(items),
+            Ok(ListResult::Error) => bail!("PulseAudio {what} query failed"),
+            Err(mpsc::RecvTimeoutError::Timeout) => bail!("PulseAudio {what} query timed out"),
+        }
+    }
+}
+
+/// Helper function to convert the result of a list query to what is sent in [`collect`]
+fn list_item<I, T>(result: ListResult<I>, convert: impl FnOnce(I) -> T) -> ListResult<T> {
+    match result {
+        ListResult::Item(item) => ListResult::Item(convert(item)),
+        ListResult::End => ListResult::End,
+        ListResult::Error => ListResult::Error,
+    }
+}
+
+/// Helper function to query the complete audio setup
+///
+/// # Errors
+///
+/// This function will return an error if any part of the setup couldn't be queried.
+fn query_audio(connection: &mut Connection) -> Result<AudioData> {
+    let (default_sink, default_source) = collect(connection, "server", |context, tx| {
+        context
+            .introspect()
+            .get_server_info(move |info: &ServerInfo<'_>| {
+                let _ = tx.send(ListResult::Item((
+                    info.default_sink_name.as_deref().map(str::to_string),
+                    info.default_source_name.as_deref().map(str::to_string),
+                )));
+                let _ = tx.send(ListResult::End);
+            });
+    })?
+    .pop()
+    .ok_or_else(|| anyhow!("PulseAudio sent no server info"))?;
+
+    let mut sinks = collect(connection, "sink", |context, tx| {
+        context
+            .introspect()
+            .get_sink_info_list(move |res: ListResult<&SinkInfo<'_>>| {
+                let _ = tx.send(list_item(res, |info| {
+                    device(
+                        info.index,
+                        info.name.as_deref(),
+                        info.description.as_deref(),
+                        &info.proplist,
+                        info.volume,
+                        info.mute,
+                    )
+                }));
+            });
+    })?;
+
+    let mut sources: Vec<AudioDevice> = collect(connection, "source", |context, tx| {
+        context
+            .introspect()
+            .get_source_info_list(move |res: ListResult<&SourceInfo<'_>>| {
+                // Monitors just mirror the output of a sink, so they aren't of interest
+                let _ = tx.send(list_item(res, |info| {
+                    info.monitor_of_sink.is_none().then(|| {
+                        device(
+                            info.index,
+                            info.name.as_deref(),
+                            info.description.as_deref(),
+                            &info.proplist,
+                            info.volume,
+                            info.mute,
+                        )
+                    })
+                }));
+            });
+    })?
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let sink_inputs = collect(connection, "sink input", |context, tx| {
+        context.introspect().get_sink_input_info_list(
+            move |res: ListResult<&SinkInputInfo<'_>>| {
+                let _ = tx.send(list_item(res, stream));
+            },
+        );
+    })?;
+
+    for sink in &mut sinks {
+        sink.default = default_sink.as_ref() == Some(&sink.name);
+    }
+
+    for source in &mut sources {
+        source.default = default_source.as_ref() == Some(&source.name);
+    }
+
+    Ok(AudioData {
+        sinks,
+        sources,
+        sink_inputs,
+    })
+}
+
+/// Helper function to create an [`AudioDevice`] from the info of a sink or source
+fn device(
+    index: u32,
+    name: Option<&str>,
+    description: Option<&str>,
+    proplist: &Proplist,
+    volume: ChannelVolumes,
+    muted: bool,
+) -> AudioDevice {
+    let name = name.unwrap_or_default().to_string();
+
+    AudioDevice {
+        index,
+        description: description.map_or_else(|| name.clone(), str::to_string),
+        name,
+        icon_name: proplist
+            .get_str(properties::DEVICE_ICON_NAME)
+            .unwrap_or_default(),
+        volume: Percentage::from(volume_level(volume.avg())),
+        muted,
+        default: false,
+        bluetooth: proplist
+            .get_str(properties::DEVICE_BUS)
+            .is_some_and(|bus| bus == "bluetooth"),
+    }
+}
+
+/// Helper function to create an [`AudioStream`] from the info of a sink input
+fn stream(info: &SinkInputInfo<'_>) -> AudioStream {
+    AudioStream {
+        index: info.index,
+        name: info
+            .proplist
+            .get_str(properties::APPLICATION_NAME)
+            .or_else(|| info.name.as_deref().map(str::to_string))
+            .unwrap_or_default(),
+        icon_name: info
+            .proplist
+            .get_str(properties::APPLICATION_ICON_NAME)
+            .unwrap_or_default(),
+        volume: Percentage::from(volume_level(info.volume.avg())),
+        muted: info.mute,
+        sink: info.sink,
+    }
+}
+
+/// Helper function to get the volumes and mute status of the default sink
+///
+/// # Errors
+///
+/// This function will return an error if there is no default sink or it couldn't be queried.
+fn default_sink(connection: &mut Connection) -> Result<(ChannelVolumes, bool)> {
+    collect(connection, "sink", |context, tx| {
+        context.introspect().get_sink_info_by_name(
+            DEFAULT_SINK,
+            move |res: ListResult<&SinkInfo<'_>>| {
+                let _ = tx.send(list_item(res, |info| (info.volume, info.mute)));
+            },
+        );
+    })?
+    .pop()
+    .ok_or_else(|| anyhow!("There is no default sink"))
+}
+
+/// Helper function to get the volumes and mute status of the sink input with `index`
+///
+/// # Errors
+///
+/// This function will return an error if there is no such sink input or it couldn't be queried.
+fn sink_input(connection: &mut Connection, index: u32) -> Result<(ChannelVolumes, bool)> {
+    collect(connection, "sink input", |context, tx| {
+        context.introspect().get_sink_input_info(
+            index,
+            move |res: ListResult<&SinkInputInfo<'_>>| {
+                let _ = tx.send(list_item(res, |info| (info.volume, info.mute)));
+            },
+        );
+    })?
+    .pop()
+    .ok_or_else(|| anyhow!("There is no sink input {index}"))
+}
+
+/// Helper function to get the mute status of the default source
+///
+/// # Errors
+///
+/// This function will return an error if there is no default source or it couldn't be queried.
+fn default_source_muted(connection: &mut Connection) -> Result<bool> {
+    collect(connection, "source", |context, tx| {
+        context.introspect().get_source_info_by_name(
+            DEFAULT_SOURCE,
+            move |res: ListResult<&SourceInfo<'_>>| {
+                let _ = tx.send(list_item(res, |info| info.mute));
+            },
+        );
+    })?
+    .pop()
+    .ok_or_else(|| anyhow!("There is no default source"))
+}
+
+/// Helper function to make the change described by `control`
+///
+/// The result is sent using `respond_to`, once `PulseAudio` made the change.
+fn apply_control(
+    connection: &mut Connection,
+    control: Control,
+    respond_to: oneshot::Sender<Result<()>>,
+) {
+    // The current state is queried first, since the mainloop can't be locked while doing so
+    let current = match control {
+        Control::SetVolume(_) | Control::ChangeVolume(_) | Control::ToggleMute => {
+            default_sink(connection)
+        }
+        Control::SetSinkInputVolume(index, _) | Control::ToggleSinkInputMute(index) => {
+            sink_input(connection, index)
+        }
+        Control::ToggleSourceMute => {
+            default_source_muted(connection).map(|muted| (ChannelVolumes::default(), muted))
+        }
+        Control::SetDefaultSink(_) => Ok((ChannelVolumes::default(), false)),
+    };
+
+    let (volumes, muted) = match current
+        .and_then(|(volumes, muted)| Ok((change_volumes(volumes, &control)?, muted)))
+    {
+        Ok(current) => current,
+        Err(e) => {
+            let _ = respond_to.send(Err(e));
+            return;
+        }
+    };
+
+    connection.mainloop.lock();
+
+    // Changes can't be made without a connection
+    if !connection.ready() {
+        connection.mainloop.unlock();
+
+        let _ = respond_to.send(Err(anyhow!("Not connected to PulseAudio")));
+        return;
+    }
+
+    let respond: Box<dyn FnMut(bool)> = Box::new(responder(respond_to));
+
+    let context = &mut connection.context;
+    let mut introspect = context.introspect();
+
+    match control {
+        Control::SetVolume(_) | Control::ChangeVolume(_) => {
+            introspect.set_sink_volume_by_name(DEFAULT_SINK, &volumes, Some(respond));
+        }
+        Control::ToggleMute => {
+            introspect.set_sink_mute_by_name(DEFAULT_SINK, !muted, Some(respond));
+        }
+        Control::SetDefaultSink(name) => {
+            context.set_default_sink(&name, respond);
+        }
+        Control::SetSinkInputVolume(index, _) => {
+            introspect.set_sink_input_volume(index, &volumes, Some(respond));
+        }
+        Control::ToggleSinkInputMute(index) => {
+            introspect.set_sink_input_mute(index, !muted, Some(respond));
+        }
+        Control::ToggleSourceMute => {
+            introspect.set_source_mute_by_name(DEFAULT_SOURCE, !muted, Some(respond));
+        }
+    }
+
+    connection.mainloop.unlock();
+}
+
+/// Helper function to apply the volume change of `control` to the current `volumes`
+///
+/// Each channel is scaled instead of being set to the new volume, so the balance between the
+/// channels is kept. Controls which don't change the volume return `volumes` as is.
+///
+/// # Errors
+///
+/// This function will return an error if `volumes` are invalid.
+fn change_volumes(mut volumes: ChannelVolumes, control: &Control) -> Result<ChannelVolumes> {
+    let changed = match *control {
+        Control::SetVolume(level) | Control::SetSinkInputVolume(_, level) => {
+            volumes.scale(volume_from_level(level))
+        }
+        Control::ChangeVolume(delta) if delta >= 0.0 => {
+            // Volumes set above the limit elsewhere must not be lowered by an increase
+            let limit = volumes.max().max(volume_from_level(MAX_VOLUME));
+
+            volumes.inc_clamp(volume_from_level(delta), limit)
+        }
+        Control::ChangeVolume(delta) => volumes.decrease(volume_from_level(-delta)),
+        _ => return Ok(volumes),
+    };
+
+    changed
+        .copied()
+        .ok_or_else(|| anyhow!("Invalid volume {volumes:?}"))
+}
+
+/// Helper function to create the callback for a `PulseAudio` operation, which sends its result
+/// using `respond_to`
+fn responder(respond_to: oneshot::Sender<Result<()>>) -> impl FnMut(bool) + 'static {
+    let mut respond_to = Some(respond_to);
+
+    move |success| {
+        if let Some(respond_to) = respond_to.take() {
+            let _ = respond_to.send(if success {
+                Ok(())
+            } else {
+                Err(anyhow!("PulseAudio rejected the change"))
+            });
+        }
+    }
+}
+
+/// Helper function to convert a [`Volume`] to a level where 1.0 is 100%
+fn volume_level(volume: Volume) -> f64 {
+    f64::from(volume.0) / f64::from(Volume::NORMAL.0)
+}
+
+/// Helper function to convert a level to a [`Volume`], clamping it to [`MAX_VOLUME`]
+#[allow(
+    clippy::cast_sign_loss,
+    reason = "The level is clamped, so it is never negative."
+)]
+fn volume_from_level(level: f64) -> Volume {
+    let level = level.clamp(0.0, MAX_VOLUME);
+
+    Volume((level * f64::from(Volume::NORMAL.0)).round() as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use libpulse_binding::volume::{ChannelVolumes, Volume};
+
+    use super::{Control, change_volumes};
+
+    #[test]
+    fn increase_is_clamped() {
+        let mut volumes = ChannelVolumes::default();
+        volumes.set(2, Volume(Volume::NORMAL.0 * 98 / 100));
+
+        let changed = change_volumes(volumes, &Control::ChangeVolume(0.05)).unwrap();
+
+        assert_eq!(changed.max(), Volume::NORMAL);
+    }
+
+    #[test]
+    fn increase_never_lowers_volume_above_limit() {
+        let loud = Volume(Volume::NORMAL.0 * 6 / 5);
+        let mut volumes = ChannelVolumes::default();
+        volumes.set(2, loud);
+
+        let changed = change_volumes(volumes, &Control::ChangeVolume(0.05)).unwrap();
+
+        assert_eq!(changed.max(), loud);
+    }
+}
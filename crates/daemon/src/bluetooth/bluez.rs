@@ -0,0 +1,144 @@
+//! Proxies and helpers for the `BlueZ` dbus API
+//!
+//! Shared by [``super::Bluetooth``] and the bluetooth collector of
+//! [``crate::system_state::SystemState``].
+//!
+//! See: <https://git.kernel.org/pub/scm/bluetooth/bluez.git/tree/doc>
+use std::collections::HashMap;
+
+use zbus::{
+    fdo::ObjectManagerProxy,
+    proxy,
+    zvariant::{OwnedObjectPath, OwnedValue},
+};
+
+/// Dbus service name for `BlueZ`
+pub const BLUEZ_SERVICE_NAME: &str = "org.bluez";
+
+/// Prefix of the paths of all adapters and devices
+const PATH_PREFIX: &str = "/org/bluez";
+
+/// Interface implemented by all adapters
+pub const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+
+/// Interface implemented by all devices
+pub const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+/// Interface implemented by devices which report their battery charge
+pub const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+
+/// Proxy for a Bluetooth adapter
+///
+/// See: <https://git.kernel.org/pub/scm/bluetooth/bluez.git/tree/doc/org.bluez.Adapter.rst>
+#[proxy(
+    interface = "org.bluez.Adapter1",
+    default_service = "org.bluez",
+    gen_blocking = false
+)]
+pub trait Adapter {
+    /// Start searching for new devices
+    fn start_discovery(&self) -> zbus::Result<()>;
+
+    /// Stop searching for new devices
+    fn stop_discovery(&self) -> zbus::Result<()>;
+
+    /// If the adapter is searching for new devices
+    #[zbus(property)]
+    fn discovering(&self) -> zbus::Result<bool>;
+
+    /// Power the adapter on or off
+    #[zbus(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+}
+
+/// Proxy for a Bluetooth device
+///
+/// See: <https://git.kernel.org/pub/scm/bluetooth/bluez.git/tree/doc/org.bluez.Device.rst>
+#[proxy(
+    interface = "org.bluez.Device1",
+    default_service = "org.bluez",
+    gen_blocking = false
+)]
+pub trait Device {
+    /// Connect all profiles the device supports
+    fn connect(&self) -> zbus::Result<()>;
+
+    /// Disconnect all profiles of the device
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    /// Pair with the device
+    fn pair(&self) -> zbus::Result<()>;
+
+    /// Allow (or disallow) the device to connect without confirmation
+    #[zbus(property)]
+    fn set_trusted(&self, value: bool) -> zbus::Result<()>;
+}
+
+/// Create a proxy for the object manager of `BlueZ`, which knows all adapters and devices
+///
+/// `connection` has to be a connection to the system bus.
+pub async fn object_manager(
+    connection: &zbus::Connection,
+) -> zbus::Result<ObjectManagerProxy<'static>> {
+    ObjectManagerProxy::builder(connection)
+        .destination(BLUEZ_SERVICE_NAME)?
+        .path("/")?
+        .build()
+        .await
+}
+
+/// Helper function to get the property `name` from `properties`
+///
+/// Returns [``None``] if the property doesn't exist or has a different type.
+pub fn property<T>(properties: &HashMap<String, OwnedValue>, name: &str) -> Option<T>
+where
+    T: TryFrom<OwnedValue>,
+{
+    properties.get(name)?.try_clone().ok()?.try_into().ok()
+}
+
+/// Helper function to get the path of the adapter `name` (e.g. `/org/bluez/hci0` for `hci0`)
+///
+/// This is the inverse of [``object_name``].
+pub fn adapter_path(name: &str) -> String {
+    format!("{PATH_PREFIX}/{name}")
+}
+
+/// Helper function to get the path of the device with `address` known to the adapter `adapter`
+///
+/// `BlueZ` names devices after their address, e.g. `/org/bluez/hci0/dev_00_11_22_33_44_55`.
+pub fn device_path(adapter: &str, address: &str) -> String {
+    format!(
+        "{}/dev_{}",
+        adapter_path(adapter),
+        address.replace(':', "_")
+    )
+}
+
+/// Helper function to get the last part of `path`, which `BlueZ` uses as the name of adapters
+/// (e.g. `hci0` for `/org/bluez/hci0`)
+pub fn object_name(path: &OwnedObjectPath) -> String {
+    path.as_str()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use zbus::zvariant::OwnedObjectPath;
+
+    use super::{adapter_path, device_path, object_name};
+
+    #[test]
+    fn builds_paths() {
+        let adapter = OwnedObjectPath::try_from(adapter_path("hci0")).unwrap();
+
+        assert_eq!(object_name(&adapter), "hci0");
+        assert_eq!(
+            device_path("hci0", "00:11:22:AA:BB:CC"),
+            "/org/bluez/hci0/dev_00_11_22_AA_BB_CC"
+        );
+    }
+}
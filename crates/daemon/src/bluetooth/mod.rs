@@ -0,0 +1,199 @@
+//! This module contains items relating to controlling Bluetooth
+//!
+//! This works by talking to `BlueZ`.
+//!
+//! The main type is [``Bluetooth``]
+use zbus::{ObjectServer, fdo, interface, object_server::SignalEmitter, proxy::CacheProperties};
+
+use bluez::{AdapterProxy, DeviceProxy};
+
+use crate::system_state::SystemState;
+
+pub(crate) mod bluez;
+
+/// Allows powering Bluetooth adapters on and off and connecting to devices
+///
+/// The current adapters and devices are part of
+/// [``crate::system_state::SystemStateData::bluetooth``]. Devices are identified by their address.
+/// Their paths are taken from there as well, so [``SystemState``] has to be served at the same
+/// path.
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``BluetoothProxy``]
+#[derive(Debug)]
+pub struct Bluetooth {
+    /// Connection to the system bus
+    connection: zbus::Connection,
+}
+
+impl Bluetooth {
+    /// Create a new [``Bluetooth``]
+    ///
+    /// `connection` has to be a connection to the system bus.
+    #[must_use]
+    pub const fn new(connection: zbus::Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Helper function to get the proxies of all adapters
+    ///
+    /// `server` and `emitter` are those of the method call, to find the [``SystemState``].
+    async fn adapters(
+        &self,
+        server: &ObjectServer,
+        emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<Vec<AdapterProxy<'static>>> {
+        let paths: Vec<String> = server
+            .interface::<_, SystemState>(emitter.path())
+            .await?
+            .get()
+            .await
+            .bluetooth()
+            .adapters
+            .iter()
+            .map(|adapter| bluez::adapter_path(&adapter.name))
+            .collect();
+
+        let mut adapters = Vec::new();
+
+        for path in paths {
+            adapters.push(
+                AdapterProxy::builder(&self.connection)
+                    .path(path)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?,
+            );
+        }
+
+        Ok(adapters)
+    }
+
+    /// Helper function to get the proxy of the device with `address`
+    ///
+    /// See [``Self::adapters``]
+    async fn device(
+        &self,
+        address: &str,
+        server: &ObjectServer,
+        emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<DeviceProxy<'static>> {
+        let path = server
+            .interface::<_, SystemState>(emitter.path())
+            .await?
+            .get()
+            .await
+            .bluetooth()
+            .devices
+            .iter()
+            .find(|device| device.address.eq_ignore_ascii_case(address))
+            .map(|device| bluez::device_path(&device.adapter, &device.address))
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("There is no device {address}")))?;
+
+        Ok(DeviceProxy::builder(&self.connection)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?)
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Bluetooth",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Bluetooth {
+    /// Power all adapters on or off
+    async fn set_powered(
+        &self,
+        powered: bool,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        for adapter in self.adapters(server, &emitter).await? {
+            adapter.set_powered(powered).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Start or stop searching for new devices on all adapters
+    ///
+    /// Devices found are added to [``crate::system_state::BluetoothData::devices``].
+    async fn set_discovering(
+        &self,
+        discovering: bool,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        for adapter in self.adapters(server, &emitter).await? {
+            if adapter.discovering().await? == discovering {
+                continue;
+            }
+
+            if discovering {
+                adapter.start_discovery().await?;
+            } else {
+                adapter.stop_discovery().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connect to the device with `address`
+    ///
+    /// Returns once the device is connected, which can take a few seconds.
+    async fn connect(
+        &self,
+        address: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.device(address, server, &emitter)
+            .await?
+            .connect()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Disconnect from the device with `address`
+    async fn disconnect(
+        &self,
+        address: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.device(address, server, &emitter)
+            .await?
+            .disconnect()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Pair with the device with `address` and trust it, so it can connect on its own later
+    ///
+    /// Only works for devices which don't require a PIN or confirmation, since there is no agent
+    /// to ask the user.
+    async fn pair(
+        &self,
+        address: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let device = self.device(address, server, &emitter).await?;
+
+        device.pair().await?;
+        device.set_trusted(true).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+//! Proxy for the session object of `systemd-logind`
+//!
+//! See: <https://www.freedesktop.org/software/systemd/man/latest/org.freedesktop.login1.html>
+use zbus::proxy;
+
+/// Proxy for the session the daemon runs in
+///
+/// `auto` is the session of the caller, or the graphical session of the user if the caller isn't
+/// part of a session (e.g. when running as a user service).
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto",
+    gen_blocking = false
+)]
+pub trait Session {
+    /// Set the brightness of the device `name` in `subsystem` (`backlight` or `leds`)
+    ///
+    /// Unlike writing to sysfs directly, this doesn't require root.
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
@@ -0,0 +1,180 @@
+//! This module contains items relating to controlling the brightness of backlights
+//!
+//! The brightness is read from sysfs and set through `systemd-logind`, so no root is needed.
+//!
+//! The main type is [``Brightness``]
+use zbus::{fdo, interface};
+
+use crate::{system_state::BacklightData, util::finite};
+
+use logind::SessionProxy;
+
+mod logind;
+pub(crate) mod sysfs;
+
+/// Allows changing the brightness of the screen and keyboard backlights
+///
+/// The current brightness is part of [``crate::system_state::SystemStateData::brightness``].
+/// All levels are clamped to 0% - 100%, but the screen backlight is never turned off completely.
+/// Changes always move the brightness by at least one step, since keyboard backlights often only
+/// have a few.
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``BrightnessProxy``]
+#[derive(Debug)]
+pub struct Brightness {
+    /// Connection to the system bus
+    connection: zbus::Connection,
+}
+
+/// The backlights which can be controlled
+#[derive(Debug, Clone, Copy)]
+enum Device {
+    /// See [``sysfs::backlight``]
+    Screen,
+    /// See [``sysfs::keyboard_backlight``]
+    Keyboard,
+}
+
+impl Device {
+    /// Helper function to read the current state of the backlight
+    async fn read(self) -> fdo::Result<BacklightData> {
+        let backlight = match self {
+            Self::Screen => sysfs::backlight().await,
+            Self::Keyboard => sysfs::keyboard_backlight().await,
+        };
+
+        backlight.ok_or_else(|| fdo::Error::Failed(format!("There is no {self} backlight")))
+    }
+
+    /// The sysfs subsystem of the backlight
+    const fn subsystem(self) -> &'static str {
+        match self {
+            Self::Screen => "backlight",
+            Self::Keyboard => "leds",
+        }
+    }
+
+    /// The lowest brightness the backlight can be set to
+    const fn min_brightness(self) -> u32 {
+        match self {
+            Self::Screen => 1,
+            Self::Keyboard => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Screen => write!(f, "screen"),
+            Self::Keyboard => write!(f, "keyboard"),
+        }
+    }
+}
+
+impl Brightness {
+    /// Create a new [``Brightness``]
+    ///
+    /// `connection` has to be a connection to the system bus.
+    #[must_use]
+    pub const fn new(connection: zbus::Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Helper function to set the brightness of `device` to the level returned by `level`
+    ///
+    /// `level` gets passed the current level of the device.
+    async fn set(&self, device: Device, level: impl FnOnce(f64) -> f64) -> fdo::Result<()> {
+        let backlight = device.read().await?;
+        let level = finite(level(*backlight.level), "brightness")?;
+
+        let brightness = brightness(&backlight, level).max(device.min_brightness());
+
+        SessionProxy::new(&self.connection)
+            .await?
+            .set_brightness(device.subsystem(), &backlight.name, brightness)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Brightness",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Brightness {
+    /// Set the brightness of the screen to `level` (0.0 - 1.0)
+    async fn set_brightness(&self, level: f64) -> fdo::Result<()> {
+        self.set(Device::Screen, |_| level).await
+    }
+
+    /// Change the brightness of the screen by `delta` (-1.0 - 1.0)
+    async fn change_brightness(&self, delta: f64) -> fdo::Result<()> {
+        self.set(Device::Screen, |current| current + delta).await
+    }
+
+    /// Set the brightness of the keyboard backlight to `level` (0.0 - 1.0)
+    async fn set_keyboard_brightness(&self, level: f64) -> fdo::Result<()> {
+        self.set(Device::Keyboard, |_| level).await
+    }
+
+    /// Change the brightness of the keyboard backlight by `delta` (-1.0 - 1.0)
+    async fn change_keyboard_brightness(&self, delta: f64) -> fdo::Result<()> {
+        self.set(Device::Keyboard, |current| current + delta).await
+    }
+}
+
+/// Helper function to convert `level` into the brightness of `backlight`
+///
+/// If `level` differs from the current level, but would round to the current brightness, the
+/// brightness is moved by one step, so small changes aren't lost on backlights with few steps.
+#[allow(
+    clippy::cast_sign_loss,
+    reason = "The value is clamped to 0 - max_brightness before casting."
+)]
+fn brightness(backlight: &BacklightData, level: f64) -> u32 {
+    let max = f64::from(backlight.max_brightness);
+    let brightness = (level.clamp(0.0, 1.0) * max).round() as u32;
+
+    if brightness != backlight.brightness {
+        return brightness;
+    }
+
+    if level > *backlight.level {
+        brightness.saturating_add(1).min(backlight.max_brightness)
+    } else if level < *backlight.level {
+        brightness.saturating_sub(1)
+    } else {
+        brightness
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::brightness;
+    use crate::system_state::BacklightData;
+
+    #[test]
+    fn converts_brightness() {
+        let backlight = BacklightData {
+            name: "tpacpi::kbd_backlight".to_string(),
+            brightness: 1,
+            max_brightness: 2,
+            level: 0.5.into(),
+        };
+
+        assert_eq!(brightness(&backlight, 1.0), 2);
+        assert_eq!(brightness(&backlight, -0.5), 0);
+        assert_eq!(brightness(&backlight, 0.55), 2);
+        assert_eq!(brightness(&backlight, 0.45), 0);
+        assert_eq!(brightness(&backlight, 0.5), 1);
+    }
+}
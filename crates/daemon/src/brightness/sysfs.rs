@@ -0,0 +1,144 @@
+//! Reading backlights from [``BACKLIGHT_PATH``] and [``LEDS_PATH``]
+//!
+//! Shared by [``super::Brightness``] and the brightness collector of
+//! [``crate::system_state::SystemState``].
+//!
+//! See: <https://www.kernel.org/doc/html/latest/gpu/backlight.html>
+use std::path::{Path, PathBuf};
+
+use crate::{
+    system_state::BacklightData,
+    util::{entries, read_value},
+};
+
+/// Directory containing all screen backlights
+pub const BACKLIGHT_PATH: &str = "/sys/class/backlight/";
+
+/// Directory containing all LEDs, including keyboard backlights
+pub const LEDS_PATH: &str = "/sys/class/leds/";
+
+/// Suffix of the names of keyboard backlights in [``LEDS_PATH``]
+const KEYBOARD_BACKLIGHT_SUFFIX: &str = "::kbd_backlight";
+
+/// Get the main screen backlight
+pub async fn backlight() -> Option<BacklightData> {
+    find_backlight(Path::new(BACKLIGHT_PATH)).await
+}
+
+/// Get the main keyboard backlight
+pub async fn keyboard_backlight() -> Option<BacklightData> {
+    find_keyboard_backlight(Path::new(LEDS_PATH)).await
+}
+
+/// Find the screen backlight in `dir` which should be used
+///
+/// If there are multiple, firmware interfaces are preferred over platform specific ones, which
+/// are preferred over raw ones, as recommended by the kernel.
+async fn find_backlight(dir: &Path) -> Option<BacklightData> {
+    let mut best: Option<(u8, PathBuf)> = None;
+
+    for path in entries(dir).await {
+        let priority = match read_value::<String>(&path, "type").await.as_deref() {
+            Some("firmware") => 0,
+            Some("platform") => 1,
+            Some("raw") => 2,
+            _ => 3,
+        };
+
+        if best.as_ref().is_none_or(|(best, _)| priority < *best) {
+            best = Some((priority, path));
+        }
+    }
+
+    read_backlight(&best?.1).await
+}
+
+/// Find the first keyboard backlight in `dir`
+async fn find_keyboard_backlight(dir: &Path) -> Option<BacklightData> {
+    for path in entries(dir).await {
+        if path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().ends_with(KEYBOARD_BACKLIGHT_SUFFIX))
+        {
+            return read_backlight(&path).await;
+        }
+    }
+
+    None
+}
+
+/// Get information about the backlight at `path`
+///
+/// Returns [``None``] if its brightness can't be read.
+async fn read_backlight(path: &Path) -> Option<BacklightData> {
+    let brightness: u32 = read_value(path, "brightness").await?;
+    let max_brightness: u32 = read_value(path, "max_brightness").await?;
+
+    Some(BacklightData {
+        name: path.file_name()?.to_string_lossy().to_string(),
+        brightness,
+        max_brightness,
+        level: if max_brightness == 0 {
+            0.0.into()
+        } else {
+            (f64::from(brightness) / f64::from(max_brightness)).into()
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_backlight, find_keyboard_backlight};
+    use crate::util::sysfs_dir;
+
+    #[tokio::test]
+    async fn finds_backlights() {
+        let dir = sysfs_dir(&[
+            ("backlight/intel_backlight/type", "raw\n"),
+            ("backlight/intel_backlight/brightness", "19200\n"),
+            ("backlight/intel_backlight/max_brightness", "96000\n"),
+            ("backlight/acpi_video0/type", "firmware\n"),
+            ("backlight/acpi_video0/brightness", "5\n"),
+            ("backlight/acpi_video0/max_brightness", "10\n"),
+            ("leds/input3::capslock/brightness", "0\n"),
+            ("leds/tpacpi::kbd_backlight/brightness", "1\n"),
+            ("leds/tpacpi::kbd_backlight/max_brightness", "2\n"),
+        ]);
+
+        let backlight = find_backlight(&dir.path().join("backlight")).await.unwrap();
+
+        assert_eq!(backlight.name, "acpi_video0");
+        assert_eq!(backlight.brightness, 5);
+        assert_eq!(backlight.level.to_string(), "50%");
+
+        let keyboard = find_keyboard_backlight(&dir.path().join("leds"))
+            .await
+            .unwrap();
+
+        assert_eq!(keyboard.name, "tpacpi::kbd_backlight");
+        assert_eq!(keyboard.max_brightness, 2);
+
+        assert_eq!(find_backlight(&dir.path().join("missing")).await, None);
+    }
+
+    #[tokio::test]
+    async fn missing_max_brightness_is_none() {
+        let dir = sysfs_dir(&[("backlight/intel_backlight/brightness", "19200\n")]);
+
+        assert_eq!(find_backlight(&dir.path().join("backlight")).await, None);
+    }
+
+    #[tokio::test]
+    async fn zero_max_brightness_is_off() {
+        let dir = sysfs_dir(&[
+            ("leds/asus::kbd_backlight/brightness", "0\n"),
+            ("leds/asus::kbd_backlight/max_brightness", "0\n"),
+        ]);
+
+        let keyboard = find_keyboard_backlight(&dir.path().join("leds"))
+            .await
+            .unwrap();
+
+        assert_eq!(keyboard.level.to_string(), "0%");
+    }
+}
@@ -0,0 +1,201 @@
+//! Errors encountered while loading the config
+//!
+//! See [`ConfigError`]
+use std::{fmt::Display, io, path::Path};
+
+use zbus::zvariant;
+
+/// An error in one of the config files
+///
+/// These are collected by [`super::Config::update`] and made available over dbus, so issues with
+/// the config don't have to be looked up in the daemon's logs.
+#[derive(Debug, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type)]
+pub struct ConfigError {
+    /// The config value this error prevented from being updated
+    pub source: ConfigSource,
+    /// Path to the file containing the error
+    ///
+    /// This isn't always the file corresponding to [`Self::source`], since `style.scss` may
+    /// import other files.
+    pub file: String,
+    /// Line the error is on (starting from 1)
+    ///
+    /// Is 0 if the location is unknown.
+    pub line: u32,
+    /// Column the error is on (starting from 1)
+    ///
+    /// Is 0 if the location is unknown.
+    pub column: u32,
+    /// Description of the error
+    pub message: String,
+}
+
+/// The different config values which can fail to load
+///
+/// See: [`super::Config`]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::Display,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub enum ConfigSource {
+    /// `config.toml`
+    #[strum(to_string = "config.toml")]
+    Toml,
+    /// `style.scss`
+    #[strum(to_string = "style.scss")]
+    Scss,
+    /// `layouts.json`
+    #[strum(to_string = "layouts.json")]
+    Layouts,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}: {}", self.file, self.message);
+        }
+
+        write!(
+            f,
+            "{}:{}:{}: {}",
+            self.file, self.line, self.column, self.message
+        )
+    }
+}
+
+impl ConfigError {
+    /// Create a new error for a file which couldn't be read
+    #[must_use]
+    pub fn from_io(source: ConfigSource, path: &Path, err: &io::Error) -> Self {
+        Self {
+            source,
+            file: path.display().to_string(),
+            line: 0,
+            column: 0,
+            message: format!("failed to read file: {err}"),
+        }
+    }
+
+    /// Create a new error from a failure to parse `config.toml`
+    ///
+    /// `content` has to be the string which failed to parse, since it is used to find the
+    /// location of the error.
+    #[must_use]
+    pub fn from_toml(path: &Path, content: &str, err: &toml::de::Error) -> Self {
+        let offset = err.span().map(|span| span.start);
+
+        Self::at_offset(
+            ConfigSource::Toml,
+            path,
+            content,
+            offset,
+            err.message().to_string(),
+        )
+    }
+
+    /// Create a new error located at the byte `offset` within `content`
+    ///
+    /// If `offset` is [`None`] the location is unknown.
+    #[must_use]
+    pub fn at_offset(
+        source: ConfigSource,
+        path: &Path,
+        content: &str,
+        offset: Option<usize>,
+        message: String,
+    ) -> Self {
+        let (line, column) = offset.map_or((0, 0), |offset| line_column(content, offset));
+
+        Self {
+            source,
+            file: path.display().to_string(),
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Create a new error from a failure to parse `layouts.json`
+    #[must_use]
+    pub fn from_json(path: &Path, err: &serde_json::Error) -> Self {
+        let line = u32::try_from(err.line()).unwrap_or(u32::MAX);
+        let column = u32::try_from(err.column()).unwrap_or(u32::MAX);
+
+        // The Display impl of serde_json::Error already includes the location
+        let message = err.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {line} column {column}"))
+            .unwrap_or(&message)
+            .to_string();
+
+        Self {
+            source: ConfigSource::Layouts,
+            file: path.display().to_string(),
+            line,
+            column,
+            message,
+        }
+    }
+
+    /// Create a new error from a failure to compile `style.scss`
+    ///
+    /// `path` is used if the error doesn't contain a location itself.
+    #[must_use]
+    pub fn from_scss(path: &Path, err: grass::Error) -> Self {
+        let mut error = Self {
+            source: ConfigSource::Scss,
+            file: path.display().to_string(),
+            line: 0,
+            column: 0,
+            message: String::new(),
+        };
+
+        match err.kind() {
+            grass::ErrorKind::ParseError { message, loc, .. } => {
+                error.file = loc.file.name().to_string();
+                error.line = u32::try_from(loc.begin.line + 1).unwrap_or(u32::MAX);
+                error.column = u32::try_from(loc.begin.column + 1).unwrap_or(u32::MAX);
+                error.message = message;
+            }
+            grass::ErrorKind::IoError(e) => error.message = format!("failed to read file: {e}"),
+            grass::ErrorKind::FromUtf8Error(e) => error.message = e,
+            e => error.message = format!("{e:?}"),
+        }
+
+        error
+    }
+}
+
+/// Helper function to get the line and column (both starting at 1) of a byte offset in `content`
+fn line_column(content: &str, offset: usize) -> (u32, u32) {
+    let before = content.get(..offset).unwrap_or(content);
+
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |l| l.chars().count()) + 1;
+
+    (
+        u32::try_from(line).unwrap_or(u32::MAX),
+        u32::try_from(column).unwrap_or(u32::MAX),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::line_column;
+
+    #[test]
+    fn line_column_offsets() {
+        let content = "[bar]\ndisk = 1\n";
+
+        assert_eq!(line_column(content, 0), (1, 1));
+        assert_eq!(line_column(content, 6), (2, 1));
+        assert_eq!(line_column(content, 13), (2, 8));
+    }
+}
@@ -0,0 +1,324 @@
+//! Merging `config.toml` from multiple files
+//!
+//! The toml config is made up of multiple layers, each one overriding the ones before it:
+//!
+//! 1. `config.toml` in [`common::SYSTEM_CONFIG_PATH`]
+//! 2. `config.toml` in [`common::CONFIG_PATH`]
+//! 3. All `*.toml` files in `config.d`, sorted by file name
+//! 4. `config.<hostname>.toml`
+//!
+//! Layers which don't exist are skipped. With [`LayerScope::ConfigDir`] only layers 2 & 3 are
+//! read. Tables are merged recursively, all other values
+//! (including arrays) replace the previous value.
+//!
+//! This makes it possible to share most of the config between machines, while e.g. only setting
+//! `bar.battery` on a laptop.
+//!
+//! Options of older versions are migrated after merging (see [`migrate`]).
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use common::SYSTEM_CONFIG_PATH;
+use sysinfo::System;
+
+use super::{
+    error::{ConfigError, ConfigSource},
+    parse_toml,
+};
+
+/// Name of the directory containing additional layers
+pub const CONFIG_D: &str = "config.d";
+
+/// File listing all mounted file systems, used to migrate `bar.disk`
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+/// A single file making up the toml config
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    /// Path to the file
+    pub path: PathBuf,
+    /// Content of the file
+    pub content: String,
+}
+
+/// Which layers are read for a config dir
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerScope {
+    /// All layers, as read by the daemon
+    All,
+    /// Only the layers in the config dir which don't depend on the current machine
+    ///
+    /// Used to check a config in isolation (e.g. while building it with nix). Having no layers at
+    /// all is valid in this scope.
+    ConfigDir,
+}
+
+/// Name of the layer specific to this machine
+fn host_file_name() -> Option<String> {
+    System::host_name().map(|host| format!("config.{host}.toml"))
+}
+
+/// All existing layers in `scope` for `config_dir`, in the order they are merged in
+#[must_use]
+pub fn layer_paths(config_dir: &Path, scope: LayerScope) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if scope == LayerScope::All {
+        paths.push(Path::new(SYSTEM_CONFIG_PATH).join("config.toml"));
+    }
+
+    paths.push(config_dir.join("config.toml"));
+
+    if let Ok(entries) = fs::read_dir(config_dir.join(CONFIG_D)) {
+        let mut config_d: Vec<PathBuf> = entries
+            .filter_map(|e| Some(e.ok()?.path()))
+            .filter(|p| p.extension() == Some(OsStr::new("toml")))
+            .collect();
+
+        config_d.sort_unstable();
+
+        paths.extend(config_d);
+    }
+
+    if scope == LayerScope::All
+        && let Some(name) = host_file_name()
+    {
+        paths.push(config_dir.join(name));
+    }
+
+    paths.retain(|p| p.is_file());
+
+    paths
+}
+
+/// If `path` is (or would be) one of the layers for `config_dir`
+///
+/// Used to decide if a change to `path` requires reloading the config.
+#[must_use]
+pub fn is_layer(config_dir: &Path, path: &Path) -> bool {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(OsStr::to_str)) else {
+        return false;
+    };
+
+    if dir == config_dir.join(CONFIG_D) {
+        return Path::new(name).extension() == Some(OsStr::new("toml"));
+    }
+
+    if dir == config_dir {
+        return name == "config.toml" || host_file_name().is_some_and(|host| name == host);
+    }
+
+    dir == Path::new(SYSTEM_CONFIG_PATH) && name == "config.toml"
+}
+
+/// Read all layers in `scope` for `config_dir`
+///
+/// # Errors
+///
+/// This function will return an error if a layer can't be read or there are no layers at all in
+/// [`LayerScope::All`].
+pub fn read_layers(config_dir: &Path, scope: LayerScope) -> Result<Vec<ConfigLayer>, ConfigError> {
+    let paths = layer_paths(config_dir, scope);
+
+    if paths.is_empty() && scope == LayerScope::All {
+        return Err(ConfigError::from_io(
+            ConfigSource::Toml,
+            &config_dir.join("config.toml"),
+            &io::ErrorKind::NotFound.into(),
+        ));
+    }
+
+    paths
+        .into_iter()
+        .map(|path| match fs::read_to_string(&path) {
+            Ok(content) => Ok(ConfigLayer { path, content }),
+            Err(e) => Err(ConfigError::from_io(ConfigSource::Toml, &path, &e)),
+        })
+        .collect()
+}
+
+/// Merge `layers` into a single toml table
+///
+/// The result is checked to be a valid [`common::Config`].
+///
+/// # Errors
+///
+/// This function will return an error if any of the layers isn't valid toml or the merged result
+/// isn't a valid [`common::Config`].
+pub fn merge_layers(layers: &[ConfigLayer]) -> Result<toml::Table, ConfigError> {
+    // With a single layer the error can point to the exact location
+    if let [layer] = layers {
+        parse_toml(&layer.path, &layer.content)?;
+    }
+
+    let mut merged = toml::Table::new();
+
+    for layer in layers {
+        let table: toml::Table = toml::from_str(&layer.content)
+            .map_err(|e| ConfigError::from_toml(&layer.path, &layer.content, &e))?;
+
+        merge_tables(&mut merged, table);
+    }
+
+    migrate(
+        &mut merged,
+        &fs::read_to_string(MOUNTS_PATH).unwrap_or_default(),
+    );
+
+    if let Err(e) = toml::Value::Table(merged.clone()).try_into::<common::Config>() {
+        let files = layers
+            .iter()
+            .map(|l| l.path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        return Err(ConfigError::at_offset(
+            ConfigSource::Toml,
+            layers.last().map_or_else(|| Path::new(""), |l| &l.path),
+            "",
+            None,
+            format!("{} (in config merged from {files})", e.message()),
+        ));
+    }
+
+    Ok(merged)
+}
+
+/// Migrate options of older versions in the `merged` config
+///
+/// `bar.disk` (a device, e.g. `/dev/sda1`) was replaced by `bar.disks` (mount points). Unless
+/// `bar.disks` is set as well, it is set to where the device is mounted according to `mounts` (in
+/// the format of [`MOUNTS_PATH`]). A deprecation warning is logged either way.
+fn migrate(merged: &mut toml::Table, mounts: &str) {
+    let Some(toml::Value::Table(bar)) = merged.get_mut("bar") else {
+        return;
+    };
+
+    let Some(disk) = bar.remove("disk") else {
+        return;
+    };
+
+    if bar.contains_key("disks") {
+        log::warn!("bar.disk is deprecated and ignored, since bar.disks is set");
+        return;
+    }
+
+    let device = disk.as_str().unwrap_or_default();
+
+    let mount_points: Vec<toml::Value> = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let mount_point = fields.next()?;
+
+            // Spaces in mount points are escaped as octal
+            (source == device).then(|| mount_point.replace("\\040", " ").into())
+        })
+        .collect();
+
+    if mount_points.is_empty() {
+        log::warn!("bar.disk is deprecated, use bar.disks instead ({device} isn't mounted)");
+        return;
+    }
+
+    log::warn!(
+        "bar.disk is deprecated, use bar.disks = {} instead",
+        toml::Value::from(mount_points.clone())
+    );
+
+    bar.insert("disks".to_string(), mount_points.into());
+}
+
+/// Read and merge all layers for `config_dir`, as done by the daemon
+///
+/// See [`read_layers`] & [`merge_layers`]
+///
+/// # Errors
+///
+/// See [`read_layers`] & [`merge_layers`]
+pub fn load(config_dir: &Path) -> Result<toml::Table, ConfigError> {
+    merge_layers(&read_layers(config_dir, LayerScope::All)?)
+}
+
+/// Recursively merge `overlay` into `base`
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overlay)) => {
+                merge_tables(base, overlay);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LayerScope, merge_tables, migrate, read_layers};
+
+    #[test]
+    fn config_dir_scope_allows_no_layers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(
+            read_layers(dir.path(), LayerScope::ConfigDir)
+                .unwrap()
+                .is_empty()
+        );
+
+        std::fs::write(dir.path().join("config.toml"), "[bar]").unwrap();
+
+        let layers = read_layers(dir.path(), LayerScope::ConfigDir).unwrap();
+
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].path, dir.path().join("config.toml"));
+    }
+
+    #[test]
+    fn merge_overrides() {
+        let mut base: toml::Table = toml::from_str(
+            "[bar]\ndisks = [\"/\"]\nshow_capslock = true\n[launcher.launch_mode]\napps = [1, 2]",
+        )
+        .unwrap();
+        let overlay: toml::Table =
+            toml::from_str("[bar]\nbattery = \"BAT0\"\n[launcher.launch_mode]\napps = [3]")
+                .unwrap();
+
+        merge_tables(&mut base, overlay);
+
+        let expected: toml::Table = toml::from_str(
+            "[bar]\ndisks = [\"/\"]\nshow_capslock = true\nbattery = \"BAT0\"\n[launcher.launch_mode]\napps = [3]",
+        )
+        .unwrap();
+
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn migrates_disk() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n/dev/sda2 /home/my\\040files ext4 rw 0 0\n";
+
+        let mut old: toml::Table = toml::from_str("[bar]\ndisk = \"/dev/sda2\"").unwrap();
+        migrate(&mut old, mounts);
+
+        assert_eq!(
+            old,
+            toml::from_str("[bar]\ndisks = [\"/home/my files\"]").unwrap()
+        );
+
+        let mut both: toml::Table =
+            toml::from_str("[bar]\ndisk = \"/dev/sda2\"\ndisks = [\"/\"]").unwrap();
+        migrate(&mut both, mounts);
+        assert_eq!(both, toml::from_str("[bar]\ndisks = [\"/\"]").unwrap());
+
+        let mut unmounted: toml::Table = toml::from_str("[bar]\ndisk = \"/dev/sdb1\"").unwrap();
+        migrate(&mut unmounted, mounts);
+        assert_eq!(unmounted, toml::from_str("[bar]").unwrap());
+    }
+}
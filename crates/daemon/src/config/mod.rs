@@ -0,0 +1,375 @@
+//! This module contains items relating to the config
+//!
+//! "Config" in this context means anything located in [``common::CONFIG_PATH``].
+//!
+//! The main type is [``Config``].
+//!
+//! Changes to the config files are picked up by [``watcher::ConfigWatcher``].
+//!
+//! `config.toml` can be split across multiple files, see [``layers``].
+use std::{
+    cell::RefCell,
+    io,
+    path::{Path, PathBuf},
+};
+
+use tokio::fs;
+use zbus::{interface, zvariant};
+
+use common::CONFIG_PATH;
+
+pub mod error;
+pub mod layers;
+pub mod watcher;
+
+use error::{ConfigError, ConfigSource};
+use watcher::ChangedFiles;
+
+/// Contains all config found within the config directory located at [``common::CONFIG_PATH``]
+///
+/// ## Why is [``Self::toml``] not serialized?
+///
+/// While it would be much more comfortable to just serialize the toml and then pass around
+/// [``common::Config``]s this doesn't work since zbus has issues handling [``Option``]s due to
+/// them not being a concept within the dbus protocol.
+///
+/// See <https://dbus2.github.io/zbus/faq.html#how-do-i-use-optiont-with-zbus>
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``ConfigProxy``]
+///
+/// ## Confusion with [``common::Config``]
+///
+/// Although they have the same name these two serve very different functions.
+///
+/// [``common::Config``], as the docs state, relates to the concrete format of `config.toml`
+///
+/// whereas
+///
+/// [``Config``] refers to all types of config found within the config directory
+///
+/// Make sure you know which one you need.
+#[derive(Debug, Clone, zvariant::Value, zvariant::OwnedValue, zvariant::Type)]
+pub struct Config {
+    /// The config from `config.toml`
+    ///
+    /// This is the result of merging all [``layers``].
+    pub toml: String,
+    /// The css from `style.scss`
+    pub css: String,
+    /// The osk layouts from `layouts.json`
+    pub layouts: String,
+    /// Errors which occurred the last time the config files were loaded
+    ///
+    /// If a config value has an error its value is the last valid one.
+    pub errors: Vec<ConfigError>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            toml: toml::to_string(&common::Config::default())
+                .expect("Should never fail to serialize our format to toml."),
+            css: String::default(),
+            layouts: serde_json::to_string(&common::Layouts::default())
+                .expect("Should never fail to serialize our format to json"),
+            errors: Vec::default(),
+        }
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Config",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Config {
+    /// Dbus property for the toml config
+    #[zbus(property)]
+    fn config(&self) -> String {
+        self.toml.clone()
+    }
+
+    /// Dbus property for the css
+    #[zbus(property)]
+    fn css(&self) -> String {
+        self.css.clone()
+    }
+
+    /// Dbus property for the osk layouts
+    #[zbus(property)]
+    fn layouts(&self) -> String {
+        self.layouts.clone()
+    }
+
+    /// Dbus property for all errors in the config files
+    ///
+    /// Listen for changes to this property to get notified about new errors (or them being
+    /// fixed).
+    #[zbus(property)]
+    fn config_errors(&self) -> Vec<ConfigError> {
+        self.errors.clone()
+    }
+
+    /// Dbus property for both the toml config and css
+    #[zbus(property)]
+    #[allow(
+        clippy::use_self,
+        reason = "Can't use Self here because of zbus macro."
+    )]
+    fn all_config(&self) -> Config {
+        self.clone()
+    }
+}
+
+/// Returned by [`Config::update`] to signal which config values have changed
+#[derive(Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Each bool corresponds to a separate field of Config."
+)]
+pub struct ConfigValuesChanged {
+    /// Corresponds to [`Config::toml`]
+    toml: bool,
+    /// Corresponds to [`field@Config::css`]
+    css: bool,
+    /// Corresponds to [`field@Config::layouts`]
+    layouts: bool,
+    /// Corresponds to [`field@Config::errors`]
+    errors: bool,
+    /// All files looked at while compiling `style.scss`
+    ///
+    /// Only set if the scss was compiled.
+    scss_dependencies: Option<Vec<PathBuf>>,
+}
+
+impl ConfigValuesChanged {
+    /// If any config value has changed
+    #[must_use]
+    pub const fn any_changes(&self) -> bool {
+        self.toml || self.css || self.layouts || self.errors
+    }
+
+    /// If the toml value changed
+    #[must_use]
+    pub const fn toml_changed(&self) -> bool {
+        self.toml
+    }
+
+    /// If the css value changed
+    #[must_use]
+    pub const fn css_changed(&self) -> bool {
+        self.css
+    }
+
+    /// If the layouts value changed
+    #[must_use]
+    pub const fn layouts_changed(&self) -> bool {
+        self.layouts
+    }
+
+    /// If the errors changed
+    #[must_use]
+    pub const fn errors_changed(&self) -> bool {
+        self.errors
+    }
+
+    /// All files looked at while compiling `style.scss`, if it was compiled
+    ///
+    /// See [`watcher::ConfigWatcher::watch_scss_dependencies`]
+    #[must_use]
+    pub fn scss_dependencies(&self) -> Option<&[PathBuf]> {
+        self.scss_dependencies.as_deref()
+    }
+}
+
+/// [`grass::Fs`] which keeps track of all files grass looks at
+///
+/// This includes files which don't exist, so creating a previously missing import is also picked
+/// up as a change.
+#[derive(Debug, Default)]
+struct RecordingFs {
+    /// All files looked at
+    files: RefCell<Vec<PathBuf>>,
+}
+
+impl RecordingFs {
+    /// Helper function to record a file
+    fn record(&self, path: &Path) {
+        let mut files = self.files.borrow_mut();
+
+        if !files.iter().any(|f| f == path) {
+            files.push(path.to_path_buf());
+        }
+    }
+}
+
+impl grass::Fs for RecordingFs {
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.record(path);
+
+        path.is_file()
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.record(path);
+
+        std::fs::read(path)
+    }
+}
+
+impl Config {
+    /// Update the config values from disk
+    ///
+    /// Only the files marked in `files` are re-read.
+    pub async fn update(&mut self, files: ChangedFiles) -> ConfigValuesChanged {
+        let mut changes = ConfigValuesChanged::default();
+        let old_errors = self.errors.clone();
+
+        if files.toml {
+            let result = self.update_toml();
+            changes.toml = self.handle_result(ConfigSource::Toml, result);
+        }
+
+        if files.scss {
+            let (result, dependencies) = self.update_css();
+
+            changes.css = self.handle_result(ConfigSource::Scss, result);
+            changes.scss_dependencies = Some(dependencies);
+        }
+
+        if files.layouts {
+            let result = self.update_layouts().await;
+            changes.layouts = self.handle_result(ConfigSource::Layouts, result);
+        }
+
+        changes.errors = old_errors != self.errors;
+
+        changes
+    }
+
+    /// Helper function to replace the errors of `source` with the outcome of updating it
+    ///
+    /// Returns if the value changed.
+    fn handle_result(&mut self, source: ConfigSource, result: Result<bool, ConfigError>) -> bool {
+        self.errors.retain(|e| e.source != source);
+
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to load {source}: {e}");
+
+            self.errors.push(e);
+
+            false
+        })
+    }
+
+    /// Update [`Self::toml`] by merging all [`layers`] from disk
+    ///
+    /// Returns if the value changed.
+    fn update_toml(&mut self) -> Result<bool, ConfigError> {
+        let merged = layers::load(&CONFIG_PATH)?;
+
+        let s = toml::to_string(&merged).expect("Should never fail to serialize a toml table.");
+
+        // If the config hasn't changed don't do anything
+        if self.toml == s {
+            return Ok(false);
+        }
+
+        self.toml = s;
+
+        Ok(true)
+    }
+
+    /// Update [`field@Self::css`] by compiling `style.scss`
+    ///
+    /// Returns if the value changed and all files looked at during compilation.
+    fn update_css(&mut self) -> (Result<bool, ConfigError>, Vec<PathBuf>) {
+        let (css, dependencies) = compile_scss(&CONFIG_PATH.join("style.scss"));
+
+        let result = match css {
+            Ok(s) if self.css != s => {
+                self.css = s;
+
+                Ok(true)
+            }
+            // If the css hasn't changed don't do anything
+            Ok(_) => Ok(false),
+            Err(e) => Err(e),
+        };
+
+        (result, dependencies)
+    }
+
+    /// Update [`field@Self::layouts`] from disk
+    ///
+    /// Returns if the value changed.
+    async fn update_layouts(&mut self) -> Result<bool, ConfigError> {
+        let layouts_path = CONFIG_PATH.join("layouts.json");
+
+        let s = fs::read_to_string(&layouts_path)
+            .await
+            .map_err(|e| ConfigError::from_io(ConfigSource::Layouts, &layouts_path, &e))?;
+
+        // If the layouts haven't changed don't do anything
+        if self.layouts == s {
+            return Ok(false);
+        }
+
+        parse_layouts(&layouts_path, &s)?;
+
+        self.layouts = s;
+
+        Ok(true)
+    }
+}
+
+/// Parse `content` as `config.toml`
+///
+/// `path` is only used for the location of errors.
+///
+/// # Errors
+///
+/// This function will return an error if `content` isn't a valid [`common::Config`].
+pub fn parse_toml(path: &Path, content: &str) -> Result<common::Config, ConfigError> {
+    toml::from_str(content).map_err(|e| ConfigError::from_toml(path, content, &e))
+}
+
+/// Compile the scss file at `path` to css
+///
+/// This uses the same options the daemon uses for `style.scss`.
+///
+/// Also returns all files looked at during compilation. See [`RecordingFs`].
+///
+/// # Errors
+///
+/// The first element of the returned tuple is an error if the scss fails to compile.
+pub fn compile_scss(path: &Path) -> (Result<String, ConfigError>, Vec<PathBuf>) {
+    let fs = RecordingFs::default();
+
+    // TODO: Wait for upstream GtkCompressed output style
+    let result = grass::from_path(path, &grass::Options::default().fs(&fs))
+        .map_err(|e| ConfigError::from_scss(path, *e));
+
+    (result, fs.files.into_inner())
+}
+
+/// Parse `content` as `layouts.json`
+///
+/// `path` is only used for the location of errors.
+///
+/// # Errors
+///
+/// This function will return an error if `content` isn't valid [`common::Layouts`].
+pub fn parse_layouts(path: &Path, content: &str) -> Result<common::Layouts, ConfigError> {
+    serde_json::from_str(content).map_err(|e| ConfigError::from_json(path, &e))
+}
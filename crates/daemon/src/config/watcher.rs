@@ -0,0 +1,306 @@
+//! Watching the config directory for changes
+//!
+//! Instead of re-reading all config files on a timer, [`ConfigWatcher`] uses inotify to get
+//! notified as soon as one of them is written to.
+//!
+//! ## Scss imports
+//!
+//! `style.scss` can pull in other files via `@import` / `@use`. These files may live anywhere on
+//! disk, which is why the watcher also needs to know which files were looked at when compiling
+//! the scss. See [`ConfigWatcher::watch_scss_dependencies`].
+//!
+//! ## Toml layers
+//!
+//! `config.toml` may be split across multiple files (see [`super::layers`]), so the directories
+//! containing layers outside of the config directory are watched as well. Their parents are
+//! watched too, so e.g. `/etc/dod-shell` is picked up when it is created after the daemon started.
+//! Events for other entries of these parents (e.g. everything else in `/etc`) are ignored.
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures_util::StreamExt;
+use inotify::{EventMask, EventStream, Inotify, WatchDescriptor, WatchMask};
+
+use common::{CONFIG_PATH, SYSTEM_CONFIG_PATH};
+
+use super::layers::{self, CONFIG_D};
+
+/// How long to wait for further events after receiving one
+///
+/// Editors will often write a file in multiple steps (e.g. write to a temporary file and rename it),
+/// which we want to handle as a single change.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// The events we are interested in for all watched directories
+const WATCH_MASK: WatchMask = WatchMask::CLOSE_WRITE
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::CREATE)
+    .union(WatchMask::DELETE);
+
+/// Which config files have changed on disk
+///
+/// Passed to [`super::Config::update`] so only the changed files are re-parsed.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedFiles {
+    /// `config.toml` or any of its other layers changed
+    pub toml: bool,
+    /// `style.scss` or any of the files it depends on changed
+    pub scss: bool,
+    /// `layouts.json` changed
+    pub layouts: bool,
+}
+
+impl ChangedFiles {
+    /// All files changed
+    ///
+    /// Used for the initial load of the config.
+    pub const ALL: Self = Self {
+        toml: true,
+        scss: true,
+        layouts: true,
+    };
+
+    /// If any file changed
+    #[must_use]
+    pub const fn any(&self) -> bool {
+        self.toml || self.scss || self.layouts
+    }
+}
+
+/// Watches all files relevant to the config using inotify
+///
+/// Directories are watched rather than the files themselves, since many editors (and
+/// home-manager) replace files instead of writing to them, which would silently drop a watch on
+/// the file.
+pub struct ConfigWatcher {
+    /// The canonicalized [`common::CONFIG_PATH`]
+    config_dir: PathBuf,
+    /// Stream of inotify events
+    stream: EventStream<[u8; 1024]>,
+    /// All directories currently being watched
+    dirs: HashMap<WatchDescriptor, PathBuf>,
+    /// Directories which may contain toml layers, other than [`Self::config_dir`]
+    ///
+    /// These are only watched if they exist. Their parents are always watched, so they are noticed
+    /// as soon as they are created.
+    layer_dirs: [PathBuf; 2],
+    /// All files `style.scss` depends on (including itself)
+    ///
+    /// Paths are normalized using [`normalize`].
+    scss_files: HashSet<PathBuf>,
+    /// Changes received, but not yet returned by [`Self::next_changes`]
+    ///
+    /// Kept here so [`Self::next_changes`] is cancel safe.
+    pending: ChangedFiles,
+}
+
+impl ConfigWatcher {
+    /// Create a new watcher for [`common::CONFIG_PATH`]
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if inotify can't be initialized or the config directory
+    /// can't be watched.
+    pub fn new() -> io::Result<Self> {
+        let stream = Inotify::init()?.into_event_stream([0; 1024])?;
+        let config_dir = CONFIG_PATH.canonicalize()?;
+
+        let wd = stream.watches().add(&config_dir, WATCH_MASK)?;
+
+        let mut watcher = Self {
+            stream,
+            dirs: HashMap::from([(wd, config_dir.clone())]),
+            layer_dirs: [PathBuf::from(SYSTEM_CONFIG_PATH), config_dir.join(CONFIG_D)],
+            scss_files: HashSet::from([config_dir.join("style.scss")]),
+            config_dir,
+            pending: ChangedFiles::default(),
+        };
+
+        for dir in watcher.layer_dirs.clone() {
+            if let Some(parent) = dir.parent() {
+                watcher.watch_dir(parent);
+            }
+
+            watcher.watch_dir(&dir);
+        }
+
+        Ok(watcher)
+    }
+
+    /// Helper function to watch `dir`, if it exists and isn't already being watched
+    fn watch_dir(&mut self, dir: &Path) {
+        if self.dirs.values().any(|d| d == dir) || !dir.is_dir() {
+            return;
+        }
+
+        match self.stream.watches().add(dir, WATCH_MASK) {
+            Ok(wd) => {
+                log::debug!("Watching {} for config changes", dir.display());
+                self.dirs.insert(wd, dir.to_path_buf());
+            }
+            Err(e) => log::error!("Failed to watch {}: {e}", dir.display()),
+        }
+    }
+
+    /// Set the files `style.scss` depends on
+    ///
+    /// This should be called every time the scss is compiled, since imports may have been added or
+    /// removed. The directories of all files are watched and directories no longer needed are no
+    /// longer watched.
+    ///
+    /// Directories which don't exist are skipped.
+    pub fn watch_scss_dependencies(&mut self, files: &[PathBuf]) {
+        self.scss_files = files.iter().filter_map(|f| normalize(f)).collect();
+        self.scss_files.insert(self.config_dir.join("style.scss"));
+
+        let needed_dirs: HashSet<PathBuf> = self
+            .scss_files
+            .iter()
+            .filter_map(|f| f.parent())
+            .chain([self.config_dir.as_path()])
+            .chain(self.layer_dirs.iter().map(PathBuf::as_path))
+            .chain(self.layer_dirs.iter().filter_map(|d| d.parent()))
+            .map(Path::to_path_buf)
+            .collect();
+
+        let mut watches = self.stream.watches();
+
+        self.dirs.retain(|wd, dir| {
+            if needed_dirs.contains(dir.as_path()) {
+                return true;
+            }
+
+            if let Err(e) = watches.remove(wd.clone()) {
+                log::debug!("Failed to stop watching {}: {e}", dir.display());
+            }
+
+            false
+        });
+
+        for dir in needed_dirs {
+            self.watch_dir(&dir);
+        }
+    }
+
+    /// Wait until one or more of the config files change
+    ///
+    /// Events arriving in short succession are combined into one [`ChangedFiles`].
+    ///
+    /// This method is cancel safe.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if reading from inotify fails or the event stream ends.
+    pub async fn next_changes(&mut self) -> io::Result<ChangedFiles> {
+        while !self.pending.any() {
+            let event = self
+                .stream
+                .next()
+                .await
+                .ok_or_else(|| io::Error::other("inotify event stream ended"))??;
+
+            self.apply_event(&event.wd, event.mask, event.name.as_deref());
+        }
+
+        while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE, self.stream.next()).await {
+            let event = event?;
+
+            self.apply_event(&event.wd, event.mask, event.name.as_deref());
+        }
+
+        Ok(std::mem::take(&mut self.pending))
+    }
+
+    /// Helper function to add the effects of a single inotify event to [`Self::pending`]
+    fn apply_event(&mut self, wd: &WatchDescriptor, mask: EventMask, name: Option<&OsStr>) {
+        let (Some(dir), Some(name)) = (self.dirs.get(wd), name) else {
+            return;
+        };
+
+        let path = dir.join(name);
+
+        // Layer dirs may also be symlinks (e.g. `/etc/dod-shell` on NixOS)
+        if self.layer_dirs.contains(&path) {
+            self.apply_layer_dir_event(&path, mask);
+
+            return;
+        }
+
+        if self.is_parent_only(dir) {
+            return;
+        }
+
+        if mask.contains(EventMask::ISDIR) {
+            return;
+        }
+
+        if self.scss_files.contains(&path) {
+            self.pending.scss = true;
+        }
+
+        if layers::is_layer(&self.config_dir, &path) {
+            self.pending.toml = true;
+        }
+
+        if *dir == self.config_dir && name == "layouts.json" {
+            self.pending.layouts = true;
+        }
+
+        log::trace!("Config event {mask:?} for {}", path.display());
+    }
+
+    /// Helper function to check if `dir` is only watched because it is the parent of one of
+    /// [`Self::layer_dirs`]
+    fn is_parent_only(&self, dir: &Path) -> bool {
+        self.layer_dirs.iter().any(|d| d.parent() == Some(dir))
+            && dir != self.config_dir
+            && !self.layer_dirs.iter().any(|d| d == dir)
+            && !self.scss_files.iter().any(|f| f.parent() == Some(dir))
+    }
+
+    /// Helper function to handle one of [`Self::layer_dirs`] being created or removed
+    ///
+    /// The old watch is always removed, since a symlink may have been replaced by one pointing
+    /// somewhere else (e.g. by renaming a new symlink over it). inotify only removes the watch
+    /// itself if the directory was deleted, not if it was a symlink to it.
+    fn apply_layer_dir_event(&mut self, dir: &Path, mask: EventMask) {
+        let mut watches = self.stream.watches();
+
+        self.dirs.retain(|wd, d| {
+            if d != dir {
+                return true;
+            }
+
+            if let Err(e) = watches.remove(wd.clone()) {
+                log::debug!("Failed to stop watching {}: {e}", d.display());
+            }
+
+            false
+        });
+
+        if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+            self.watch_dir(dir);
+        }
+
+        self.pending.toml = true;
+
+        log::trace!("Config event {mask:?} for {}", dir.display());
+    }
+}
+
+/// Normalize a path, so paths to the same file can be compared
+///
+/// Only the parent directory is canonicalized, since the file itself may not exist (yet).
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
@@ -0,0 +1,38 @@
+//! This module contains items relating to diagnosing the performance of the daemon.
+//!
+//! The main type is [``Diagnostics``]
+use common::metrics::TimerStats;
+use zbus::interface;
+
+/// Makes the timing metrics of the daemon available
+///
+/// See [``common::metrics``]
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``DiagnosticsProxy``]
+#[derive(Debug, Default)]
+pub struct Diagnostics;
+
+#[interface(
+    name = "dod.shell.Daemon.Diagnostics",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Diagnostics {
+    /// Dbus property for the statistics of all timers
+    ///
+    /// This changes constantly, so no change signals are emitted.
+    #[zbus(property(emits_changed_signal = "false"))]
+    #[allow(
+        clippy::unused_self,
+        reason = "The metrics are stored globally, but zbus properties need self."
+    )]
+    fn diagnostics(&self) -> Vec<TimerStats> {
+        common::metrics::timer_stats()
+    }
+}
@@ -1,7 +1,17 @@
 //! The daemon is responsible for holding, updating and distributing all data needed by
 //! other components of the shell.
 
+pub mod audio;
+pub mod bluetooth;
+pub mod brightness;
 pub mod config;
+pub mod diagnostics;
+pub mod logging;
+pub mod network;
+pub mod notifications;
 pub mod osk;
 pub mod playback;
 pub mod system_state;
+pub mod tray;
+
+mod util;
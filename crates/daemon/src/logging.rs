@@ -0,0 +1,74 @@
+//! This module contains items relating to changing the daemon's log levels at runtime.
+//!
+//! The main type is [``Logging``]
+use std::str::FromStr;
+
+use log::LevelFilter;
+use zbus::{fdo, interface, object_server::SignalEmitter};
+
+/// Value of `level` in [``Logging::set_log_level``] to remove a previously set level
+pub const DEFAULT_LEVEL: &str = "default";
+
+/// Allows raising (or lowering) the verbosity of parts of the daemon without restarting it
+///
+/// See [``common::logging::set_level``]
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``LoggingProxy``]
+#[derive(Debug, Default)]
+pub struct Logging;
+
+#[interface(
+    name = "dod.shell.Daemon.Logging",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Logging {
+    /// Set the log level of `target` (e.g. `daemon::playback`) and all targets below it
+    ///
+    /// `level` is one of `off`, `error`, `warn`, `info`, `debug`, `trace` or [``DEFAULT_LEVEL``]
+    /// to go back to the level set via `DOD_SHELL_LOG`.
+    async fn set_log_level(
+        &self,
+        target: &str,
+        level: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let level_filter = if level == DEFAULT_LEVEL {
+            None
+        } else {
+            Some(
+                LevelFilter::from_str(level)
+                    .map_err(|_| fdo::Error::InvalidArgs(format!("Invalid log level: {level}")))?,
+            )
+        };
+
+        if !common::logging::set_level(target, level_filter) {
+            return Err(fdo::Error::Failed("Logger isn't set up".to_string()));
+        }
+
+        log::info!("Log level of \"{target}\" set to {level}");
+
+        self.log_levels_changed(&emitter).await?;
+
+        Ok(())
+    }
+
+    /// Dbus property for all log levels set at runtime as `(target, level)`
+    #[zbus(property)]
+    #[allow(
+        clippy::unused_self,
+        reason = "The levels are stored by the logger, but zbus properties need self."
+    )]
+    fn log_levels(&self) -> Vec<(String, String)> {
+        common::logging::levels()
+            .into_iter()
+            .map(|(target, level)| (target, level.as_str().to_lowercase()))
+            .collect()
+    }
+}
@@ -2,19 +2,34 @@
 
 #![deny(clippy::arbitrary_source_item_ordering)]
 
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
+use clap::Parser;
 use common::{logger, types::Timer};
 use tokio::time::interval;
 use zbus::{conn::Builder, object_server::InterfaceRef};
 
-use anyhow::{Ok, Result};
+use anyhow::{Context, Ok, Result, bail};
 
 use daemon::{
-    config::{Config, ConfigProxy},
+    audio::Audio,
+    bluetooth::Bluetooth,
+    brightness::Brightness,
+    config::{
+        Config, ConfigProxy,
+        watcher::{ChangedFiles, ConfigWatcher},
+    },
+    diagnostics::Diagnostics,
+    logging::Logging,
+    network::Network,
+    notifications::{self, Notifications, server::Server as NotificationServer},
     osk::{Osk, state::State as OskState},
     playback::Playback,
-    system_state::SystemState,
+    system_state::{self, SystemState},
+    tray::{
+        Tray,
+        watcher::{self, Watcher},
+    },
 };
 
 /// Macro used to create [`zbus::object_server::InterfaceRef`]s
@@ -31,49 +46,130 @@ macro_rules! create_ifaces {
     };
 }
 
+/// Name the daemon owns on the session bus
+///
+/// Only one daemon can own it per bus, see [`Args::config_dir`].
+const DBUS_NAME: &str = "dod.shell.Daemon";
+
 /// Path to all dbus served [`zbus::object_server::Interface`]s
 const DBUS_PATH: &str = "/dod/shell/Daemon";
 
+/// The daemon of the dod-shell
+///
+/// Holds, updates and distributes all data needed by other components of the shell.
+#[derive(Parser, Debug)]
+struct Args {
+    /// Use this directory for the config instead of the default
+    ///
+    /// Can also be set via `DOD_SHELL_CONFIG_DIR`. To run a second daemon (e.g. to test a theme),
+    /// start it and its components on their own session bus using `dbus-run-session`, since only
+    /// one daemon can own the bus name.
+    #[arg(long)]
+    config_dir: Option<PathBuf>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     logger!();
+    let args = Args::parse();
+
+    if let Some(config_dir) = args.config_dir
+        && !common::set_config_path(config_dir)
+    {
+        bail!("Config dir has to be set before it is used");
+    }
+
+    log::info!("Using config dir {}", common::CONFIG_PATH.display());
+
+    let audio = Audio::default();
+    let audio_data = audio.audio();
+
+    // Shared by everything talking to system services, the session bus uses `connection`
+    let system_bus = zbus::Connection::system().await?;
 
     let connection = Builder::session()?
-        .name("dod.shell.Daemon")?
+        .name(DBUS_NAME)?
         .serve_at(DBUS_PATH, Config::default())?
         .serve_at(DBUS_PATH, Osk::new()?)?
         .serve_at(DBUS_PATH, OskState::default())?
         .serve_at(DBUS_PATH, SystemState::default())?
         .serve_at(DBUS_PATH, Playback::default())?
+        .serve_at(DBUS_PATH, audio)?
+        .serve_at(DBUS_PATH, Network::new(system_bus.clone()))?
+        .serve_at(DBUS_PATH, Bluetooth::new(system_bus.clone()))?
+        .serve_at(DBUS_PATH, Brightness::new(system_bus.clone()))?
+        .serve_at(DBUS_PATH, Notifications::default())?
+        .serve_at(DBUS_PATH, Tray::default())?
+        .serve_at(DBUS_PATH, Logging)?
+        .serve_at(DBUS_PATH, Diagnostics)?
         .build()
-        .await?;
+        .await
+        .with_context(|| format!("Failed to own {DBUS_NAME}, is another daemon running?"))?;
 
     let obj_server = connection.object_server();
 
-    let (state_iface, config_iface, osk_iface, osk_state_iface, mpris_iface) = create_ifaces!(
+    let (
+        state_iface,
+        config_iface,
+        osk_iface,
+        osk_state_iface,
+        mpris_iface,
+        notifications_iface,
+        tray_iface,
+    ) = create_ifaces!(
         obj_server,
         DBUS_PATH,
         SystemState,
         Config,
         Osk,
         OskState,
-        Playback
+        Playback,
+        Notifications,
+        Tray
     );
 
+    serve_standard_ifaces(&connection, notifications_iface.clone(), tray_iface).await?;
+
+    system_state::spawn_collectors(&state_iface, &system_bus, audio_data);
+
     let config_proxy = ConfigProxy::new(&connection).await?;
 
+    let mut config_watcher = ConfigWatcher::new()?;
+    let mut changed_files = ChangedFiles::ALL;
+
     let mut interval = interval(Duration::from_secs(1));
     loop {
-        if update_config(&config_iface).await? {
+        if changed_files.any()
+            && update_config(&config_iface, &mut config_watcher, changed_files).await?
+        {
             let config = toml::from_str::<common::Config>(&config_proxy.config().await?)
                 .expect("Config string returned by daemon should always be valid.");
 
+            let mut notifications = notifications_iface.get_mut().await;
+
+            notifications.set_config(config.notifications.clone());
+            notifications
+                .changed(notifications_iface.signal_emitter())
+                .await?;
+
+            drop(notifications);
+
             let mut state = state_iface.get_mut().await;
 
             state.set_config(config);
         }
 
-        update_state(&state_iface).await?;
+        changed_files = ChangedFiles::default();
+
+        // Config changes are handled right away, everything else is updated once per tick
+        // SystemState is kept up to date by its own collectors
+        tokio::select! {
+            changes = config_watcher.next_changes() => {
+                changed_files = changes?;
+                continue;
+            }
+            _ = interval.tick() => (),
+        }
 
         {
             let osk = osk_iface.get().await;
@@ -92,31 +188,60 @@ async fn main() -> Result<()> {
                 .update(mpris_iface.signal_emitter())
                 .await;
         }
-
-        interval.tick().await;
     }
 }
 
-/// Helper method to update the values of [`SystemState`]
-async fn update_state(iface: &InterfaceRef<SystemState>) -> Result<()> {
-    // Leave the target at 10 to avoid a warning on first run
-    let _timer = Timer::new("SystemState updated", Some(Duration::from_millis(10)));
+/// Helper method to serve the interfaces defined by freedesktop specs under their well known names
+///
+/// These names may already be owned by other programs, in which case only a warning is logged.
+async fn serve_standard_ifaces(
+    connection: &zbus::Connection,
+    notifications_iface: InterfaceRef<Notifications>,
+    tray_iface: InterfaceRef<Tray>,
+) -> Result<()> {
+    connection
+        .object_server()
+        .at(
+            notifications::FDO_PATH,
+            NotificationServer::new(notifications_iface),
+        )
+        .await?;
 
-    let mut state = iface.get_mut().await;
+    // Another notification daemon (e.g. mako) may already be running
+    if let Err(e) = connection.request_name(notifications::FDO_NAME).await {
+        log::warn!("Failed to become the notification server: {e}");
+    }
 
-    state.update().await;
-    state.state_data_changed(iface.signal_emitter()).await?;
+    connection
+        .object_server()
+        .at(watcher::WATCHER_PATH, Watcher::new(tray_iface))
+        .await?;
+
+    // Another tray (e.g. waybar) may already be running
+    if let Err(e) = connection.request_name(watcher::WATCHER_NAME).await {
+        log::warn!("Failed to become the tray watcher: {e}");
+    }
 
     Ok(())
 }
 
 /// Helper method to update the values of [`Config`]
-async fn update_config(iface: &InterfaceRef<Config>) -> Result<bool> {
+///
+/// Only the files in `changed_files` are re-read.
+async fn update_config(
+    iface: &InterfaceRef<Config>,
+    watcher: &mut ConfigWatcher,
+    changed_files: ChangedFiles,
+) -> Result<bool> {
     let _timer = Timer::new("Config updated", Some(Duration::from_millis(5)));
 
     let mut state = iface.get_mut().await;
 
-    let changes = state.update().await;
+    let changes = state.update(changed_files).await;
+
+    if let Some(dependencies) = changes.scss_dependencies() {
+        watcher.watch_scss_dependencies(dependencies);
+    }
 
     if changes.toml_changed() {
         state.config_changed(iface.signal_emitter()).await?;
@@ -130,6 +255,10 @@ async fn update_config(iface: &InterfaceRef<Config>) -> Result<bool> {
         state.layouts_changed(iface.signal_emitter()).await?;
     }
 
+    if changes.errors_changed() {
+        state.config_errors_changed(iface.signal_emitter()).await?;
+    }
+
     if changes.any_changes() {
         state.all_config_changed(iface.signal_emitter()).await?;
     }
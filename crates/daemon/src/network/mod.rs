@@ -0,0 +1,366 @@
+//! This module contains items relating to controlling the network connections of the system
+//!
+//! This works by talking to `NetworkManager`.
+//!
+//! The main type is [``Network``]
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use zbus::{
+    fdo, interface,
+    proxy::CacheProperties,
+    zvariant::{self, ObjectPath, OwnedObjectPath},
+};
+
+use common::types::Percentage;
+
+use nm::{
+    AccessPointProxy, DeviceProxy, NetworkManagerProxy, SettingsConnectionProxy, SettingsProxy,
+    WIFI_DEVICE_TYPE, WirelessProxy, is_set,
+};
+
+pub(crate) mod nm;
+
+/// `NM80211ApFlags` flag set if the access point requires encryption
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NM80211ApFlags>
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+
+/// `NM80211ApSecurityFlags` flag set if a pre-shared key is used
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NM80211ApSecurityFlags>
+const KEY_MGMT_PSK: u32 = 0x100;
+
+/// `NM80211ApSecurityFlags` flag set if 802.1x authentication is used
+const KEY_MGMT_802_1X: u32 = 0x200;
+
+/// `NM80211ApSecurityFlags` flag set if WPA3 SAE is used
+const KEY_MGMT_SAE: u32 = 0x400;
+
+/// Allows listing and connecting to Wi-Fi networks and turning Wi-Fi on and off
+///
+/// The current connections are part of [``crate::system_state::SystemStateData::network``].
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``NetworkProxy``]
+#[derive(Debug)]
+pub struct Network {
+    /// Connection to the system bus
+    connection: zbus::Connection,
+}
+
+impl Network {
+    /// Create a new [``Network``]
+    ///
+    /// `connection` has to be a connection to the system bus.
+    #[must_use]
+    pub const fn new(connection: zbus::Connection) -> Self {
+        Self { connection }
+    }
+
+    /// Helper function to get the proxies of all Wi-Fi devices
+    async fn wifi_devices(
+        &self,
+    ) -> zbus::Result<Vec<(DeviceProxy<'static>, WirelessProxy<'static>)>> {
+        let manager = NetworkManagerProxy::new(&self.connection).await?;
+        let mut devices = Vec::new();
+
+        for path in manager.get_devices().await? {
+            let device = DeviceProxy::builder(&self.connection)
+                .path(path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            if device.device_type().await? == WIFI_DEVICE_TYPE {
+                let wireless = WirelessProxy::builder(&self.connection)
+                    .path(path)?
+                    .cache_properties(CacheProperties::No)
+                    .build()
+                    .await?;
+
+                devices.push((device, wireless));
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// Helper function to get the proxy of the access point at `path`
+    ///
+    /// Properties aren't cached, since the proxy is only used once and there can be dozens of
+    /// access points.
+    async fn access_point(&self, path: OwnedObjectPath) -> zbus::Result<AccessPointProxy<'static>> {
+        AccessPointProxy::builder(&self.connection)
+            .path(path)?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await
+    }
+
+    /// Helper function to get the paths of all saved Wi-Fi connection profiles, keyed by SSID
+    async fn known_networks(&self) -> zbus::Result<HashMap<Vec<u8>, OwnedObjectPath>> {
+        let settings = SettingsProxy::new(&self.connection).await?;
+        let mut known = HashMap::new();
+
+        for path in settings.list_connections().await? {
+            let connection = SettingsConnectionProxy::builder(&self.connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            let ssid = connection
+                .get_settings()
+                .await?
+                .get("802-11-wireless")
+                .and_then(|wireless| wireless.get("ssid"))
+                .and_then(|ssid| ssid.try_clone().ok())
+                .and_then(|ssid| Vec::<u8>::try_from(ssid).ok());
+
+            if let Some(ssid) = ssid {
+                known.insert(ssid, path);
+            }
+        }
+
+        Ok(known)
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Network",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Network {
+    /// Get all Wi-Fi networks currently visible, sorted by signal strength
+    ///
+    /// The network currently connected to comes first. Hidden networks aren't included.
+    async fn access_points(&self) -> fdo::Result<Vec<AccessPointData>> {
+        let known = self.known_networks().await?;
+        let mut access_points: Vec<AccessPointData> = Vec::new();
+
+        for (_, wireless) in self.wifi_devices().await? {
+            let active = wireless.active_access_point().await?;
+
+            for path in wireless.access_points().await? {
+                let access_point = self.access_point(path.clone()).await?;
+
+                let ssid = access_point.ssid().await?;
+
+                if ssid.is_empty() {
+                    continue;
+                }
+
+                let data = AccessPointData {
+                    ssid: String::from_utf8_lossy(&ssid).to_string(),
+                    strength: Percentage::from(access_point.strength().await?),
+                    security: security(
+                        access_point.flags().await?,
+                        access_point.wpa_flags().await?,
+                        access_point.rsn_flags().await?,
+                    ),
+                    known: known.contains_key(&ssid),
+                    active: path == active,
+                };
+
+                // Networks often have multiple access points, only the best one is kept
+                match access_points.iter_mut().find(|a| a.ssid == data.ssid) {
+                    Some(existing) => {
+                        if (data.active, data.strength) > (existing.active, existing.strength) {
+                            *existing = data;
+                        }
+                    }
+                    None => access_points.push(data),
+                }
+            }
+        }
+
+        access_points.sort_by(|a, b| {
+            b.active
+                .cmp(&a.active)
+                .then_with(|| b.strength.total_cmp(&a.strength))
+        });
+
+        Ok(access_points)
+    }
+
+    /// Scan for Wi-Fi networks
+    ///
+    /// Returns right away. The results are available from [``Self::access_points``] a few seconds
+    /// later.
+    async fn scan(&self) -> fdo::Result<()> {
+        for (_, wireless) in self.wifi_devices().await? {
+            wireless.request_scan(HashMap::new()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Connect to the known Wi-Fi network called `ssid`
+    ///
+    /// Only networks with a saved connection profile can be connected to, since this doesn't ask
+    /// for passwords. The Wi-Fi device which sees the network is used. If none does (e.g. for
+    /// hidden networks), the first Wi-Fi device is used.
+    async fn connect(&self, ssid: &str) -> fdo::Result<()> {
+        let known = self.known_networks().await?;
+
+        let connection = known
+            .get(ssid.as_bytes())
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("{ssid} isn't a known network")))?;
+
+        let devices = self.wifi_devices().await?;
+        let mut target = None;
+
+        'devices: for (device, wireless) in &devices {
+            for path in wireless.access_points().await? {
+                if self.access_point(path.clone()).await?.ssid().await? == ssid.as_bytes() {
+                    target = Some((device, path));
+                    break 'devices;
+                }
+            }
+        }
+
+        let (device, access_point) = match target {
+            Some((device, path)) => (device, path.into_inner()),
+            None => (
+                &devices
+                    .first()
+                    .ok_or_else(|| fdo::Error::Failed("There is no Wi-Fi device".to_string()))?
+                    .0,
+                ObjectPath::from_static_str_unchecked("/"),
+            ),
+        };
+
+        NetworkManagerProxy::new(&self.connection)
+            .await?
+            .activate_connection(connection, device.inner().path(), &access_point)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Disconnect from the current Wi-Fi network
+    ///
+    /// The network isn't connected to again automatically until [``Self::connect``] is used.
+    async fn disconnect(&self) -> fdo::Result<()> {
+        for (device, _) in self.wifi_devices().await? {
+            if is_set(&device.active_connection().await?) {
+                device.disconnect().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Enable or disable Wi-Fi
+    async fn set_wifi_enabled(&self, enabled: bool) -> fdo::Result<()> {
+        NetworkManagerProxy::new(&self.connection)
+            .await?
+            .set_wireless_enabled(enabled)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Enable or disable airplane mode, which disables both Wi-Fi and mobile broadband
+    ///
+    /// Bluetooth isn't affected, since it isn't managed by `NetworkManager`.
+    async fn set_airplane_mode(&self, enabled: bool) -> fdo::Result<()> {
+        let manager = NetworkManagerProxy::new(&self.connection).await?;
+
+        manager.set_wireless_enabled(!enabled).await?;
+        manager.set_wwan_enabled(!enabled).await?;
+
+        Ok(())
+    }
+}
+
+/// A Wi-Fi network, as returned by [``NetworkProxy::access_points``]
+#[derive(
+    Debug,
+    Clone,
+    PartialEq,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub struct AccessPointData {
+    /// SSID of the network
+    pub ssid: String,
+    /// Signal strength
+    pub strength: Percentage,
+    /// How the network is secured
+    pub security: Security,
+    /// If there is a saved connection profile for the network
+    ///
+    /// Only known networks can be connected to using [``NetworkProxy::connect``].
+    pub known: bool,
+    /// If the system is currently connected to the network
+    pub active: bool,
+}
+
+/// How a Wi-Fi network is secured
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub enum Security {
+    /// No password is needed
+    Open,
+    /// WEP, which is outdated and insecure
+    Wep,
+    /// WPA or WPA2 with a password
+    Wpa,
+    /// WPA3 with a password
+    Wpa3,
+    /// WPA or WPA3 Enterprise, which uses 802.1x authentication
+    Enterprise,
+}
+
+/// Helper function to get the [``Security``] of an access point from its flags
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NM80211ApSecurityFlags>
+const fn security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Security {
+    let key_management = wpa_flags | rsn_flags;
+
+    if key_management & KEY_MGMT_802_1X != 0 {
+        Security::Enterprise
+    } else if key_management & KEY_MGMT_SAE != 0 {
+        Security::Wpa3
+    } else if key_management & KEY_MGMT_PSK != 0 {
+        Security::Wpa
+    } else if flags & AP_FLAGS_PRIVACY != 0 {
+        Security::Wep
+    } else {
+        Security::Open
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{KEY_MGMT_802_1X, KEY_MGMT_PSK, KEY_MGMT_SAE, Security, security};
+
+    #[test]
+    fn detects_security() {
+        assert_eq!(security(0, 0, 0), Security::Open);
+        assert_eq!(security(1, 0, 0), Security::Wep);
+        assert_eq!(security(1, KEY_MGMT_PSK, 0), Security::Wpa);
+        assert_eq!(security(1, 0, KEY_MGMT_PSK | KEY_MGMT_SAE), Security::Wpa3);
+        assert_eq!(security(1, 0, KEY_MGMT_802_1X), Security::Enterprise);
+    }
+}
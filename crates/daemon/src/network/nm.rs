@@ -0,0 +1,252 @@
+//! Proxies for the `NetworkManager` dbus API
+//!
+//! Shared by [``super::Network``] and the network collector of
+//! [``crate::system_state::SystemState``].
+//!
+//! See: <https://networkmanager.dev/docs/api/latest/spec.html>
+use std::collections::HashMap;
+
+use zbus::{
+    proxy,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+};
+
+/// Dbus service name for `NetworkManager`
+pub const NM_SERVICE_NAME: &str = "org.freedesktop.NetworkManager";
+
+/// `NMDeviceType` of a Wi-Fi device
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMDeviceType>
+pub const WIFI_DEVICE_TYPE: u32 = 2;
+
+/// Helper function to check if an object path property is set
+///
+/// `NetworkManager` uses `/` for unset object paths.
+pub fn is_set(path: &OwnedObjectPath) -> bool {
+    path.as_str() != "/"
+}
+
+/// Proxy for the main `NetworkManager` object
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager",
+    gen_blocking = false
+)]
+pub trait NetworkManager {
+    /// All network devices
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// Activate the saved `connection` on `device`
+    ///
+    /// `specific_object` can be used to select an access point. `/` lets `NetworkManager` choose.
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    /// All active connections
+    #[zbus(property)]
+    fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// The connection used for the default route. `/` if there is none
+    #[zbus(property)]
+    fn primary_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// `NMConnectivityState` of the system
+    #[zbus(property)]
+    fn connectivity(&self) -> zbus::Result<u32>;
+
+    /// `NMMetered` state of the primary connection
+    #[zbus(property)]
+    fn metered(&self) -> zbus::Result<u32>;
+
+    /// If Wi-Fi is enabled
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> zbus::Result<bool>;
+
+    /// Enable or disable Wi-Fi
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    /// If mobile broadband is enabled
+    #[zbus(property)]
+    fn wwan_enabled(&self) -> zbus::Result<bool>;
+
+    /// Enable or disable mobile broadband
+    #[zbus(property)]
+    fn set_wwan_enabled(&self, value: bool) -> zbus::Result<()>;
+}
+
+/// Proxy for the saved connection profiles
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.Settings.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings",
+    gen_blocking = false
+)]
+pub trait Settings {
+    /// All saved connection profiles
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+/// Proxy for a saved connection profile
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.Settings.Connection.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait SettingsConnection {
+    /// The settings of the profile, grouped by setting name (e.g. `802-11-wireless`)
+    ///
+    /// Secrets (e.g. passwords) aren't included.
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+/// Proxy for an active connection
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.Connection.Active.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait ActiveConnection {
+    /// Name of the connection profile
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    /// Type of the connection profile (e.g. `802-11-wireless`)
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<String>;
+
+    /// Devices used by the connection
+    #[zbus(property)]
+    fn devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// If the connection is a VPN
+    #[zbus(property)]
+    fn vpn(&self) -> zbus::Result<bool>;
+
+    /// IPv4 configuration of the connection. `/` if there is none
+    #[zbus(property)]
+    fn ip4_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    /// IPv6 configuration of the connection. `/` if there is none
+    #[zbus(property)]
+    fn ip6_config(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// Proxy for a network device
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.Device.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait Device {
+    /// Disconnect the device and prevent it from automatically connecting again
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    /// Name of the network interface (e.g. `wlan0`)
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+
+    /// `NMDeviceType` of the device
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+
+    /// The active connection of the device. `/` if there is none
+    #[zbus(property)]
+    fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// Proxy for the Wi-Fi specific part of a network device
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.Device.Wireless.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait Wireless {
+    /// Scan for access points
+    ///
+    /// Returns right away. The results show up in [``WirelessProxy::access_points``] later.
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    /// All access points currently visible to the device
+    #[zbus(property)]
+    fn access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// The access point the device is connected to. `/` if there is none
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+/// Proxy for a Wi-Fi access point
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.AccessPoint.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait AccessPoint {
+    /// SSID of the network
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    /// Signal strength in percent (0 - 100)
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    /// `NM80211ApFlags` of the access point
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    /// `NM80211ApSecurityFlags` for WPA
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    /// `NM80211ApSecurityFlags` for WPA2 and WPA3 (aka. RSN)
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+/// Proxy for the IPv4 configuration of a connection
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.IP4Config.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.IP4Config",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait Ip4Config {
+    /// Addresses, each containing at least `address` and `prefix`
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+/// Proxy for the IPv6 configuration of a connection
+///
+/// See: <https://networkmanager.dev/docs/api/latest/gdbus-org.freedesktop.NetworkManager.IP6Config.html>
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.IP6Config",
+    default_service = "org.freedesktop.NetworkManager",
+    gen_blocking = false
+)]
+pub trait Ip6Config {
+    /// Addresses, each containing at least `address` and `prefix`
+    #[zbus(property)]
+    fn address_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
@@ -0,0 +1,667 @@
+//! This module contains items relating to desktop notifications
+//!
+//! The daemon acts as the notification server of the session (see [``server::Server``]), so no
+//! separate notification daemon (e.g. mako) is needed. Received notifications are kept in a
+//! history, which is shown by the notifications component and the bar.
+//!
+//! The main type is [``Notifications``]
+//!
+//! See: <https://specifications.freedesktop.org/notification-spec/latest/>
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use zbus::{
+    fdo, interface,
+    object_server::{InterfaceRef, SignalEmitter},
+    zvariant,
+};
+
+use common::config::notifications::NotificationsConfig;
+
+pub mod server;
+
+use server::{CloseReason, Server};
+
+/// Well-known name of the notification server
+pub const FDO_NAME: &str = "org.freedesktop.Notifications";
+
+/// Path the notification server is served at
+pub const FDO_PATH: &str = "/org/freedesktop/Notifications";
+
+/// Holds all notifications received by the [``server::Server``]
+///
+/// Notifications are shown as popups until they expire or are dismissed, or more than
+/// [``NotificationsConfig::max_popups``] newer popups are shown. Afterwards they stay in the
+/// history, until it is longer than [``NotificationsConfig::history``].
+///
+/// While do not disturb is active only critical notifications are shown as popups.
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``NotificationsProxy``]
+#[derive(Debug, Default)]
+pub struct Notifications {
+    /// All notifications, newest first
+    entries: VecDeque<Entry>,
+    /// Id of the last notification received
+    last_id: u32,
+    /// Incremented for every notification received
+    ///
+    /// Used to ignore expiry timers of notifications which have been replaced since.
+    serial: u64,
+    /// If do not disturb is active
+    dnd: bool,
+    /// The current config
+    config: NotificationsConfig,
+    /// Notifications closed by [``Self::trim``], which haven't been signaled yet
+    ///
+    /// See [``Self::changed``]
+    trimmed: Vec<(u32, CloseReason)>,
+}
+
+/// A [``Notification``] together with state which isn't exposed over dbus
+#[derive(Debug)]
+struct Entry {
+    /// The notification itself
+    notification: Notification,
+    /// See [``Notifications::serial``]
+    serial: u64,
+    /// If the notification hasn't been closed yet
+    ///
+    /// Closed notifications may stay in the history, but they are only closed once.
+    open: bool,
+    /// Hints changing how the notification is handled
+    hints: Hints,
+}
+
+/// Hints of a notification, which change how it is handled but aren't shown
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hints {
+    /// Remove the notification from the history once it is closed
+    pub transient: bool,
+    /// Keep the notification open after one of its actions was invoked
+    pub resident: bool,
+}
+
+/// A notification received by the [``server::Server``]
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct Notification {
+    /// Id of the notification, unique while the daemon is running
+    pub id: u32,
+    /// Name of the application which sent the notification
+    pub app_name: String,
+    /// Icon of the application, either an icon name or a `file://` URI
+    pub app_icon: String,
+    /// Single line overview of the notification
+    pub summary: String,
+    /// Detailed text of the notification
+    pub body: String,
+    /// Actions the user can invoke
+    ///
+    /// An action with the key [``Action::DEFAULT``] is invoked by clicking the notification.
+    pub actions: Vec<Action>,
+    /// How urgent the notification is
+    pub urgency: Urgency,
+    /// Image shown instead of the app icon (`image-data` hint)
+    pub image: zvariant::Optional<Image>,
+    /// Image shown instead of the app icon (`image-path` hint), either an icon name or a
+    /// `file://` URI
+    pub image_path: String,
+    /// When the notification was received, in seconds since the unix epoch
+    pub timestamp: u64,
+    /// If the notification is currently shown as a popup
+    pub popup: bool,
+    /// If the user has seen the notification
+    ///
+    /// See [``Notifications::mark_all_read``]
+    pub read: bool,
+}
+
+/// An action of a [``Notification``]
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct Action {
+    /// Sent back to the application when the action is invoked
+    pub key: String,
+    /// Shown to the user
+    pub label: String,
+}
+
+impl Action {
+    /// Key of the action invoked when clicking the notification
+    pub const DEFAULT: &str = "default";
+}
+
+/// How urgent a [``Notification``] is
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub enum Urgency {
+    /// E.g. a song change
+    Low,
+    /// Everything not low or critical
+    #[default]
+    Normal,
+    /// E.g. a low battery, shown until dismissed even if do not disturb is active
+    Critical,
+}
+
+impl From<u8> for Urgency {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Low,
+            2.. => Self::Critical,
+            1 => Self::Normal,
+        }
+    }
+}
+
+/// Raw image data sent with a [``Notification``]
+///
+/// The layout matches the `image-data` hint of the spec.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct Image {
+    /// Width of the image in pixels
+    pub width: i32,
+    /// Height of the image in pixels
+    pub height: i32,
+    /// Number of bytes per row in [``Self::data``]
+    pub rowstride: i32,
+    /// If every pixel has an alpha channel
+    pub has_alpha: bool,
+    /// Bits per channel, always 8
+    pub bits_per_sample: i32,
+    /// Number of channels, 4 if [``Self::has_alpha``] otherwise 3
+    pub channels: i32,
+    /// The pixels in RGB or RGBA byte order
+    pub data: Vec<u8>,
+}
+
+impl Notifications {
+    /// Set the internal [``NotificationsConfig``]
+    ///
+    /// For more information on the updating process see [``crate::config``]
+    pub fn set_config(&mut self, config: NotificationsConfig) {
+        self.config = config;
+        self.trim();
+    }
+
+    /// Add `notification` to the history, or replace the notification with the id
+    /// `replaces_id`, if it is still in the history
+    ///
+    /// The id and [``Notification::popup``] are set here. `expire_timeout` is the timeout in
+    /// milliseconds as sent by the application.
+    ///
+    /// Returns the id of the notification and, if it expires, the serial and timeout to pass
+    /// to [``Self::expire``] once the timeout has passed.
+    pub fn insert(
+        &mut self,
+        mut notification: Notification,
+        hints: Hints,
+        replaces_id: u32,
+        expire_timeout: i32,
+    ) -> (u32, Option<(u64, Duration)>) {
+        self.serial += 1;
+
+        let critical = notification.urgency == Urgency::Critical;
+
+        notification.popup = !self.dnd || critical;
+        notification.timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        notification.id = if let Some(position) = self.position(replaces_id) {
+            self.entries.remove(position);
+
+            replaces_id
+        } else {
+            self.last_id = self.last_id.checked_add(1).unwrap_or(1);
+
+            self.last_id
+        };
+
+        let id = notification.id;
+
+        self.entries.push_front(Entry {
+            notification,
+            serial: self.serial,
+            open: true,
+            hints,
+        });
+
+        self.trim();
+
+        let timeout = match u64::try_from(expire_timeout) {
+            Ok(0) => None,
+            Ok(timeout) => Some(timeout),
+            Err(_) if critical => None,
+            Err(_) => Some(self.config.timeout),
+        };
+
+        (
+            id,
+            timeout.map(|timeout| (self.serial, Duration::from_millis(timeout))),
+        )
+    }
+
+    /// Close the notification `id` because it expired, if it wasn't replaced since
+    ///
+    /// `serial` is the serial returned by [``Self::insert``].
+    ///
+    /// Returns if the notification was closed.
+    pub fn expire(&mut self, id: u32, serial: u64) -> bool {
+        let Some(position) = self.position(id) else {
+            return false;
+        };
+
+        if self.entries[position].serial != serial || !self.entries[position].open {
+            return false;
+        }
+
+        self.close(position, true);
+
+        true
+    }
+
+    /// Remove the notification `id` from the history
+    ///
+    /// Returns if the notification was still open.
+    pub fn remove(&mut self, id: u32) -> bool {
+        self.position(id)
+            .and_then(|position| self.entries.remove(position))
+            .is_some_and(|entry| entry.open)
+    }
+
+    /// Emit that the history has changed
+    ///
+    /// Notifications closed because of the limits in [``NotificationsConfig``] are signaled as
+    /// closed here as well.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signals fails.
+    pub async fn changed(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        for (id, reason) in std::mem::take(&mut self.trimmed) {
+            closed(emitter, id, reason).await?;
+        }
+
+        self.notifications_changed(emitter).await?;
+        self.unread_changed(emitter).await
+    }
+
+    /// Helper function to close the notification at `position`
+    ///
+    /// If `keep` isn't set, the notification is also removed from the history. Transient
+    /// notifications are always removed.
+    fn close(&mut self, position: usize, keep: bool) {
+        let entry = &mut self.entries[position];
+
+        entry.open = false;
+        entry.notification.popup = false;
+
+        if !keep || entry.hints.transient {
+            self.entries.remove(position);
+        }
+    }
+
+    /// Helper function to get the position of the notification `id` in [``Self::entries``]
+    fn position(&self, id: u32) -> Option<usize> {
+        if id == 0 {
+            return None;
+        }
+
+        self.entries.iter().position(|e| e.notification.id == id)
+    }
+
+    /// Helper function to keep the history within the limits of the [``NotificationsConfig``]
+    ///
+    /// Only the newest [``NotificationsConfig::max_popups``] popups are kept. Then the oldest
+    /// notifications which aren't popups are removed, until the history is no longer than
+    /// [``NotificationsConfig::history``]. If only popups are left (e.g. because the history is
+    /// shorter than `max_popups`), the oldest popups are removed as well.
+    ///
+    /// Hidden popups are closed as expired, removed notifications which were still open with an
+    /// undefined reason. Both are kept in [``Self::trimmed``] until they are signaled.
+    fn trim(&mut self) {
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|e| e.notification.popup)
+            .skip(self.config.max_popups)
+        {
+            entry.notification.popup = false;
+
+            if entry.open {
+                entry.open = false;
+                self.trimmed
+                    .push((entry.notification.id, CloseReason::Expired));
+            }
+        }
+
+        while self.entries.len() > self.config.history {
+            let position = self
+                .entries
+                .iter()
+                .rposition(|e| !e.notification.popup)
+                .unwrap_or(self.entries.len() - 1);
+
+            if let Some(entry) = self.entries.remove(position)
+                && entry.open
+            {
+                self.trimmed
+                    .push((entry.notification.id, CloseReason::Undefined));
+            }
+        }
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Notifications",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Notifications {
+    /// Dbus property to get all notifications in the history, newest first
+    #[zbus(property)]
+    fn notifications(&self) -> Vec<Notification> {
+        self.entries
+            .iter()
+            .map(|e| e.notification.clone())
+            .collect()
+    }
+
+    /// Dbus property for the number of notifications the user hasn't seen
+    #[zbus(property)]
+    fn unread(&self) -> u32 {
+        u32::try_from(self.entries.iter().filter(|e| !e.notification.read).count())
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Dbus property for if do not disturb is active
+    #[zbus(property)]
+    const fn dnd(&self) -> bool {
+        self.dnd
+    }
+
+    /// Dbus property to set do not disturb
+    #[zbus(property)]
+    const fn set_dnd(&mut self, dnd: bool) {
+        self.dnd = dnd;
+    }
+
+    /// Dismiss the notification `id`, removing it from the history
+    async fn dismiss(
+        &mut self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let Some(position) = self.position(id) else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "There is no notification with id {id}"
+            )));
+        };
+
+        let open = self.entries[position].open;
+
+        self.close(position, false);
+
+        if open {
+            closed(&emitter, id, CloseReason::Dismissed).await?;
+        }
+
+        Ok(self.changed(&emitter).await?)
+    }
+
+    /// Dismiss all notifications, clearing the history
+    async fn dismiss_all(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        for entry in std::mem::take(&mut self.entries) {
+            if entry.open {
+                closed(&emitter, entry.notification.id, CloseReason::Dismissed).await?;
+            }
+        }
+
+        Ok(self.changed(&emitter).await?)
+    }
+
+    /// Hide the popup of the notification `id`, keeping it in the history
+    async fn hide_popup(
+        &mut self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let Some(position) = self.position(id) else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "There is no notification with id {id}"
+            )));
+        };
+
+        let open = self.entries[position].open;
+
+        self.entries[position].notification.read = true;
+        self.close(position, true);
+
+        if open {
+            closed(&emitter, id, CloseReason::Dismissed).await?;
+        }
+
+        Ok(self.changed(&emitter).await?)
+    }
+
+    /// Invoke the action `key` of the notification `id`
+    ///
+    /// Afterwards the notification is dismissed, unless it has the `resident` hint.
+    async fn invoke_action(
+        &mut self,
+        id: u32,
+        key: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let Some(position) = self.position(id) else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "There is no notification with id {id}"
+            )));
+        };
+
+        let entry = &mut self.entries[position];
+
+        if !entry.notification.actions.iter().any(|a| a.key == key) {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Notification {id} has no action {key}"
+            )));
+        }
+
+        entry.notification.read = true;
+
+        Server::action_invoked(&fdo_emitter(&emitter)?, id, key).await?;
+
+        if !entry.hints.resident {
+            let open = entry.open;
+
+            self.close(position, false);
+
+            if open {
+                closed(&emitter, id, CloseReason::Dismissed).await?;
+            }
+        }
+
+        Ok(self.changed(&emitter).await?)
+    }
+
+    /// Mark all notifications as read
+    async fn mark_all_read(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        for entry in &mut self.entries {
+            entry.notification.read = true;
+        }
+
+        Ok(self.changed(&emitter).await?)
+    }
+}
+
+/// Close the notification `id` after `timeout`, unless it was replaced since
+///
+/// `serial` is the serial returned by [``Notifications::insert``].
+pub fn expire_after(iface: InterfaceRef<Notifications>, id: u32, serial: u64, timeout: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(timeout).await;
+
+        let mut notifications = iface.get_mut().await;
+
+        if !notifications.expire(id, serial) {
+            return;
+        }
+
+        let emitter = iface.signal_emitter();
+
+        if let Err(e) = closed(emitter, id, CloseReason::Expired).await {
+            log::error!("Failed to signal that notification {id} expired: {e}");
+        }
+
+        if let Err(e) = notifications.changed(emitter).await {
+            log::error!("Failed to signal notifications changed: {e}");
+        }
+    });
+}
+
+/// Helper function to emit [``Server::NOTIFICATION_CLOSED``]
+///
+/// `emitter` can be for any path on the connection the server is served on.
+async fn closed(emitter: &SignalEmitter<'_>, id: u32, reason: CloseReason) -> zbus::Result<()> {
+    Server::notification_closed(&fdo_emitter(emitter)?, id, reason).await
+}
+
+/// Helper function to get an emitter for signals of the [``server::Server``]
+fn fdo_emitter(emitter: &SignalEmitter<'_>) -> zbus::Result<SignalEmitter<'static>> {
+    SignalEmitter::new(emitter.connection(), FDO_PATH)
+}
+
+#[cfg(test)]
+mod test {
+    use common::config::notifications::NotificationsConfig;
+
+    use super::{CloseReason, Hints, Notification, Notifications, Urgency};
+
+    #[test]
+    fn replaces_and_expires() {
+        let mut notifications = Notifications::default();
+
+        notifications.set_config(NotificationsConfig {
+            history: 2,
+            ..Default::default()
+        });
+
+        let (first, first_expiry) =
+            notifications.insert(Notification::default(), Hints::default(), 0, -1);
+        let (_, first_expiry) = first_expiry.unwrap();
+
+        assert_eq!(first_expiry.as_millis(), 5000);
+
+        let replaced = Notification {
+            summary: "replaced".to_string(),
+            ..Default::default()
+        };
+
+        let (id, expiry) = notifications.insert(replaced, Hints::default(), first, 1000);
+        let (serial, timeout) = expiry.unwrap();
+
+        assert_eq!(id, first);
+        assert_eq!(timeout.as_millis(), 1000);
+        assert_eq!(notifications.notifications()[0].summary, "replaced");
+
+        // The timer of the replaced notification is ignored
+        assert!(!notifications.expire(id, serial - 1));
+        assert!(notifications.expire(id, serial));
+        assert!(!notifications.notifications()[0].popup);
+
+        let critical = Notification {
+            urgency: Urgency::Critical,
+            ..Default::default()
+        };
+
+        assert!(
+            notifications
+                .insert(critical, Hints::default(), 0, -1)
+                .1
+                .is_none()
+        );
+
+        notifications.insert(Notification::default(), Hints::default(), 0, 0);
+
+        // The expired notification is removed first, since the history is limited to 2
+        assert_eq!(notifications.notifications().len(), 2);
+        assert!(notifications.notifications().iter().all(|n| n.id != id));
+        assert_eq!(notifications.unread(), 2);
+    }
+
+    #[test]
+    fn caps_popups() {
+        let mut notifications = Notifications::default();
+
+        notifications.set_config(NotificationsConfig {
+            max_popups: 2,
+            ..Default::default()
+        });
+
+        let (oldest, _) = notifications.insert(Notification::default(), Hints::default(), 0, 0);
+
+        for _ in 0..2 {
+            notifications.insert(Notification::default(), Hints::default(), 0, 0);
+        }
+
+        let popups: Vec<bool> = notifications
+            .notifications()
+            .iter()
+            .map(|n| n.popup)
+            .collect();
+
+        assert_eq!(popups, [true, true, false]);
+        assert_eq!(notifications.trimmed, [(oldest, CloseReason::Expired)]);
+    }
+
+    #[test]
+    fn limits_history_of_popups() {
+        let mut notifications = Notifications::default();
+
+        notifications.set_config(NotificationsConfig {
+            history: 1,
+            max_popups: 5,
+            ..Default::default()
+        });
+
+        let (oldest, _) = notifications.insert(Notification::default(), Hints::default(), 0, 0);
+        let (newest, _) = notifications.insert(Notification::default(), Hints::default(), 0, 0);
+
+        assert_eq!(notifications.notifications().len(), 1);
+        assert_eq!(notifications.notifications()[0].id, newest);
+        assert_eq!(notifications.trimmed, [(oldest, CloseReason::Undefined)]);
+    }
+}
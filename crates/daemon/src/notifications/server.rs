@@ -0,0 +1,267 @@
+//! The notification server, implementing the `org.freedesktop.Notifications` dbus interface
+//!
+//! See: <https://specifications.freedesktop.org/notification-spec/latest/protocol.html>
+use std::collections::HashMap;
+
+use zbus::{
+    fdo, interface,
+    object_server::{Interface, InterfaceRef, SignalEmitter},
+    zvariant::OwnedValue,
+};
+
+use super::{Action, Hints, Image, Notification, Notifications, expire_after};
+
+/// Receives notifications from applications
+///
+/// All notifications are kept by [``Notifications``], this only translates between it and the
+/// notification spec. It is served at [``super::FDO_PATH``] under the name [``super::FDO_NAME``].
+pub struct Server {
+    /// Where the notifications are kept
+    notifications: InterfaceRef<Notifications>,
+}
+
+/// Why a notification was closed, as defined by the spec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    /// The notification expired
+    Expired = 1,
+    /// The notification was dismissed by the user
+    Dismissed = 2,
+    /// The notification was closed by a call to `CloseNotification`
+    Closed = 3,
+    /// The notification was closed for another reason (e.g. because the history is full)
+    Undefined = 4,
+}
+
+impl Server {
+    /// Name of the dbus signal emitted when a notification is closed
+    ///
+    /// The signal has the id of the notification and the [``CloseReason``] as arguments.
+    pub const NOTIFICATION_CLOSED: &str = "NotificationClosed";
+
+    /// Name of the dbus signal emitted when the user invokes an action of a notification
+    ///
+    /// The signal has the id of the notification and the key of the action as arguments.
+    pub const ACTION_INVOKED: &str = "ActionInvoked";
+
+    /// Optional features of the spec which are supported
+    const CAPABILITIES: [&str; 4] = ["actions", "body", "icon-static", "persistence"];
+
+    /// Create a new [``Server``]
+    #[must_use]
+    pub const fn new(notifications: InterfaceRef<Notifications>) -> Self {
+        Self { notifications }
+    }
+
+    /// Emit the [``Self::NOTIFICATION_CLOSED``] dbus signal
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: CloseReason,
+    ) -> zbus::Result<()> {
+        emitter
+            .emit(
+                Self::name(),
+                Self::NOTIFICATION_CLOSED,
+                &(id, reason as u32),
+            )
+            .await
+    }
+
+    /// Emit the [``Self::ACTION_INVOKED``] dbus signal
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        key: &str,
+    ) -> zbus::Result<()> {
+        emitter
+            .emit(Self::name(), Self::ACTION_INVOKED, &(id, key))
+            .await
+    }
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl Server {
+    /// Show a notification
+    ///
+    /// If `replaces_id` is the id of an existing notification, that notification is replaced.
+    /// `actions` is a list of alternating keys and labels.
+    ///
+    /// Returns the id of the notification.
+    #[allow(
+        clippy::too_many_arguments,
+        reason = "Defined by the notification spec."
+    )]
+    async fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> fdo::Result<u32> {
+        let mut notification = Notification {
+            app_name,
+            app_icon,
+            summary,
+            body,
+            actions: actions
+                .chunks_exact(2)
+                .map(|action| Action {
+                    key: action[0].clone(),
+                    label: action[1].clone(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let hints = parse_hints(&mut notification, &hints);
+
+        log::debug!(
+            "Received notification from {}: {}",
+            notification.app_name,
+            notification.summary
+        );
+
+        let mut notifications = self.notifications.get_mut().await;
+
+        let (id, expiry) = notifications.insert(notification, hints, replaces_id, expire_timeout);
+
+        notifications
+            .changed(self.notifications.signal_emitter())
+            .await?;
+
+        if let Some((serial, timeout)) = expiry {
+            expire_after(self.notifications.clone(), id, serial, timeout);
+        }
+
+        Ok(id)
+    }
+
+    /// Close the notification `id` and remove it from the history
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let mut notifications = self.notifications.get_mut().await;
+
+        if notifications.remove(id) {
+            Self::notification_closed(&emitter, id, CloseReason::Closed).await?;
+        }
+
+        notifications
+            .changed(self.notifications.signal_emitter())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the optional features of the spec which are supported
+    #[allow(
+        clippy::unused_self,
+        reason = "The capabilities are constant, but zbus methods need self."
+    )]
+    fn get_capabilities(&self) -> Vec<String> {
+        Self::CAPABILITIES.map(String::from).to_vec()
+    }
+
+    /// Get the name, vendor and version of the server and the version of the spec
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    #[allow(
+        clippy::unused_self,
+        reason = "The information is constant, but zbus methods need self."
+    )]
+    fn get_server_information(&self) -> (String, String, String, String) {
+        (
+            "dod-shell".to_string(),
+            "dod-101".to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+            "1.2".to_string(),
+        )
+    }
+}
+
+/// Helper function to set the fields of `notification` which are sent as `hints`
+///
+/// Returns the hints which change how the notification is handled.
+fn parse_hints(notification: &mut Notification, hints: &HashMap<String, OwnedValue>) -> Hints {
+    // Older versions of the spec use different names for some hints
+    let hint = |names: &[&str]| names.iter().find_map(|name| hints.get(*name));
+    let flag = |name: &str| hints.get(name).and_then(|v| bool::try_from(v).ok());
+
+    if let Some(urgency) = hint(&["urgency"]).and_then(|v| u8::try_from(v).ok()) {
+        notification.urgency = urgency.into();
+    }
+
+    notification.image = hint(&["image-data", "image_data", "icon_data"])
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| Image::try_from(v).ok())
+        .into();
+
+    notification.image_path = hint(&["image-path", "image_path"])
+        .and_then(|v| <&str>::try_from(v).ok())
+        .unwrap_or_default()
+        .to_string();
+
+    Hints {
+        transient: flag("transient").unwrap_or_default(),
+        resident: flag("resident").unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::parse_hints;
+    use crate::notifications::{Image, Notification, Urgency};
+
+    #[test]
+    fn parses_hints() {
+        let image = Image {
+            width: 1,
+            height: 1,
+            rowstride: 4,
+            has_alpha: true,
+            bits_per_sample: 8,
+            channels: 4,
+            data: vec![255, 0, 0, 255],
+        };
+
+        let hints: HashMap<String, OwnedValue> = HashMap::from([
+            ("urgency".to_string(), Value::from(2_u8).try_into().unwrap()),
+            ("image_data".to_string(), image.clone().try_into().unwrap()),
+            (
+                "image-path".to_string(),
+                Value::from("dialog-information").try_into().unwrap(),
+            ),
+            (
+                "resident".to_string(),
+                Value::from(true).try_into().unwrap(),
+            ),
+        ]);
+
+        let mut notification = Notification::default();
+        let parsed = parse_hints(&mut notification, &hints);
+
+        assert_eq!(notification.urgency, Urgency::Critical);
+        assert_eq!(*notification.image, Some(image));
+        assert_eq!(notification.image_path, "dialog-information");
+        assert!(parsed.resident);
+        assert!(!parsed.transient);
+    }
+}
@@ -88,11 +88,12 @@ impl Playback {
 
 impl Playback {
     /// Update playback related state
-    #[allow(clippy::missing_panics_doc, reason = "See expect msg.")]
+    ///
+    /// The media players are found using the session bus connection of `ctxt`.
     pub async fn update(&mut self, ctxt: &zbus::object_server::SignalEmitter<'_>) {
-        let Ok(media_players)  = media_player::MediaPlayer::new_all(&zbus::Connection::session().await.expect("Shouldn't fail to connect to system dbus, since we have interacted with dbus before this point already.")).await else {
+        let Ok(media_players) = media_player::MediaPlayer::new_all(ctxt.connection()).await else {
             log::error!("Failed to find media players.");
-                return;
+            return;
         };
 
         let players: Vec<Player> = stream::iter(media_players)
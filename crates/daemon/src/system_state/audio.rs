@@ -0,0 +1,39 @@
+//! Collector for the audio devices and streams
+//!
+//! Instead of polling, the audio setup is pushed by [``crate::audio::Audio``] whenever it changes.
+use std::time::Duration;
+
+use common::types::Timer;
+use tokio::sync::watch;
+use zbus::object_server::InterfaceRef;
+
+use super::{AudioData, SystemState, VolumeState, replace, update_data};
+
+/// Keep [``super::SystemStateData::audio``] and [``super::SystemStateData::volume``] up to date
+///
+/// See [``crate::audio::Audio::audio``]
+pub(super) async fn run(iface: InterfaceRef<SystemState>, mut audio: watch::Receiver<AudioData>) {
+    loop {
+        if audio.changed().await.is_err() {
+            log::error!("PulseAudio thread stopped, audio will no longer be updated");
+
+            update_data(&iface, |data| {
+                replace(&mut data.volume, VolumeState::default())
+                    | replace(&mut data.audio, AudioData::default())
+            })
+            .await;
+
+            return;
+        }
+
+        let _timer = Timer::new("SystemState audio updated", Some(Duration::from_millis(2)));
+
+        let audio = audio.borrow_and_update().clone();
+        let volume = VolumeState::from(audio.default_sink());
+
+        update_data(&iface, |data| {
+            replace(&mut data.volume, volume) | replace(&mut data.audio, audio)
+        })
+        .await;
+    }
+}
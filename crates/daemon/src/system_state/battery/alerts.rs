@@ -0,0 +1,158 @@
+//! Actions taken when the main battery becomes low or critical
+//!
+//! See [``common::config::battery::BatteryConfig``]
+use std::{collections::HashMap, fmt::Write};
+
+use tokio::process::Command;
+use zbus::{object_server::InterfaceRef, proxy, zvariant};
+
+use common::config::battery::BatteryConfig;
+
+use super::{BatteryData, SystemState};
+use crate::system_state::{BatteryLevel, BatteryStatus};
+
+/// Proxy for sending desktop notifications
+///
+/// See: <https://specifications.freedesktop.org/notification-spec/latest/protocol.html>
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications",
+    gen_blocking = false
+)]
+trait Notifications {
+    /// Send a notification
+    ///
+    /// Returns the id of the notification, which can be passed as `replaces_id` to replace it.
+    #[allow(
+        clippy::too_many_arguments,
+        reason = "Defined by the notification spec."
+    )]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, zvariant::Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Keeps track of the alerts sent for the main battery
+///
+/// Each level only alerts once per discharge. Plugging the system in resets this.
+#[derive(Debug, Default)]
+pub(super) struct Alerts {
+    /// Level of the main battery the last time [``Self::update``] was called while discharging
+    level: BatteryLevel,
+    /// Id of the last notification sent, so a critical notification replaces the low one
+    notification_id: u32,
+}
+
+impl Alerts {
+    /// Alert if `battery` became low or critical since the last call
+    pub(super) async fn update(
+        &mut self,
+        iface: &InterfaceRef<SystemState>,
+        battery: Option<&BatteryData>,
+        config: &BatteryConfig,
+    ) {
+        let Some(battery) = battery.filter(|b| b.status == BatteryStatus::Discharging) else {
+            self.level = BatteryLevel::Normal;
+            return;
+        };
+
+        let previous = std::mem::replace(&mut self.level, battery.level);
+
+        if battery.level <= previous {
+            return;
+        }
+
+        log::info!(
+            "Battery {} is at {} ({:?})",
+            battery.name,
+            battery.charge,
+            battery.level
+        );
+
+        if let Err(e) =
+            SystemState::battery_level_reached(iface.signal_emitter(), &battery.name, battery.level)
+                .await
+        {
+            log::error!("Failed to signal battery level: {e}");
+        }
+
+        if config.notify {
+            self.notify(iface.signal_emitter().connection(), battery)
+                .await
+                .unwrap_or_else(|e| log::error!("Failed to send battery notification: {e}"));
+        }
+
+        if battery.level == BatteryLevel::Critical
+            && let Some(command) = &config.critical_command
+        {
+            run_command(command);
+        }
+    }
+
+    /// Send a desktop notification about `battery`
+    ///
+    /// `connection` has to be a connection to the session bus.
+    async fn notify(
+        &mut self,
+        connection: &zbus::Connection,
+        battery: &BatteryData,
+    ) -> zbus::Result<()> {
+        let proxy = NotificationsProxy::new(connection).await?;
+
+        let (summary, icon, urgency) = match battery.level {
+            BatteryLevel::Critical => ("Battery critical", "battery-caution", 2_u8),
+            _ => ("Battery low", "battery-low", 1_u8),
+        };
+
+        let mut body = format!("{} remaining", battery.charge);
+
+        if battery.time_to_empty > 0 {
+            let minutes = battery.time_to_empty / 60;
+            let _ = write!(body, " ({}h {:02}min)", minutes / 60, minutes % 60);
+        }
+
+        self.notification_id = proxy
+            .notify(
+                "dod-shell",
+                self.notification_id,
+                icon,
+                summary,
+                &body,
+                &[],
+                HashMap::from([("urgency", zvariant::Value::from(urgency))]),
+                -1,
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Helper function to run [``BatteryConfig::critical_command``]
+fn run_command(command: &str) {
+    log::info!("Running critical battery command: {command}");
+
+    match Command::new("sh").arg("-c").arg(command).spawn() {
+        Ok(mut child) => {
+            tokio::spawn(async move {
+                match child.wait().await {
+                    Ok(status) if !status.success() => {
+                        log::error!("Critical battery command exited with {status}");
+                    }
+                    Ok(_) => (),
+                    Err(e) => log::error!("Failed to wait for critical battery command: {e}"),
+                }
+            });
+        }
+        Err(e) => log::error!("Failed to run critical battery command: {e}"),
+    }
+}
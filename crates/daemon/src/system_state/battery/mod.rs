@@ -0,0 +1,110 @@
+//! Collector for batteries
+//!
+//! Batteries are discovered using `UPower`, which also knows about the batteries of peripherals
+//! (e.g. a bluetooth mouse). If `UPower` isn't available [``POWER_SUPPLY_PATH``] is read instead.
+use std::{path::Path, time::Duration};
+
+use common::types::Timer;
+use zbus::{object_server::InterfaceRef, zvariant};
+
+use super::{
+    BatteryData, BatteryKind, BatteryLevel, POWER_SUPPLY_PATH, SystemState, interval, replace,
+    update_data,
+};
+
+mod alerts;
+mod sysfs;
+mod upower;
+
+/// How often the batteries are refreshed
+const REFRESH_RATE: Duration = Duration::from_secs(10);
+
+/// Information about all batteries and power sources
+#[derive(Debug, Default)]
+struct Batteries {
+    /// See [``super::SystemStateData::batteries``]
+    batteries: Vec<BatteryData>,
+    /// See [``super::SystemStateData::ac_online``]
+    ac_online: bool,
+}
+
+/// Keep [``super::SystemStateData::battery``], [``super::SystemStateData::batteries``] and
+/// [``super::SystemStateData::ac_online``] up to date
+///
+/// Also takes the actions configured in [``common::config::battery::BatteryConfig``] once the main
+/// battery becomes low or critical.
+///
+/// `connection` has to be a connection to the system bus.
+pub(super) async fn run(iface: InterfaceRef<SystemState>, connection: zbus::Connection) {
+    let mut upower = upower::UPower::new(&connection)
+        .await
+        .inspect_err(|e| {
+            log::warn!("UPower isn't available, reading {POWER_SUPPLY_PATH} instead: {e}");
+        })
+        .ok();
+
+    let mut interval = interval(REFRESH_RATE);
+    let mut alerts = alerts::Alerts::default();
+
+    loop {
+        interval.tick().await;
+
+        let _timer = Timer::new(
+            "SystemState battery updated",
+            Some(Duration::from_millis(5)),
+        );
+
+        let batteries = match &mut upower {
+            Some(upower) => upower.batteries().await,
+            None => sysfs::batteries().await,
+        };
+
+        let mut batteries = match batteries {
+            Ok(batteries) => batteries,
+            Err(e) => {
+                log::error!("Failed to update battery information: {e}");
+                continue;
+            }
+        };
+
+        let (configured, config) = {
+            let state = iface.get().await;
+            (
+                state.config.bar.battery.clone(),
+                state.config.battery.clone(),
+            )
+        };
+
+        for battery in &mut batteries.batteries {
+            battery.level = BatteryLevel::new(battery.charge, &config);
+        }
+
+        let battery = main_battery(&batteries.batteries, configured.as_deref());
+
+        alerts.update(&iface, battery.as_ref(), &config).await;
+
+        update_data(&iface, |data| {
+            replace(&mut data.battery, zvariant::Optional::from(battery))
+                | replace(&mut data.batteries, batteries.batteries)
+                | replace(&mut data.ac_online, batteries.ac_online)
+        })
+        .await;
+    }
+}
+
+/// The battery shown by default
+///
+/// `configured` may be a name (e.g. `BAT0`) or a path in [``POWER_SUPPLY_PATH``].
+///
+/// See [``super::SystemStateData::battery``]
+fn main_battery(batteries: &[BatteryData], configured: Option<&Path>) -> Option<BatteryData> {
+    configured
+        .map_or_else(
+            || batteries.iter().find(|b| b.kind == BatteryKind::System),
+            |configured| {
+                let name = configured.file_name().unwrap_or(configured.as_os_str());
+                batteries.iter().find(|b| name == b.name.as_str())
+            },
+        )
+        .cloned()
+}
@@ -0,0 +1,196 @@
+//! Reading batteries from [``POWER_SUPPLY_PATH``]
+//!
+//! Used if `UPower` isn't available.
+//!
+//! See: <https://www.kernel.org/doc/html/latest/power/power_supply_class.html>
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tokio::fs;
+
+use common::types::Percentage;
+
+use super::{Batteries, BatteryData, BatteryKind, POWER_SUPPLY_PATH};
+use crate::{
+    system_state::{BatteryLevel, BatteryStatus},
+    util::read_value,
+};
+
+/// Get information about all batteries in [``POWER_SUPPLY_PATH``]
+pub(super) async fn batteries() -> Result<Batteries> {
+    let mut entries = fs::read_dir(POWER_SUPPLY_PATH).await?;
+    let mut batteries = Batteries::default();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        match read_value::<String>(&path, "type").await.as_deref() {
+            Some("Battery") => match read_battery(&path).await {
+                Ok(battery) => batteries.batteries.push(battery),
+                Err(e) => log::warn!("Failed to read battery {}: {e}", path.display()),
+            },
+            Some("Mains" | "USB") => {
+                batteries.ac_online |= read_value::<u8>(&path, "online").await == Some(1);
+            }
+            _ => (),
+        }
+    }
+
+    batteries
+        .batteries
+        .sort_unstable_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(batteries)
+}
+
+/// Get information about the battery at `path`
+#[allow(clippy::cast_sign_loss, reason = "The times are never negative.")]
+async fn read_battery(path: &Path) -> Result<BatteryData> {
+    let charge: u8 = read_value(path, "capacity")
+        .await
+        .context("capacity is missing or isn't a number")?;
+
+    let status: BatteryStatus = read_value::<String>(path, "status")
+        .await
+        .as_deref()
+        .unwrap_or_default()
+        .into();
+
+    // Batteries either report energy (µWh & µW) or charge (µAh & µA)
+    let (now, full, full_design, rate) =
+        if let Some(now) = read_value::<f64>(path, "energy_now").await {
+            (
+                Some(now),
+                read_value::<f64>(path, "energy_full").await,
+                read_value::<f64>(path, "energy_full_design").await,
+                read_value::<f64>(path, "power_now").await.map(f64::abs),
+            )
+        } else {
+            (
+                read_value::<f64>(path, "charge_now").await,
+                read_value::<f64>(path, "charge_full").await,
+                read_value::<f64>(path, "charge_full_design").await,
+                read_value::<f64>(path, "current_now").await.map(f64::abs),
+            )
+        };
+
+    let energy_rate = if read_value::<f64>(path, "power_now").await.is_some() {
+        rate.map(|rate| rate / 1e6)
+    } else {
+        read_value::<f64>(path, "voltage_now")
+            .await
+            .zip(rate)
+            .map(|(voltage, current)| voltage * current / 1e12)
+    };
+
+    // Hours until `amount` is reached with the current rate
+    let hours_until = |amount: Option<f64>| {
+        amount
+            .zip(rate)
+            .filter(|(_, rate)| *rate > 0.0)
+            .map_or(0, |(amount, rate)| (amount / rate * 3600.0) as u64)
+    };
+
+    let kind = match read_value::<String>(path, "scope").await.as_deref() {
+        Some("Device") => BatteryKind::Other,
+        _ => BatteryKind::System,
+    };
+
+    Ok(BatteryData {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        model: read_value(path, "model_name").await.unwrap_or_default(),
+        kind,
+        charge: charge.into(),
+        time_to_empty: if status == BatteryStatus::Discharging {
+            hours_until(now)
+        } else {
+            0
+        },
+        time_to_full: if status == BatteryStatus::Charging {
+            hours_until(full.zip(now).map(|(full, now)| full - now))
+        } else {
+            0
+        },
+        status,
+        energy_rate: energy_rate.unwrap_or_default(),
+        health: full
+            .zip(full_design)
+            .filter(|(_, design)| *design > 0.0)
+            .map_or_else(Percentage::default, |(full, design)| {
+                Percentage::from(full / design)
+            }),
+        // Set by `super::run`, which knows the config
+        level: BatteryLevel::default(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_battery;
+    use crate::{system_state::BatteryStatus, util::sysfs_dir};
+
+    #[tokio::test]
+    #[allow(
+        clippy::float_cmp,
+        reason = "The values are exactly representable and tested to compare equal."
+    )]
+    async fn reads_battery() {
+        let dir = sysfs_dir(&[
+            ("BAT0/capacity", "50\n"),
+            ("BAT0/status", "Discharging\n"),
+            ("BAT0/energy_now", "25000000\n"),
+            ("BAT0/energy_full", "50000000\n"),
+            ("BAT0/energy_full_design", "62500000\n"),
+            ("BAT0/power_now", "12500000\n"),
+        ]);
+
+        let data = read_battery(&dir.path().join("BAT0")).await.unwrap();
+
+        assert_eq!(data.name, "BAT0");
+        assert_eq!(data.status, BatteryStatus::Discharging);
+        assert_eq!(*data.charge, 0.5);
+        assert_eq!(data.time_to_empty, 2 * 3600);
+        assert_eq!(data.energy_rate, 12.5);
+        assert_eq!(*data.health, 0.8);
+    }
+
+    #[tokio::test]
+    #[allow(
+        clippy::float_cmp,
+        reason = "The values are exactly representable and tested to compare equal."
+    )]
+    async fn reads_charge_based_battery() {
+        let dir = sysfs_dir(&[
+            ("BAT0/capacity", "50\n"),
+            ("BAT0/status", "Charging\n"),
+            ("BAT0/charge_now", "2000000\n"),
+            ("BAT0/charge_full", "4000000\n"),
+            ("BAT0/current_now", "1000000\n"),
+            ("BAT0/voltage_now", "12000000\n"),
+        ]);
+
+        let data = read_battery(&dir.path().join("BAT0")).await.unwrap();
+
+        assert_eq!(data.time_to_full, 2 * 3600);
+        assert_eq!(data.time_to_empty, 0);
+        assert_eq!(data.energy_rate, 12.0);
+        assert_eq!(*data.health, 0.0);
+    }
+
+    #[tokio::test]
+    async fn missing_capacity_fails() {
+        let dir = sysfs_dir(&[("BAT0/status", "Discharging\n")]);
+
+        assert!(read_battery(&dir.path().join("BAT0")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unparsable_capacity_fails() {
+        let dir = sysfs_dir(&[("BAT0/capacity", "unknown\n")]);
+
+        assert!(read_battery(&dir.path().join("BAT0")).await.is_err());
+    }
+}
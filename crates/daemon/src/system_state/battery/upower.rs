@@ -0,0 +1,210 @@
+//! Reading batteries using `UPower`
+//!
+//! See: <https://upower.freedesktop.org/docs/>
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Result;
+use zbus::{proxy, zvariant::OwnedObjectPath};
+
+use common::types::Percentage;
+
+use super::{Batteries, BatteryData, BatteryKind};
+use crate::system_state::{BatteryLevel, BatteryStatus};
+
+/// `UPower` device type of line power (e.g. an AC adapter)
+///
+/// See: <https://upower.freedesktop.org/docs/Device.html#Device:Type>
+const LINE_POWER_TYPE: u32 = 1;
+
+/// Proxy for the main `UPower` object
+#[proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower",
+    gen_blocking = false
+)]
+trait UPowerManager {
+    /// All devices known to `UPower`
+    fn enumerate_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    /// If the system is running on battery power
+    #[zbus(property)]
+    fn on_battery(&self) -> zbus::Result<bool>;
+}
+
+/// Proxy for a single `UPower` device
+///
+/// See: <https://upower.freedesktop.org/docs/Device.html>
+#[proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower",
+    gen_blocking = false
+)]
+trait Device {
+    /// Name of the device used by the kernel
+    #[zbus(property)]
+    fn native_path(&self) -> zbus::Result<String>;
+
+    /// Model of the device
+    #[zbus(property)]
+    fn model(&self) -> zbus::Result<String>;
+
+    /// What kind of device this is
+    #[zbus(property, name = "Type")]
+    fn kind(&self) -> zbus::Result<u32>;
+
+    /// If the device powers the system
+    #[zbus(property)]
+    fn power_supply(&self) -> zbus::Result<bool>;
+
+    /// If the battery is present
+    #[zbus(property)]
+    fn is_present(&self) -> zbus::Result<bool>;
+
+    /// Charge in percent (0 - 100)
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<f64>;
+
+    /// What the battery is currently doing
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    /// Seconds until the battery is empty
+    #[zbus(property)]
+    fn time_to_empty(&self) -> zbus::Result<i64>;
+
+    /// Seconds until the battery is full
+    #[zbus(property)]
+    fn time_to_full(&self) -> zbus::Result<i64>;
+
+    /// Rate at which energy is drawn from (or put into) the battery in W
+    #[zbus(property)]
+    fn energy_rate(&self) -> zbus::Result<f64>;
+
+    /// How much of its original capacity the battery can still hold in percent (0 - 100)
+    #[zbus(property)]
+    fn capacity(&self) -> zbus::Result<f64>;
+}
+
+/// Connection to `UPower`
+///
+/// The proxies of all devices are kept around, so their properties are cached and only updated
+/// when `UPower` signals a change.
+pub(super) struct UPower {
+    /// The main `UPower` object
+    manager: UPowerManagerProxy<'static>,
+    /// Proxies of all devices known to `UPower`
+    devices: HashMap<OwnedObjectPath, DeviceProxy<'static>>,
+}
+
+impl UPower {
+    /// Connect to `UPower`
+    ///
+    /// `connection` has to be a connection to the system bus.
+    pub(super) async fn new(connection: &zbus::Connection) -> zbus::Result<Self> {
+        let manager = UPowerManagerProxy::new(connection).await?;
+
+        // Fails if UPower isn't running
+        manager.on_battery().await?;
+
+        Ok(Self {
+            manager,
+            devices: HashMap::new(),
+        })
+    }
+
+    /// Get information about all batteries
+    pub(super) async fn batteries(&mut self) -> Result<Batteries> {
+        let paths = self.manager.enumerate_devices().await?;
+
+        self.devices.retain(|path, _| paths.contains(path));
+
+        for path in paths {
+            if !self.devices.contains_key(&path) {
+                let device = DeviceProxy::builder(self.manager.inner().connection())
+                    .path(path.clone())?
+                    .build()
+                    .await?;
+
+                self.devices.insert(path, device);
+            }
+        }
+
+        let mut batteries = Vec::new();
+
+        for device in self.devices.values() {
+            match battery(device).await {
+                Ok(Some(battery)) => batteries.push(battery),
+                Ok(None) => (),
+                Err(e) => log::warn!(
+                    "Failed to read UPower device {}: {e}",
+                    device.inner().path()
+                ),
+            }
+        }
+
+        batteries.sort_unstable_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Batteries {
+            batteries,
+            ac_online: !self.manager.on_battery().await?,
+        })
+    }
+}
+
+/// Helper function to get information about the battery of `device`
+///
+/// Returns [``None``] if the device isn't a battery.
+async fn battery(device: &DeviceProxy<'_>) -> zbus::Result<Option<BatteryData>> {
+    let kind = device.kind().await?;
+
+    if kind == LINE_POWER_TYPE || !device.is_present().await? {
+        return Ok(None);
+    }
+
+    let native_path = device.native_path().await?;
+
+    Ok(Some(BatteryData {
+        // The native path is usually just the name, but can be a full path
+        name: Path::new(&native_path)
+            .file_name()
+            .map_or_else(|| native_path.clone(), |n| n.to_string_lossy().to_string()),
+        model: device.model().await?,
+        kind: battery_kind(kind, device.power_supply().await?),
+        charge: Percentage::from(device.percentage().await? / 100.0),
+        status: battery_status(device.state().await?),
+        time_to_empty: u64::try_from(device.time_to_empty().await?).unwrap_or_default(),
+        time_to_full: u64::try_from(device.time_to_full().await?).unwrap_or_default(),
+        energy_rate: device.energy_rate().await?,
+        health: Percentage::from(device.capacity().await? / 100.0),
+        // Set by `super::run`, which knows the config
+        level: BatteryLevel::default(),
+    }))
+}
+
+/// Helper function to convert a `UPower` device type to a [``BatteryKind``]
+///
+/// See: <https://upower.freedesktop.org/docs/Device.html#Device:Type>
+const fn battery_kind(kind: u32, power_supply: bool) -> BatteryKind {
+    match kind {
+        2 | 3 if power_supply => BatteryKind::System,
+        5 => BatteryKind::Mouse,
+        6 => BatteryKind::Keyboard,
+        12 => BatteryKind::Gamepad,
+        17 | 19 => BatteryKind::Headset,
+        _ => BatteryKind::Other,
+    }
+}
+
+/// Helper function to convert a `UPower` device state to a [``BatteryStatus``]
+///
+/// See: <https://upower.freedesktop.org/docs/Device.html#Device:State>
+const fn battery_status(state: u32) -> BatteryStatus {
+    match state {
+        1 => BatteryStatus::Charging,
+        2 | 6 => BatteryStatus::Discharging,
+        4 => BatteryStatus::Full,
+        5 => BatteryStatus::NotCharging,
+        _ => BatteryStatus::Unknown,
+    }
+}
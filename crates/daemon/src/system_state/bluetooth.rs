@@ -0,0 +1,230 @@
+//! Collector for bluetooth adapters and devices using `BlueZ`
+//!
+//! Instead of polling, this fetches all objects once and then keeps them up to date using the
+//! `InterfacesAdded`, `InterfacesRemoved` and `PropertiesChanged` signals `BlueZ` sends.
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use common::types::{Percentage, Timer};
+use futures_util::StreamExt;
+use zbus::{
+    MatchRule, MessageStream,
+    fdo::{InterfacesAdded, InterfacesRemoved, ManagedObjects, PropertiesChanged},
+    message::Type,
+    object_server::InterfaceRef,
+    zvariant::{self, OwnedObjectPath, OwnedValue},
+};
+
+use super::{BluetoothAdapter, BluetoothData, BluetoothDevice, SystemState, replace, update_data};
+use crate::bluetooth::bluez::{
+    self, ADAPTER_INTERFACE, BATTERY_INTERFACE, BLUEZ_SERVICE_NAME, DEVICE_INTERFACE, object_name,
+    property,
+};
+
+/// How long to wait before reconnecting to `BlueZ` after an error
+///
+/// This is also how long it takes to notice `BlueZ` being started.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Keep [``super::SystemStateData::bluetooth``] up to date
+///
+/// `connection` has to be a connection to the system bus.
+pub(super) async fn run(iface: InterfaceRef<SystemState>, connection: zbus::Connection) {
+    loop {
+        if let Err(e) = listen(&iface, &connection).await {
+            log::error!("Failed to listen for BlueZ changes: {e}");
+        }
+
+        update_data(&iface, |data| {
+            replace(&mut data.bluetooth, BluetoothData::default())
+        })
+        .await;
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Update the bluetooth data every time `BlueZ` signals a change
+///
+/// Only returns if the connection to `BlueZ` was lost.
+async fn listen(iface: &InterfaceRef<SystemState>, connection: &zbus::Connection) -> Result<()> {
+    let manager = bluez::object_manager(connection).await?;
+
+    // Subscribe before fetching the objects, so no changes are missed in between
+    let mut added = manager.receive_interfaces_added().await?;
+    let mut removed = manager.receive_interfaces_removed().await?;
+    let mut owner_changes = manager.inner().receive_owner_changed().await?;
+
+    let rule = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender(BLUEZ_SERVICE_NAME)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .build();
+    let mut changes = MessageStream::for_match_rule(rule, connection, None).await?;
+
+    let mut objects = manager.get_managed_objects().await?;
+
+    loop {
+        {
+            let _timer = Timer::new(
+                "SystemState bluetooth updated",
+                Some(Duration::from_millis(5)),
+            );
+
+            let bluetooth = bluetooth_data(&objects);
+
+            update_data(iface, |data| replace(&mut data.bluetooth, bluetooth)).await;
+        }
+
+        tokio::select! {
+            Some(signal) = added.next() => interfaces_added(&mut objects, &signal)?,
+            Some(signal) = removed.next() => interfaces_removed(&mut objects, &signal)?,
+            Some(message) = changes.next() => {
+                if let Some(signal) = PropertiesChanged::from_message(message?) {
+                    properties_changed(&mut objects, &signal)?;
+                }
+            }
+            // All objects are gone if BlueZ stopped
+            _ = owner_changes.next() => {
+                log::warn!("BlueZ stopped or restarted");
+                return Ok(());
+            }
+            else => return Ok(()),
+        }
+    }
+}
+
+/// Helper function to add the interfaces of an `InterfacesAdded` signal to `objects`
+fn interfaces_added(objects: &mut ManagedObjects, signal: &InterfacesAdded) -> zbus::Result<()> {
+    let args = signal.args()?;
+    let interfaces = objects
+        .entry(args.object_path().clone().into())
+        .or_default();
+
+    for (name, properties) in args.interfaces_and_properties() {
+        let properties = properties
+            .iter()
+            .map(|(name, value)| Ok(((*name).to_string(), value.try_to_owned()?)))
+            .collect::<zbus::Result<_>>()?;
+
+        interfaces.insert(name.clone().into(), properties);
+    }
+
+    Ok(())
+}
+
+/// Helper function to remove the interfaces of an `InterfacesRemoved` signal from `objects`
+fn interfaces_removed(
+    objects: &mut ManagedObjects,
+    signal: &InterfacesRemoved,
+) -> zbus::Result<()> {
+    let args = signal.args()?;
+    let path = OwnedObjectPath::from(args.object_path().clone());
+
+    if let Some(interfaces) = objects.get_mut(&path) {
+        for name in args.interfaces().iter() {
+            interfaces.remove(name.as_str());
+        }
+
+        if interfaces.is_empty() {
+            objects.remove(&path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Helper function to apply a `PropertiesChanged` signal to `objects`
+///
+/// Invalidated properties are removed, since their new value isn't known.
+fn properties_changed(
+    objects: &mut ManagedObjects,
+    signal: &PropertiesChanged,
+) -> zbus::Result<()> {
+    let Some(path) = signal.message().header().path().cloned() else {
+        return Ok(());
+    };
+    let args = signal.args()?;
+
+    let Some(properties) = objects
+        .get_mut(&OwnedObjectPath::from(path))
+        .and_then(|interfaces| interfaces.get_mut(args.interface_name().as_str()))
+    else {
+        return Ok(());
+    };
+
+    for (name, value) in args.changed_properties() {
+        properties.insert((*name).to_string(), value.try_to_owned()?);
+    }
+
+    for name in args.invalidated_properties().iter() {
+        properties.remove(*name);
+    }
+
+    Ok(())
+}
+
+/// Gathers information about all adapters and devices in `objects`
+fn bluetooth_data(objects: &ManagedObjects) -> BluetoothData {
+    let mut data = BluetoothData::default();
+
+    for (path, interfaces) in objects {
+        if let Some(properties) = interfaces.get(ADAPTER_INTERFACE) {
+            data.adapters.push(adapter(path, properties));
+        }
+
+        if let Some(properties) = interfaces.get(DEVICE_INTERFACE) {
+            data.devices
+                .push(device(properties, interfaces.get(BATTERY_INTERFACE)));
+        }
+    }
+
+    data.adapters.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    data.devices.sort_unstable_by(|a, b| {
+        a.alias
+            .cmp(&b.alias)
+            .then_with(|| a.address.cmp(&b.address))
+    });
+
+    data
+}
+
+/// Helper function to create a [``BluetoothAdapter``] from the properties of the adapter at `path`
+fn adapter(path: &OwnedObjectPath, properties: &HashMap<String, OwnedValue>) -> BluetoothAdapter {
+    BluetoothAdapter {
+        name: object_name(path),
+        address: property(properties, "Address").unwrap_or_default(),
+        alias: property(properties, "Alias").unwrap_or_default(),
+        powered: property(properties, "Powered").unwrap_or_default(),
+        discovering: property(properties, "Discovering").unwrap_or_default(),
+    }
+}
+
+/// Helper function to create a [``BluetoothDevice``] from its properties
+///
+/// `battery` are the properties of the `org.bluez.Battery1` interface, if the device has it.
+fn device(
+    properties: &HashMap<String, OwnedValue>,
+    battery: Option<&HashMap<String, OwnedValue>>,
+) -> BluetoothDevice {
+    let address: String = property(properties, "Address").unwrap_or_default();
+
+    BluetoothDevice {
+        name: property(properties, "Name").unwrap_or_default(),
+        alias: property(properties, "Alias").unwrap_or_else(|| address.clone()),
+        icon: property(properties, "Icon").unwrap_or_default(),
+        adapter: property::<OwnedObjectPath>(properties, "Adapter")
+            .as_ref()
+            .map(object_name)
+            .unwrap_or_default(),
+        connected: property(properties, "Connected").unwrap_or_default(),
+        paired: property(properties, "Paired").unwrap_or_default(),
+        battery: zvariant::Optional::from(
+            battery
+                .and_then(|battery| property::<u8>(battery, "Percentage"))
+                .map(Percentage::from),
+        ),
+        address,
+    }
+}
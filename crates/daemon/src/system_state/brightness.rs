@@ -0,0 +1,35 @@
+//! Collector for the brightness of the screen and keyboard backlights
+use std::time::Duration;
+
+use common::types::Timer;
+use zbus::object_server::InterfaceRef;
+
+use super::{BrightnessData, SystemState, interval, replace, update_data};
+use crate::brightness::sysfs;
+
+/// How often the backlights are refreshed
+///
+/// This is kept short, since the brightness is also changed by hotkeys handled by the firmware,
+/// where the user expects immediate feedback.
+const REFRESH_RATE: Duration = Duration::from_millis(250);
+
+/// Keep [``super::SystemStateData::brightness``] up to date
+pub(super) async fn run(iface: InterfaceRef<SystemState>) {
+    let mut interval = interval(REFRESH_RATE);
+
+    loop {
+        interval.tick().await;
+
+        let _timer = Timer::new(
+            "SystemState brightness updated",
+            Some(Duration::from_millis(2)),
+        );
+
+        let brightness = BrightnessData {
+            backlight: sysfs::backlight().await.into(),
+            keyboard: sysfs::keyboard_backlight().await.into(),
+        };
+
+        update_data(&iface, |data| replace(&mut data.brightness, brightness)).await;
+    }
+}
@@ -0,0 +1,126 @@
+//! Collector for CPU and memory usage and temperatures
+use std::time::Duration;
+
+use common::types::{Percentage, Timer};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+use zbus::object_server::InterfaceRef;
+
+use super::{LoadAverage, SystemState, interval, replace, temperatures::Sensors, update_data};
+
+/// How often the usage is refreshed
+///
+/// This is also the interval between the samples of [``super::MetricsHistory``].
+const REFRESH_RATE: Duration = Duration::from_secs(1);
+
+/// Keep [``super::SystemStateData::cpu_usage``], the memory fields, the temperatures and the
+/// history up to date
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "Precision loss only occurs when calculating percentages, where we don't care since they are just for display."
+)]
+pub(super) async fn run(iface: InterfaceRef<SystemState>) {
+    let mut sys = System::new();
+    let mut sensors = Sensors::default();
+    let mut interval = interval(REFRESH_RATE);
+
+    loop {
+        interval.tick().await;
+
+        let _timer = Timer::new("SystemState cpu updated", Some(Duration::from_millis(2)));
+
+        sys.refresh_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing().with_cpu_usage().with_frequency())
+                .with_memory(MemoryRefreshKind::nothing().with_ram().with_swap()),
+        );
+
+        let config = iface.get().await.config.metrics.clone();
+        let temperatures = sensors.temperatures(&config).await;
+
+        let cpu_usage = Percentage::from(f64::from(sys.global_cpu_usage()) / 100.0);
+        let cpu_core_usage = sys
+            .cpus()
+            .iter()
+            .map(|cpu| Percentage::from(f64::from(cpu.cpu_usage()) / 100.0))
+            .collect();
+        let cpu_frequency = sys
+            .cpus()
+            .iter()
+            .map(sysinfo::Cpu::frequency)
+            .sum::<u64>()
+            .checked_div(sys.cpus().len() as u64)
+            .unwrap_or_default();
+
+        let used_mem = sys.used_memory();
+        let total_mem = sys.total_memory();
+        // NaN would never compare equal, so the data would change every tick
+        let mem_usage = if total_mem == 0 {
+            Percentage::default()
+        } else {
+            Percentage::from(used_mem as f64 / total_mem as f64)
+        };
+
+        let load = System::load_average();
+
+        add_samples(
+            &iface,
+            cpu_usage,
+            mem_usage,
+            temperatures.cpu.unwrap_or_default(),
+            config.history_length,
+        )
+        .await;
+
+        update_data(&iface, |data| {
+            replace(&mut data.cpu_usage, cpu_usage)
+                | replace(&mut data.cpu_core_usage, cpu_core_usage)
+                | replace(&mut data.cpu_frequency, cpu_frequency)
+                | replace(
+                    &mut data.load_average,
+                    LoadAverage {
+                        one: load.one,
+                        five: load.five,
+                        fifteen: load.fifteen,
+                    },
+                )
+                | replace(&mut data.used_mem, used_mem)
+                | replace(&mut data.total_mem, total_mem)
+                | replace(&mut data.mem_usage, mem_usage)
+                | replace(&mut data.used_swap, sys.used_swap())
+                | replace(&mut data.total_swap, sys.total_swap())
+                | replace(&mut data.temperatures, temperatures)
+        })
+        .await;
+    }
+}
+
+/// Helper function to add a sample to each part of the [``super::MetricsHistory``] of `iface`
+///
+/// Other components are always notified, since a sample is added every time.
+async fn add_samples(
+    iface: &InterfaceRef<SystemState>,
+    cpu_usage: Percentage,
+    mem_usage: Percentage,
+    cpu_temperature: f64,
+    length: usize,
+) {
+    let mut state = iface.get_mut().await;
+
+    let history = &mut state.history;
+    push_sample(&mut history.cpu_usage, cpu_usage, length);
+    push_sample(&mut history.mem_usage, mem_usage, length);
+    push_sample(&mut history.cpu_temperature, cpu_temperature, length);
+
+    if let Err(e) = state.metrics_history_changed(iface.signal_emitter()).await {
+        log::error!("Failed to signal MetricsHistory change: {e}");
+    }
+}
+
+/// Helper function to add `sample` to `history`, dropping the oldest samples if it is longer than
+/// `length`
+fn push_sample<T>(history: &mut Vec<T>, sample: T, length: usize) {
+    history.push(sample);
+
+    let excess = history.len().saturating_sub(length);
+    history.drain(..excess);
+}
@@ -0,0 +1,313 @@
+//! Collector for disk usage and throughput
+//!
+//! The throughput is calculated from the sectors read and written, as counted in
+//! [``DISKSTATS_PATH``].
+//!
+//! See: <https://www.kernel.org/doc/html/latest/admin-guide/iostats.html>
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use common::types::{Percentage, Timer};
+use sysinfo::{Disk, Disks};
+use zbus::object_server::InterfaceRef;
+
+use super::{DiskData, SystemState, interval, replace, update_data};
+
+/// How often the throughput is sampled
+///
+/// This is also the period the throughput is averaged over. It only requires reading
+/// [``DISKSTATS_PATH``], so it is cheap enough to do often.
+const THROUGHPUT_REFRESH_RATE: Duration = Duration::from_secs(2);
+
+/// How often the usage of the disks is refreshed
+///
+/// This requires a `statvfs` call for each disk, which can block for a long time (e.g. for network
+/// file systems), so it is done less often and outside of the async runtime. Mounting or
+/// unmounting a disk triggers a refresh as well (see [``MOUNTS_PATH``]).
+const USAGE_REFRESH_RATE: Duration = Duration::from_secs(30);
+
+/// File containing the I/O statistics of all block devices
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+/// File listing all mounted file systems, checked for changes with the throughput
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+/// Size of the sectors counted in [``DISKSTATS_PATH``] in bytes
+///
+/// This is always 512, no matter the actual sector size of the device.
+const SECTOR_SIZE: u64 = 512;
+
+/// Sectors read from and written to a block device since boot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IoStats {
+    /// Sectors read
+    read: u64,
+    /// Sectors written
+    written: u64,
+}
+
+/// Keep [``super::SystemStateData::disks``] up to date
+///
+/// Emits [``SystemState::DISK_ADDED``] and [``SystemState::DISK_REMOVED``] when removable disks
+/// are mounted or unmounted.
+pub(super) async fn run(iface: InterfaceRef<SystemState>) {
+    let mut disks = Disks::new();
+    // Data of each disk without throughput, with its name in DISKSTATS_PATH
+    let mut usage: Vec<(DiskData, String)> = Vec::new();
+    let mut mounts = String::new();
+    let mut usage_interval = interval(USAGE_REFRESH_RATE);
+    let mut throughput_interval = interval(THROUGHPUT_REFRESH_RATE);
+    let mut previous_stats: Option<(Instant, HashMap<String, IoStats>)> = None;
+    // None until the first refresh, so the disks present at startup aren't signaled as added
+    let mut previous_removable: Option<Vec<DiskData>> = None;
+
+    loop {
+        let refresh = tokio::select! {
+            biased;
+            _ = usage_interval.tick() => {
+                mounts = read_mounts().await;
+                true
+            }
+            _ = throughput_interval.tick() => {
+                let current = read_mounts().await;
+                let changed = current != mounts;
+                mounts = current;
+
+                if changed {
+                    usage_interval.reset();
+                }
+
+                changed
+            }
+        };
+
+        if refresh {
+            (disks, usage) = refresh_usage(disks).await;
+        }
+
+        let _timer = Timer::new("SystemState disks updated", Some(Duration::from_millis(5)));
+
+        let now = Instant::now();
+        let stats = tokio::fs::read_to_string(DISKSTATS_PATH).await.map_or_else(
+            |e| {
+                log::warn!("Failed to read {DISKSTATS_PATH}: {e}");
+                HashMap::new()
+            },
+            |content| parse_diskstats(&content),
+        );
+
+        let data: Vec<DiskData> = usage
+            .iter()
+            .map(|(disk, key)| {
+                let current = stats.get(key);
+                let previous = previous_stats
+                    .as_ref()
+                    .and_then(|(time, stats)| Some((*time, stats.get(key)?)));
+
+                let (read_rate, write_rate) = match (current, previous) {
+                    (Some(current), Some((previous_time, previous))) => {
+                        let elapsed = now.duration_since(previous_time);
+
+                        (
+                            rate(current.read.saturating_sub(previous.read), elapsed),
+                            rate(current.written.saturating_sub(previous.written), elapsed),
+                        )
+                    }
+                    _ => (0, 0),
+                };
+
+                DiskData {
+                    read_rate,
+                    write_rate,
+                    ..disk.clone()
+                }
+            })
+            .collect();
+
+        previous_stats = Some((now, stats));
+
+        let removable: Vec<DiskData> = data.iter().filter(|d| d.removable).cloned().collect();
+
+        update_data(&iface, |state| replace(&mut state.disks, data)).await;
+
+        if refresh && let Some(previous) = previous_removable.replace(removable.clone()) {
+            signal_removable_changes(&iface, &previous, &removable).await;
+        }
+    }
+}
+
+/// Helper function to read [``MOUNTS_PATH``], to detect disks being mounted or unmounted
+async fn read_mounts() -> String {
+    tokio::fs::read_to_string(MOUNTS_PATH)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read {MOUNTS_PATH}: {e}");
+            String::new()
+        })
+}
+
+/// Helper function to refresh the usage of `disks` on a blocking thread
+///
+/// Returns `disks` and the data of each disk (without throughput) with its name in
+/// [``DISKSTATS_PATH``], sorted by [``DiskData::mount_point``].
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "Precision loss only occurs when calculating percentages, where we don't care since they are just for display."
+)]
+async fn refresh_usage(mut disks: Disks) -> (Disks, Vec<(DiskData, String)>) {
+    let disks = tokio::task::spawn_blocking(move || {
+        disks.refresh(true);
+        disks
+    })
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Failed to refresh disks: {e}");
+        Disks::new()
+    });
+
+    let mut usage = Vec::new();
+
+    for disk in disks.list() {
+        let size = disk.total_space();
+        let free = disk.available_space();
+        // Pseudo file systems have no size
+        let used = if size == 0 {
+            Percentage::default()
+        } else {
+            Percentage::from((size as f64 - free as f64) / size as f64)
+        };
+
+        usage.push((
+            DiskData {
+                name: disk.name().to_string_lossy().to_string(),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                file_system: disk.file_system().to_string_lossy().to_string(),
+                removable: disk.is_removable(),
+                size,
+                free,
+                used,
+                read_rate: 0,
+                write_rate: 0,
+            },
+            stats_key(disk).await.unwrap_or_default(),
+        ));
+    }
+
+    usage.sort_unstable_by(|(a, _), (b, _)| a.mount_point.cmp(&b.mount_point));
+
+    (disks, usage)
+}
+
+/// Helper function to emit the signals for removable disks which are in only one of `previous`
+/// and `current`
+async fn signal_removable_changes(
+    iface: &InterfaceRef<SystemState>,
+    previous: &[DiskData],
+    current: &[DiskData],
+) {
+    let contains = |disks: &[DiskData], disk: &DiskData| {
+        disks.iter().any(|d| d.mount_point == disk.mount_point)
+    };
+
+    for disk in current.iter().filter(|d| !contains(previous, d)) {
+        log::info!(
+            "Removable disk {} mounted at {}",
+            disk.name,
+            disk.mount_point
+        );
+
+        if let Err(e) = SystemState::disk_added(iface.signal_emitter(), disk).await {
+            log::error!("Failed to signal added disk: {e}");
+        }
+    }
+
+    for disk in previous.iter().filter(|d| !contains(current, d)) {
+        log::info!(
+            "Removable disk {} unmounted from {}",
+            disk.name,
+            disk.mount_point
+        );
+
+        if let Err(e) = SystemState::disk_removed(iface.signal_emitter(), disk).await {
+            log::error!("Failed to signal removed disk: {e}");
+        }
+    }
+}
+
+/// Helper function to get the name `disk` has in [``DISKSTATS_PATH``] (e.g. `sda1` or `dm-0`)
+///
+/// Symlinks are resolved, since e.g. `/dev/mapper/root` is only listed as `dm-0`.
+async fn stats_key(disk: &Disk) -> Option<String> {
+    let path = Path::new(disk.name());
+    let path = tokio::fs::canonicalize(path)
+        .await
+        .unwrap_or_else(|_| path.to_path_buf());
+
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// Helper function to convert `sectors` transferred during `elapsed` into bytes per second
+fn rate(sectors: u64, elapsed: Duration) -> u64 {
+    let bytes = u128::from(sectors) * u128::from(SECTOR_SIZE);
+
+    u64::try_from(bytes * 1000 / elapsed.as_millis().max(1)).unwrap_or(u64::MAX)
+}
+
+/// Parse the content of [``DISKSTATS_PATH``] into the [``IoStats``] of each device
+///
+/// Lines which can't be parsed are skipped.
+fn parse_diskstats(content: &str) -> HashMap<String, IoStats> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            Some((
+                (*fields.get(2)?).to_string(),
+                IoStats {
+                    read: fields.get(5)?.parse().ok()?,
+                    written: fields.get(9)?.parse().ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{IoStats, parse_diskstats, rate};
+
+    #[test]
+    fn parses_diskstats() {
+        let content = "\
+ 259       0 nvme0n1 254 0 20480 31 120 10 4096 52 0 120 83 0 0 0 0 0 0
+ 259       1 nvme0n1p1 12 0 1024 2 0 0 0 0 0 8 2 0 0 0 0 0 0
+ invalid line
+";
+        let stats = parse_diskstats(content);
+
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats.get("nvme0n1"),
+            Some(&IoStats {
+                read: 20480,
+                written: 4096
+            })
+        );
+        assert_eq!(
+            stats.get("nvme0n1p1"),
+            Some(&IoStats {
+                read: 1024,
+                written: 0
+            })
+        );
+
+        assert_eq!(rate(4096, Duration::from_secs(2)), 1_048_576);
+    }
+}
@@ -0,0 +1,86 @@
+//! Collector for the capslock and numlock LEDs
+use std::{path::Path, sync::LazyLock, time::Duration};
+
+use anyhow::Result;
+use common::types::Timer;
+use regex::Regex;
+use tokio::fs;
+use zbus::object_server::InterfaceRef;
+
+use super::{SystemState, interval, replace, update_data};
+
+/// How often the LEDs are refreshed
+///
+/// This is kept short, since the user expects immediate feedback after pressing the key.
+const REFRESH_RATE: Duration = Duration::from_millis(250);
+
+/// [``Regex``] used by [``key_states``]
+static CAPSLOCK_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"input\d+::capslock").unwrap());
+/// [``Regex``] used by [``key_states``]
+static NUMLOCK_PATTERN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"input\d+::numlock").unwrap());
+
+/// Keep [``super::SystemStateData::capslock``] and [``super::SystemStateData::numlock``] up to
+/// date
+pub(super) async fn run(iface: InterfaceRef<SystemState>) {
+    let mut interval = interval(REFRESH_RATE);
+
+    loop {
+        interval.tick().await;
+
+        let _timer = Timer::new("SystemState keys updated", Some(Duration::from_millis(2)));
+
+        let (capslock, numlock) = key_states()
+            .await
+            .inspect_err(|e| log::error!("Failed to update key state information: {e}"))
+            .unwrap_or_default();
+
+        update_data(&iface, |data| {
+            replace(&mut data.capslock, capslock) | replace(&mut data.numlock, numlock)
+        })
+        .await;
+    }
+}
+
+/// Checks if capslock / numlock are enabled
+///
+/// Field 0 signals if capslock is enabled
+/// Field 1 signals if numlock is enabled
+async fn key_states() -> Result<(bool, bool)> {
+    // Helper function to read the brightness of the given path
+    let read_brightness = async |path: &str| {
+        let content = fs::read_to_string(path).await?;
+        Ok::<u32, std::io::Error>(
+            content
+                .trim()
+                .parse()
+                .expect("Value of brightness file should always be a number"),
+        )
+    };
+
+    let led_dir = Path::new("/sys/class/leds");
+    let mut entries = fs::read_dir(led_dir).await?;
+
+    let mut capslock_brightness_sum = 0;
+    let mut numlock_brightness_sum = 0;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if let Some(file_name) = path.file_name() {
+            let file_name_str = file_name.to_string_lossy();
+            let brightness_path = path.join("brightness");
+
+            // Check if the directory name matches the Caps Lock or Num Lock pattern
+            if CAPSLOCK_PATTERN.is_match(&file_name_str) && brightness_path.exists() {
+                capslock_brightness_sum +=
+                    read_brightness(&brightness_path.to_string_lossy()).await?;
+            } else if NUMLOCK_PATTERN.is_match(&file_name_str) && brightness_path.exists() {
+                numlock_brightness_sum +=
+                    read_brightness(&brightness_path.to_string_lossy()).await?;
+            }
+        }
+    }
+
+    Ok((capslock_brightness_sum > 0, numlock_brightness_sum > 0))
+}
@@ -0,0 +1,891 @@
+//! This module contains items relating to getting "State" (aka. Information)
+//! about the system and other processes running on the system
+//!
+//! It can be thought of as an interface between information gathered from outside the shell and
+//! the components which need that information.
+//!
+//! The main type is [``SystemState``]
+//!
+//! ## Collectors
+//!
+//! The data is gathered by independent collectors (see [``spawn_collectors``]), each running as its
+//! own task with its own refresh rate, or no polling at all if it is event driven. This way a slow
+//! collector (e.g. waiting on `NetworkManager`) doesn't delay any of the others.
+//!
+//! Each collector is timed as `SystemState <collector> updated` (e.g. `SystemState cpu updated`),
+//! and every change to the data as `SystemState updated` (see `dod-shell-cli stats`).
+use std::{path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::watch,
+    time::{Interval, MissedTickBehavior},
+};
+use zbus::{
+    interface,
+    object_server::{Interface, InterfaceRef, SignalEmitter},
+    zvariant,
+};
+
+use common::{
+    config::battery::BatteryConfig,
+    types::{Percentage, Timer},
+};
+
+mod audio;
+mod battery;
+mod bluetooth;
+mod brightness;
+mod cpu;
+mod disks;
+mod keys;
+mod network;
+mod temperatures;
+mod workspace;
+
+/// Directory containing the batteries referenced by [``common::config::bar::BarConfig::battery``]
+pub const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply/";
+
+/// All of the State (aka. Information) gathered from the system
+///
+/// Internally holds [``SystemStateData``] to actually hold all of the data, which is kept up to
+/// date by the collectors started with [``spawn_collectors``].
+///
+/// # Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``SystemStateProxy``]
+#[derive(Debug, Default)]
+pub struct SystemState {
+    /// Actual data
+    data: SystemStateData,
+    /// Recent metrics, published separately since they change every second
+    history: MetricsHistory,
+    /// The current config
+    config: common::Config,
+}
+
+impl SystemState {
+    /// Name of the dbus signal emitted when the main battery becomes low or critical while
+    /// discharging
+    ///
+    /// See [``BatteryData::level``]
+    pub const BATTERY_LEVEL_REACHED: &str = "BatteryLevelReached";
+
+    /// Name of the dbus signal emitted when a removable disk (e.g. a USB drive) is mounted
+    ///
+    /// See [``DiskData::removable``]
+    pub const DISK_ADDED: &str = "DiskAdded";
+
+    /// Name of the dbus signal emitted when a removable disk is unmounted
+    pub const DISK_REMOVED: &str = "DiskRemoved";
+
+    /// The current Bluetooth adapters and devices
+    ///
+    /// Used by [``crate::bluetooth::Bluetooth``] to find them without asking `BlueZ`.
+    #[must_use]
+    pub const fn bluetooth(&self) -> &BluetoothData {
+        &self.data.bluetooth
+    }
+
+    /// Set the internal [``common::Config``]
+    ///
+    /// This is used primarily if there has been a change to the on-disk config file.
+    ///
+    /// For more information on the updating process see [``crate::config``]
+    pub fn set_config(&mut self, config: common::Config) {
+        self.config = config;
+    }
+
+    /// Emit the [``Self::BATTERY_LEVEL_REACHED``] dbus signal
+    ///
+    /// The signal has the name of the main battery and its new [``BatteryLevel``] as arguments.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn battery_level_reached(
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+        level: BatteryLevel,
+    ) -> zbus::Result<()> {
+        emitter
+            .emit(Self::name(), Self::BATTERY_LEVEL_REACHED, &(name, level))
+            .await
+    }
+
+    /// Emit the [``Self::DISK_ADDED``] dbus signal
+    ///
+    /// The signal has the [``DiskData::name``] and [``DiskData::mount_point``] of `disk` as
+    /// arguments.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn disk_added(emitter: &SignalEmitter<'_>, disk: &DiskData) -> zbus::Result<()> {
+        emitter
+            .emit(
+                Self::name(),
+                Self::DISK_ADDED,
+                &(&disk.name, &disk.mount_point),
+            )
+            .await
+    }
+
+    /// Emit the [``Self::DISK_REMOVED``] dbus signal
+    ///
+    /// The signal has the same arguments as [``Self::DISK_ADDED``].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn disk_removed(emitter: &SignalEmitter<'_>, disk: &DiskData) -> zbus::Result<()> {
+        emitter
+            .emit(
+                Self::name(),
+                Self::DISK_REMOVED,
+                &(&disk.name, &disk.mount_point),
+            )
+            .await
+    }
+}
+
+/// Start all collectors keeping the [``SystemState``] served at `iface` up to date
+///
+/// Each collector runs as its own task for as long as the daemon runs.
+///
+/// `system_bus` has to be a connection to the system bus. It is shared by all collectors, so
+/// their subscriptions are kept on a single long-lived connection.
+///
+/// `audio` is the receiver returned by [``crate::audio::Audio::audio``].
+pub fn spawn_collectors(
+    iface: &InterfaceRef<SystemState>,
+    system_bus: &zbus::Connection,
+    audio: watch::Receiver<AudioData>,
+) {
+    tokio::spawn(cpu::run(iface.clone()));
+    tokio::spawn(disks::run(iface.clone()));
+    tokio::spawn(battery::run(iface.clone(), system_bus.clone()));
+    tokio::spawn(keys::run(iface.clone()));
+    tokio::spawn(brightness::run(iface.clone()));
+    tokio::spawn(audio::run(iface.clone(), audio));
+    tokio::spawn(workspace::run(iface.clone()));
+    tokio::spawn(network::run(iface.clone(), system_bus.clone()));
+    tokio::spawn(bluetooth::run(iface.clone(), system_bus.clone()));
+}
+
+/// Helper function to apply `update` to the data of `iface`
+///
+/// `update` returns if it changed anything (see [``replace``]). Only then are other components
+/// notified about the change.
+///
+/// Every call is timed as `SystemState updated`, regardless of the collector, so the time spent
+/// holding the lock and notifying other components can be watched as a whole. The time spent by
+/// each collector gathering its data is timed separately as `SystemState <collector> updated`.
+async fn update_data(
+    iface: &InterfaceRef<SystemState>,
+    update: impl FnOnce(&mut SystemStateData) -> bool,
+) {
+    let _timer = Timer::new("SystemState updated", Some(Duration::from_millis(10)));
+
+    let mut state = iface.get_mut().await;
+
+    if !update(&mut state.data) {
+        return;
+    }
+
+    if let Err(e) = state.state_data_changed(iface.signal_emitter()).await {
+        log::error!("Failed to signal SystemState change: {e}");
+    }
+}
+
+/// Helper function to set `field` to `value`
+///
+/// Returns if the value changed.
+fn replace<T: PartialEq>(field: &mut T, value: T) -> bool {
+    if *field == value {
+        return false;
+    }
+
+    *field = value;
+
+    true
+}
+
+/// Helper function to create the [``Interval``] used by polling collectors
+///
+/// If a collector takes longer than `period` the following ticks are delayed instead of bursting to
+/// catch up.
+fn interval(period: Duration) -> Interval {
+    let mut interval = tokio::time::interval(period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    interval
+}
+
+#[interface(
+    name = "dod.shell.Daemon.SystemState",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl SystemState {
+    /// Dbus property to get the current data
+    #[zbus(property)]
+    fn state_data(&self) -> SystemStateData {
+        self.data.clone()
+    }
+
+    /// Dbus property to get recent CPU and memory usage and temperatures, for drawing graphs
+    ///
+    /// This is kept separate from [``Self::state_data``], since a sample is added every second,
+    /// even if nothing else changed.
+    #[zbus(property)]
+    fn metrics_history(&self) -> MetricsHistory {
+        self.history.clone()
+    }
+}
+
+/// Data component of [``SystemState``]
+#[derive(
+    Debug, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type, Default,
+)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Each bool is an independent piece of system state."
+)]
+pub struct SystemStateData {
+    /// CPU usage
+    pub cpu_usage: Percentage,
+    /// Amount of memory on the system (only RAM no SWAP) in bytes
+    pub total_mem: u64,
+    /// Amount of memory in use (only RAM no SWAP) in bytes
+    pub used_mem: u64,
+    /// Memory (only RAM no SWAP) usage
+    pub mem_usage: Percentage,
+    /// Usage of each CPU core
+    pub cpu_core_usage: Vec<Percentage>,
+    /// Average frequency of all CPU cores in MHz
+    pub cpu_frequency: u64,
+    /// Average number of processes running or waiting to run
+    pub load_average: LoadAverage,
+    /// Amount of swap on the system in bytes
+    pub total_swap: u64,
+    /// Amount of swap in use in bytes
+    pub used_swap: u64,
+    /// Temperatures of the sensors set in [``common::config::metrics::MetricsConfig``]
+    pub temperatures: Temperatures,
+    /// The current workspace number
+    pub workspace: i32,
+    /// Data about the network connections
+    pub network: NetworkData,
+    /// Data about the main battery
+    ///
+    /// This is the battery set in [``common::config::bar::BarConfig::battery``] or the first
+    /// battery powering the system.
+    pub battery: zvariant::Optional<BatteryData>,
+    /// Data about all batteries, including those of peripherals (e.g. a mouse)
+    pub batteries: Vec<BatteryData>,
+    /// If the system is connected to a power source
+    pub ac_online: bool,
+    /// All mounted disks, sorted by [``DiskData::mount_point``]
+    ///
+    /// See [``Self::disk``]
+    pub disks: Vec<DiskData>,
+    /// Data about the Bluetooth adapters and devices
+    pub bluetooth: BluetoothData,
+    /// Brightness of the screen and keyboard backlights
+    pub brightness: BrightnessData,
+    /// If capslock is active
+    pub capslock: bool,
+    /// If numlock is active
+    pub numlock: bool,
+    /// Volume of the default audio output
+    pub volume: VolumeState,
+    /// All audio devices and streams
+    pub audio: AudioData,
+}
+
+impl SystemStateData {
+    /// The disk mounted at `mount_point`
+    #[must_use]
+    pub fn disk(&self, mount_point: &Path) -> Option<&DiskData> {
+        self.disks
+            .iter()
+            .find(|d| Path::new(&d.mount_point) == mount_point)
+    }
+}
+
+/// Average number of processes running or waiting to run over different periods of time
+///
+/// See: <https://www.man7.org/linux/man-pages/man5/proc_loadavg.5.html>
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct LoadAverage {
+    /// Over the last minute
+    pub one: f64,
+    /// Over the last 5 minutes
+    pub five: f64,
+    /// Over the last 15 minutes
+    pub fifteen: f64,
+}
+
+/// Temperatures of different parts of the system in °C
+///
+/// Each is [``None``] if there is no matching sensor.
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct Temperatures {
+    /// Temperature of the CPU (package)
+    pub cpu: zvariant::Optional<f64>,
+    /// Temperature of the GPU
+    pub gpu: zvariant::Optional<f64>,
+    /// Temperature of the `NVMe` drive
+    pub nvme: zvariant::Optional<f64>,
+}
+
+/// Samples of metrics taken every second, oldest first
+///
+/// See [``SystemStateProxy::metrics_history``]
+///
+/// Holds at most [``common::config::metrics::MetricsConfig::history_length``] samples.
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct MetricsHistory {
+    /// See [``SystemStateData::cpu_usage``]
+    pub cpu_usage: Vec<Percentage>,
+    /// See [``SystemStateData::mem_usage``]
+    pub mem_usage: Vec<Percentage>,
+    /// See [``Temperatures::cpu``]. 0 if unknown
+    pub cpu_temperature: Vec<f64>,
+}
+
+/// Brightness of the backlights controlled by [``crate::brightness::Brightness``]
+///
+/// Each is [``None``] if the system doesn't have such a backlight.
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct BrightnessData {
+    /// The main screen backlight
+    pub backlight: zvariant::Optional<BacklightData>,
+    /// The keyboard backlight
+    pub keyboard: zvariant::Optional<BacklightData>,
+}
+
+/// Information about a backlight
+///
+/// See: [``crate::brightness::sysfs``]
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct BacklightData {
+    /// Name of the device (e.g. `intel_backlight` or `tpacpi::kbd_backlight`)
+    pub name: String,
+    /// Current brightness in steps of the device
+    pub brightness: u32,
+    /// Highest brightness of the device
+    pub max_brightness: u32,
+    /// Brightness relative to [``Self::max_brightness``]
+    pub level: Percentage,
+}
+
+/// Information about a mounted disk
+///
+/// A device mounted in multiple places (e.g. btrfs subvolumes) is listed once per mount point.
+///
+/// See: [``sysinfo::Disks``]
+#[derive(
+    Debug, Clone, PartialEq, zbus::zvariant::Value, zbus::zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct DiskData {
+    /// Name of the device (e.g. `/dev/sda1`)
+    pub name: String,
+    /// Where the disk is mounted (e.g. `/home`)
+    pub mount_point: String,
+    /// File system of the disk (e.g. `ext4`)
+    pub file_system: String,
+    /// If the disk is removable (e.g. a USB drive)
+    pub removable: bool,
+    /// Total space (in bytes)
+    pub size: u64,
+    /// Free space (in bytes)
+    pub free: u64,
+    /// Space used
+    pub used: Percentage,
+    /// Bytes read per second
+    pub read_rate: u64,
+    /// Bytes written per second
+    pub write_rate: u64,
+}
+
+/// State of the default audio output
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct VolumeState {
+    /// The volume, even if [``Self::muted``]
+    pub level: Percentage,
+    /// If the output is muted
+    pub muted: bool,
+    /// If there is a default audio output
+    ///
+    /// This is `false` if `PulseAudio` isn't running. All other fields should be ignored then.
+    pub available: bool,
+    /// If the output is connected via bluetooth
+    pub bluetooth: bool,
+}
+
+impl From<Option<&AudioDevice>> for VolumeState {
+    fn from(value: Option<&AudioDevice>) -> Self {
+        value.map_or_else(Self::default, |device| Self {
+            level: device.volume,
+            muted: device.muted,
+            available: true,
+            bluetooth: device.bluetooth,
+        })
+    }
+}
+
+/// Data about the audio devices and streams, as reported by `PulseAudio`
+///
+/// Changes can be made using [``crate::audio::Audio``]
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct AudioData {
+    /// Audio outputs
+    pub sinks: Vec<AudioDevice>,
+    /// Audio inputs (e.g. microphones)
+    ///
+    /// Monitors of sinks are not included.
+    pub sources: Vec<AudioDevice>,
+    /// Audio played by applications (aka. sink inputs)
+    pub sink_inputs: Vec<AudioStream>,
+}
+
+impl AudioData {
+    /// The default audio output
+    #[must_use]
+    pub fn default_sink(&self) -> Option<&AudioDevice> {
+        self.sinks.iter().find(|s| s.default)
+    }
+
+    /// The default audio input
+    #[must_use]
+    pub fn default_source(&self) -> Option<&AudioDevice> {
+        self.sources.iter().find(|s| s.default)
+    }
+}
+
+/// An audio output (sink) or input (source)
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct AudioDevice {
+    /// Index used by `PulseAudio`
+    pub index: u32,
+    /// Name used by `PulseAudio`
+    pub name: String,
+    /// Human readable name
+    pub description: String,
+    /// Name of the icon for the device. Empty if unknown
+    pub icon_name: String,
+    /// Average volume of all channels
+    pub volume: Percentage,
+    /// If the device is muted
+    pub muted: bool,
+    /// If this is the default device
+    pub default: bool,
+    /// If the device is connected via bluetooth
+    pub bluetooth: bool,
+}
+
+/// Audio played by an application (aka. sink input)
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct AudioStream {
+    /// Index used by `PulseAudio`
+    pub index: u32,
+    /// Name of the application
+    pub name: String,
+    /// Name of the icon for the application. Empty if unknown
+    pub icon_name: String,
+    /// Average volume of all channels
+    pub volume: Percentage,
+    /// If the stream is muted
+    pub muted: bool,
+    /// Index of the [``AudioDevice``] the stream is played on
+    pub sink: u32,
+}
+
+/// Data about the network, as reported by `NetworkManager`
+///
+/// Changes can be made using [``crate::network::Network``]
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct NetworkData {
+    /// All active connections, with the primary connection first
+    pub connections: Vec<ConnectionData>,
+    /// How well the system is connected to the internet
+    pub connectivity: Connectivity,
+    /// If the primary connection is metered (e.g. a mobile hotspot)
+    pub metered: bool,
+    /// If Wi-Fi is enabled
+    pub wifi_enabled: bool,
+    /// If both Wi-Fi and mobile broadband are disabled
+    ///
+    /// See [``crate::network::Network::set_airplane_mode``]
+    pub airplane_mode: bool,
+}
+
+impl NetworkData {
+    /// The connection used for the default route
+    #[must_use]
+    pub fn primary(&self) -> Option<&ConnectionData> {
+        self.connections.first().filter(|c| c.primary)
+    }
+
+    /// The connection of a physical device (e.g. Wi-Fi), preferring the primary connection
+    ///
+    /// If a VPN is the primary connection, this is usually the connection it is tunneled through.
+    #[must_use]
+    pub fn uplink(&self) -> Option<&ConnectionData> {
+        self.connections.iter().find(|c| !c.kind.is_vpn())
+    }
+
+    /// If the system is connected to the internet
+    ///
+    /// [``Connectivity::Unknown``] counts as connected while there is a primary connection, since
+    /// the connectivity check can be disabled in `NetworkManager`.
+    #[must_use]
+    pub fn connected(&self) -> bool {
+        match self.connectivity {
+            Connectivity::Full => true,
+            Connectivity::Unknown => self.primary().is_some(),
+            Connectivity::None | Connectivity::Portal | Connectivity::Limited => false,
+        }
+    }
+
+    /// If a VPN (including `WireGuard`) is active
+    #[must_use]
+    pub fn vpn_active(&self) -> bool {
+        self.connections.iter().any(|c| c.kind.is_vpn())
+    }
+}
+
+/// Data about an active network connection
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct ConnectionData {
+    /// Name of the connection profile (e.g. `Wired connection 1`)
+    pub id: String,
+    /// What kind of connection this is
+    pub kind: ConnectionKind,
+    /// Names of the network interfaces used by the connection (e.g. `wlan0`)
+    pub interfaces: Vec<String>,
+    /// IPv4 and IPv6 addresses with their prefix length (e.g. `192.168.1.2/24`)
+    pub addresses: Vec<String>,
+    /// SSID of the Wi-Fi network. Empty if the connection isn't wireless
+    pub ssid: String,
+    /// Signal strength of the Wi-Fi network. 0 if the connection isn't wireless
+    pub signal: Percentage,
+    /// If this is the primary connection, which is used for the default route
+    pub primary: bool,
+}
+
+/// What kind of network connection a [``ConnectionData``] is
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub enum ConnectionKind {
+    /// Ethernet
+    Wired,
+    /// Wi-Fi
+    Wireless,
+    /// A VPN managed by a `NetworkManager` VPN plugin (e.g. `OpenVPN`)
+    Vpn,
+    /// A `WireGuard` tunnel
+    WireGuard,
+    /// Any other kind of connection (e.g. a bridge or mobile broadband)
+    #[default]
+    Other,
+}
+
+impl ConnectionKind {
+    /// If this is a VPN, including `WireGuard`
+    #[must_use]
+    pub const fn is_vpn(self) -> bool {
+        matches!(self, Self::Vpn | Self::WireGuard)
+    }
+}
+
+/// How well the system is connected to the internet
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMConnectivityState>
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub enum Connectivity {
+    /// The connectivity couldn't be checked, or `NetworkManager` isn't running
+    #[default]
+    Unknown,
+    /// Not connected to any network
+    None,
+    /// Behind a captive portal (e.g. the login page of a hotel Wi-Fi)
+    Portal,
+    /// Connected to a network, but not to the internet
+    Limited,
+    /// Connected to the internet
+    Full,
+}
+
+/// Data about Bluetooth, as reported by `BlueZ`
+///
+/// Changes can be made using [``crate::bluetooth::Bluetooth``]
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct BluetoothData {
+    /// All Bluetooth adapters of the system
+    pub adapters: Vec<BluetoothAdapter>,
+    /// All devices known to any adapter, sorted by name
+    ///
+    /// This includes paired devices which aren't in range and devices found while discovering.
+    pub devices: Vec<BluetoothDevice>,
+}
+
+impl BluetoothData {
+    /// All devices which are currently connected
+    pub fn connected(&self) -> impl Iterator<Item = &BluetoothDevice> {
+        self.devices.iter().filter(|d| d.connected)
+    }
+
+    /// If there are currently any devices connected
+    #[must_use]
+    pub fn any_connected(&self) -> bool {
+        self.connected().next().is_some()
+    }
+
+    /// If any adapter is powered on
+    #[must_use]
+    pub fn powered(&self) -> bool {
+        self.adapters.iter().any(|a| a.powered)
+    }
+}
+
+/// A Bluetooth adapter (aka. controller)
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct BluetoothAdapter {
+    /// Name used by the kernel (e.g. `hci0`)
+    pub name: String,
+    /// Bluetooth address (e.g. `00:11:22:33:44:55`)
+    pub address: String,
+    /// Human readable name, as seen by other devices
+    pub alias: String,
+    /// If the adapter is powered on
+    pub powered: bool,
+    /// If the adapter is searching for new devices
+    pub discovering: bool,
+}
+
+/// A Bluetooth device (e.g. headphones)
+#[derive(
+    Debug, Default, Clone, PartialEq, zvariant::Value, zvariant::OwnedValue, zvariant::Type,
+)]
+pub struct BluetoothDevice {
+    /// Bluetooth address (e.g. `00:11:22:33:44:55`)
+    ///
+    /// This is used to identify the device in [``crate::bluetooth::Bluetooth``].
+    pub address: String,
+    /// Name the device reports. Empty if unknown
+    pub name: String,
+    /// Human readable name, which can be changed by the user. Falls back to the address
+    pub alias: String,
+    /// Name of the icon for the device (e.g. `audio-headset`). Empty if unknown
+    pub icon: String,
+    /// Name of the adapter the device is known to (e.g. `hci0`)
+    pub adapter: String,
+    /// If the device is connected
+    pub connected: bool,
+    /// If the device is paired
+    pub paired: bool,
+    /// Charge of the battery of the device, if it reports one
+    pub battery: zvariant::Optional<Percentage>,
+}
+
+/// Data relating to a battery
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    PartialOrd,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub struct BatteryData {
+    /// Name of the battery used by the kernel (e.g. `BAT0`)
+    ///
+    /// This is the name used in [``common::config::bar::BarConfig::battery``]
+    pub name: String,
+    /// Model of the battery or the device it is part of. Empty if unknown
+    pub model: String,
+    /// What kind of device the battery powers
+    pub kind: BatteryKind,
+    /// What percentage the battery is charged to
+    pub charge: Percentage,
+    /// The current status of the batter
+    pub status: BatteryStatus,
+    /// Seconds until the battery is empty. 0 if unknown or not discharging
+    pub time_to_empty: u64,
+    /// Seconds until the battery is full. 0 if unknown or not charging
+    pub time_to_full: u64,
+    /// Rate at which energy is drawn from (or put into) the battery in W. 0 if unknown
+    pub energy_rate: f64,
+    /// How much of its original capacity the battery can still hold. 0 if unknown
+    pub health: Percentage,
+    /// How low the charge is according to [``common::config::battery::BatteryConfig``]
+    pub level: BatteryLevel,
+}
+
+/// What the battery is currently doing
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub enum BatteryStatus {
+    /// Losing charge
+    Discharging,
+    /// Being charged
+    Charging,
+    /// Fully charged
+    Full,
+    /// Plugged in, but not being charged (e.g. due to a charge limit)
+    NotCharging,
+    /// Any other states
+    ///
+    /// If any other states are encountered they should be added to this enum. This is only
+    /// intended to act as a fallback and for [``Default``].
+    #[default]
+    Unknown,
+}
+
+impl From<&str> for BatteryStatus {
+    fn from(value: &str) -> Self {
+        match value {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Full" => Self::Full,
+            "Not charging" => Self::NotCharging,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// How low the charge of a battery is
+///
+/// See [``common::config::battery::BatteryConfig``]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub enum BatteryLevel {
+    /// Above [``common::config::battery::BatteryConfig::low``]
+    #[default]
+    Normal,
+    /// At or below [``common::config::battery::BatteryConfig::low``]
+    Low,
+    /// At or below [``common::config::battery::BatteryConfig::critical``]
+    Critical,
+}
+
+impl BatteryLevel {
+    /// Get the level of a battery with `charge`
+    #[must_use]
+    pub fn new(charge: Percentage, config: &BatteryConfig) -> Self {
+        if *charge <= config.critical {
+            Self::Critical
+        } else if *charge <= config.low {
+            Self::Low
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+/// What kind of device a battery powers
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+)]
+pub enum BatteryKind {
+    /// The system itself (e.g. the battery of a laptop)
+    #[default]
+    System,
+    /// A mouse
+    Mouse,
+    /// A keyboard
+    Keyboard,
+    /// A headset or headphones
+    Headset,
+    /// A game controller
+    Gamepad,
+    /// Any other peripheral
+    Other,
+}
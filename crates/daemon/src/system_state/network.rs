@@ -0,0 +1,337 @@
+//! Collector for the network connections using `NetworkManager`
+//!
+//! Instead of polling, this listens to the signals `NetworkManager` sends when its state or the
+//! properties of its objects change. The proxies of all objects are kept around, so their
+//! properties are cached and reading them doesn't require a round trip.
+//!
+//! See [``crate::network::nm``] for the proxies.
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use anyhow::Result;
+use futures_util::{FutureExt, StreamExt, stream};
+use zbus::{
+    MatchRule, MessageStream,
+    message::Type,
+    object_server::InterfaceRef,
+    proxy::{Builder, Defaults},
+    zvariant::{OwnedObjectPath, OwnedValue},
+};
+
+use common::types::{Percentage, Timer};
+
+use super::{
+    ConnectionData, ConnectionKind, Connectivity, NetworkData, SystemState, replace, update_data,
+};
+use crate::network::nm::{
+    AccessPointProxy, ActiveConnectionProxy, DeviceProxy, Ip4ConfigProxy, Ip6ConfigProxy,
+    NM_SERVICE_NAME, NetworkManagerProxy, WIFI_DEVICE_TYPE, WirelessProxy, is_set,
+};
+
+/// How long to wait before reconnecting to `NetworkManager` after an error
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// How long to wait after a change before updating
+///
+/// `NetworkManager` usually sends many changes at once (e.g. while connecting) and the property
+/// caches of the proxies are updated concurrently, so this gives things time to settle.
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+/// Keep [``super::SystemStateData::network``] up to date
+///
+/// `connection` has to be a connection to the system bus.
+pub(super) async fn run(iface: InterfaceRef<SystemState>, connection: zbus::Connection) {
+    loop {
+        if let Err(e) = listen(&iface, &connection).await {
+            log::error!("Failed to listen for NetworkManager changes: {e}");
+        }
+
+        update_data(&iface, |data| {
+            replace(&mut data.network, NetworkData::default())
+        })
+        .await;
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Update the network data every time `NetworkManager` signals a change
+///
+/// Only returns if the connection to `NetworkManager` was lost.
+async fn listen(iface: &InterfaceRef<SystemState>, connection: &zbus::Connection) -> Result<()> {
+    let mut network = Network::new(connection).await?;
+
+    let properties_changed = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender(NM_SERVICE_NAME)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .build();
+    let state_changed = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender(NM_SERVICE_NAME)?
+        .interface(NM_SERVICE_NAME)?
+        .member("StateChanged")?
+        .build();
+
+    let mut changes = stream::select(
+        MessageStream::for_match_rule(properties_changed, connection, None).await?,
+        MessageStream::for_match_rule(state_changed, connection, None).await?,
+    );
+    let mut owner_changes = network.manager.inner().receive_owner_changed().await?;
+
+    // Signals are only sent on change, so the initial value has to be fetched
+    network.update(iface).await;
+
+    loop {
+        tokio::select! {
+            Some(_) = changes.next() => {
+                tokio::time::sleep(SETTLE_DELAY).await;
+
+                // All changes which arrived in the meantime are handled by this update
+                while let Some(Some(_)) = changes.next().now_or_never() {}
+
+                network.update(iface).await;
+            }
+            // The cached objects are gone if NetworkManager restarted
+            _ = owner_changes.next() => {
+                log::warn!("NetworkManager stopped or restarted");
+                return Ok(());
+            }
+            else => return Ok(()),
+        }
+    }
+}
+
+/// Connection to `NetworkManager`
+struct Network {
+    /// Connection to the system bus
+    connection: zbus::Connection,
+    /// The main `NetworkManager` object
+    manager: NetworkManagerProxy<'static>,
+    /// Proxies of the active connections
+    active_connections: ProxyCache<ActiveConnectionProxy<'static>>,
+    /// Proxies of the devices used by active connections
+    devices: ProxyCache<DeviceProxy<'static>>,
+    /// Proxies of the Wi-Fi devices used by active connections
+    wireless: ProxyCache<WirelessProxy<'static>>,
+    /// Proxies of the access points Wi-Fi devices are connected to
+    access_points: ProxyCache<AccessPointProxy<'static>>,
+    /// Proxies of the IPv4 configurations of active connections
+    ip4_configs: ProxyCache<Ip4ConfigProxy<'static>>,
+    /// Proxies of the IPv6 configurations of active connections
+    ip6_configs: ProxyCache<Ip6ConfigProxy<'static>>,
+}
+
+impl Network {
+    /// Connect to `NetworkManager`
+    async fn new(connection: &zbus::Connection) -> zbus::Result<Self> {
+        let manager = NetworkManagerProxy::new(connection).await?;
+
+        // Fails if NetworkManager isn't running
+        manager.connectivity().await?;
+
+        Ok(Self {
+            connection: connection.clone(),
+            manager,
+            active_connections: ProxyCache::default(),
+            devices: ProxyCache::default(),
+            wireless: ProxyCache::default(),
+            access_points: ProxyCache::default(),
+            ip4_configs: ProxyCache::default(),
+            ip6_configs: ProxyCache::default(),
+        })
+    }
+
+    /// Helper function to set [``super::SystemStateData::network``] to the current data
+    async fn update(&mut self, iface: &InterfaceRef<SystemState>) {
+        let _timer = Timer::new(
+            "SystemState network updated",
+            Some(Duration::from_millis(10)),
+        );
+
+        let network = self
+            .network_data()
+            .await
+            .inspect_err(|e| log::error!("Failed to update network information: {e}"))
+            .unwrap_or_default();
+
+        update_data(iface, |data| replace(&mut data.network, network)).await;
+    }
+
+    /// Gathers information about all active connections
+    async fn network_data(&mut self) -> zbus::Result<NetworkData> {
+        let primary = self.manager.primary_connection().await?;
+        let mut connections = Vec::new();
+
+        for path in self.manager.active_connections().await? {
+            // Connections can disappear while they are being read, e.g. while disconnecting
+            match self.connection_data(&path, &primary).await {
+                Ok(connection) => connections.push(connection),
+                Err(e) => log::warn!("Failed to read active connection {path}: {e}"),
+            }
+        }
+
+        connections.sort_by_key(|c| !c.primary);
+
+        self.active_connections.prune();
+        self.devices.prune();
+        self.wireless.prune();
+        self.access_points.prune();
+        self.ip4_configs.prune();
+        self.ip6_configs.prune();
+
+        let wifi_enabled = self.manager.wireless_enabled().await?;
+
+        Ok(NetworkData {
+            connections,
+            connectivity: connectivity(self.manager.connectivity().await?),
+            // NMMetered: 1 = yes, 3 = guessed yes
+            // See: https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMMetered
+            metered: matches!(self.manager.metered().await?, 1 | 3),
+            wifi_enabled,
+            airplane_mode: !wifi_enabled && !self.manager.wwan_enabled().await?,
+        })
+    }
+
+    /// Gathers information about the active connection at `path`
+    async fn connection_data(
+        &mut self,
+        path: &OwnedObjectPath,
+        primary: &OwnedObjectPath,
+    ) -> zbus::Result<ConnectionData> {
+        let active = self.active_connections.get(&self.connection, path).await?;
+
+        let mut data = ConnectionData {
+            id: active.id().await?,
+            kind: connection_kind(&active.kind().await?, active.vpn().await?),
+            primary: path == primary,
+            ..ConnectionData::default()
+        };
+
+        for device_path in active.devices().await? {
+            let device = self.devices.get(&self.connection, &device_path).await?;
+            data.interfaces.push(device.interface().await?);
+
+            if device.device_type().await? != WIFI_DEVICE_TYPE {
+                continue;
+            }
+
+            let wireless = self.wireless.get(&self.connection, &device_path).await?;
+            let access_point_path = wireless.active_access_point().await?;
+
+            if is_set(&access_point_path) {
+                let access_point = self
+                    .access_points
+                    .get(&self.connection, &access_point_path)
+                    .await?;
+
+                data.ssid = String::from_utf8_lossy(&access_point.ssid().await?).to_string();
+                data.signal = Percentage::from(access_point.strength().await?);
+            }
+        }
+
+        let ip4_config = active.ip4_config().await?;
+        if is_set(&ip4_config) {
+            let config = self.ip4_configs.get(&self.connection, &ip4_config).await?;
+            data.addresses
+                .extend(addresses(config.address_data().await?));
+        }
+
+        let ip6_config = active.ip6_config().await?;
+        if is_set(&ip6_config) {
+            let config = self.ip6_configs.get(&self.connection, &ip6_config).await?;
+            data.addresses
+                .extend(addresses(config.address_data().await?));
+        }
+
+        Ok(data)
+    }
+}
+
+/// Proxies of `NetworkManager` objects of one kind, keyed by their path
+///
+/// Proxies which weren't used since the last call to [``Self::prune``] are dropped then.
+struct ProxyCache<P> {
+    /// The cached proxies
+    proxies: HashMap<OwnedObjectPath, P>,
+    /// Paths of the proxies used since the last call to [``Self::prune``]
+    used: HashSet<OwnedObjectPath>,
+}
+
+impl<P> Default for ProxyCache<P> {
+    fn default() -> Self {
+        Self {
+            proxies: HashMap::new(),
+            used: HashSet::new(),
+        }
+    }
+}
+
+impl<P> ProxyCache<P>
+where
+    P: Defaults + From<zbus::Proxy<'static>> + Clone,
+{
+    /// Get the proxy of the object at `path`, creating it if it isn't cached yet
+    async fn get(
+        &mut self,
+        connection: &zbus::Connection,
+        path: &OwnedObjectPath,
+    ) -> zbus::Result<P> {
+        self.used.insert(path.clone());
+
+        if let Some(proxy) = self.proxies.get(path) {
+            return Ok(proxy.clone());
+        }
+
+        let proxy: P = Builder::new(connection).path(path.clone())?.build().await?;
+
+        self.proxies.insert(path.clone(), proxy.clone());
+
+        Ok(proxy)
+    }
+
+    /// Drop all proxies which weren't used since the last call
+    fn prune(&mut self) {
+        let used = std::mem::take(&mut self.used);
+        self.proxies.retain(|path, _| used.contains(path));
+    }
+}
+
+/// Helper function to format the `AddressData` of an IP configuration (e.g. `192.168.1.2/24`)
+fn addresses(address_data: Vec<HashMap<String, OwnedValue>>) -> impl Iterator<Item = String> {
+    address_data.into_iter().filter_map(|address| {
+        let ip = address.get("address")?.downcast_ref::<&str>().ok()?;
+        let prefix = address.get("prefix")?.downcast_ref::<u32>().ok()?;
+
+        Some(format!("{ip}/{prefix}"))
+    })
+}
+
+/// Helper function to convert the type of a connection profile to a [``ConnectionKind``]
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-settings-dbus.html>
+fn connection_kind(kind: &str, vpn: bool) -> ConnectionKind {
+    match kind {
+        "802-3-ethernet" => ConnectionKind::Wired,
+        "802-11-wireless" => ConnectionKind::Wireless,
+        "wireguard" => ConnectionKind::WireGuard,
+        _ if vpn => ConnectionKind::Vpn,
+        _ => ConnectionKind::Other,
+    }
+}
+
+/// Helper function to convert a `NMConnectivityState` to [``Connectivity``]
+///
+/// See: <https://networkmanager.dev/docs/api/latest/nm-dbus-types.html#NMConnectivityState>
+const fn connectivity(state: u32) -> Connectivity {
+    match state {
+        1 => Connectivity::None,
+        2 => Connectivity::Portal,
+        3 => Connectivity::Limited,
+        4 => Connectivity::Full,
+        _ => Connectivity::Unknown,
+    }
+}
@@ -0,0 +1,245 @@
+//! Reading temperatures from hwmon devices and thermal zones
+//!
+//! See:
+//! - <https://www.kernel.org/doc/html/latest/hwmon/sysfs-interface.html>
+//! - <https://www.kernel.org/doc/html/latest/driver-api/thermal/sysfs-api.html>
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use common::config::metrics::MetricsConfig;
+
+use super::Temperatures;
+use crate::util::entries;
+
+/// Directory containing all hwmon devices
+const HWMON_PATH: &str = "/sys/class/hwmon/";
+
+/// Directory containing all thermal zones
+const THERMAL_PATH: &str = "/sys/class/thermal/";
+
+/// Sensors tried for the CPU if [``MetricsConfig::cpu_sensor``] isn't set
+const CPU_SENSORS: [&str; 6] = [
+    "coretemp",
+    "k10temp",
+    "zenpower",
+    "x86_pkg_temp",
+    "cpu_thermal",
+    "cpu-thermal",
+];
+
+/// Sensors tried for the GPU if [``MetricsConfig::gpu_sensor``] isn't set
+const GPU_SENSORS: [&str; 3] = ["amdgpu", "nouveau", "radeon"];
+
+/// Sensors tried for the `NVMe` drive if [``MetricsConfig::nvme_sensor``] isn't set
+const NVME_SENSORS: [&str; 1] = ["nvme"];
+
+/// Files the temperatures are read from
+///
+/// Finding the sensors means reading a lot of files, so this is only done when the config changes.
+#[derive(Debug, Default)]
+pub(super) struct Sensors {
+    /// The config the sensors were found with
+    config: Option<MetricsConfig>,
+    /// See [``Temperatures::cpu``]
+    cpu: Option<PathBuf>,
+    /// See [``Temperatures::gpu``]
+    gpu: Option<PathBuf>,
+    /// See [``Temperatures::nvme``]
+    nvme: Option<PathBuf>,
+}
+
+impl Sensors {
+    /// Read all temperatures, first finding the sensors if `config` changed
+    pub(super) async fn temperatures(&mut self, config: &MetricsConfig) -> Temperatures {
+        if self.config.as_ref() != Some(config) {
+            self.cpu = find(config.cpu_sensor.as_deref(), &CPU_SENSORS).await;
+            self.gpu = find(config.gpu_sensor.as_deref(), &GPU_SENSORS).await;
+            self.nvme = find(config.nvme_sensor.as_deref(), &NVME_SENSORS).await;
+            self.config = Some(config.clone());
+        }
+
+        Temperatures {
+            cpu: read_temperature(self.cpu.as_deref()).await.into(),
+            gpu: read_temperature(self.gpu.as_deref()).await.into(),
+            nvme: read_temperature(self.nvme.as_deref()).await.into(),
+        }
+    }
+}
+
+/// Helper function to find the `configured` sensor, or the first of `defaults` if it isn't set
+async fn find(configured: Option<&str>, defaults: &[&str]) -> Option<PathBuf> {
+    let hwmon = Path::new(HWMON_PATH);
+    let thermal = Path::new(THERMAL_PATH);
+
+    if let Some(sensor) = configured {
+        let path = find_sensor(hwmon, thermal, sensor).await;
+
+        if path.is_none() {
+            log::warn!("Failed to find temperature sensor {sensor}");
+        }
+
+        return path;
+    }
+
+    for sensor in defaults {
+        if let Some(path) = find_sensor(hwmon, thermal, sensor).await {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+/// Find the file containing the temperature of `sensor`
+///
+/// See [``MetricsConfig``] for the format of `sensor`.
+async fn find_sensor(hwmon: &Path, thermal: &Path, sensor: &str) -> Option<PathBuf> {
+    let (device, label) = sensor
+        .split_once('/')
+        .map_or((sensor, None), |(device, label)| (device, Some(label)));
+
+    for dir in entries(hwmon).await {
+        if read_string(&dir.join("name")).await.as_deref() != Some(device) {
+            continue;
+        }
+
+        let Some(label) = label else {
+            // The first temperature is usually the main one (e.g. the CPU package)
+            let input = dir.join("temp1_input");
+
+            if fs::try_exists(&input).await.unwrap_or_default() {
+                return Some(input);
+            }
+
+            continue;
+        };
+
+        for file in entries(&dir).await {
+            let Some(name) = file.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+
+            if let Some(prefix) = name.strip_suffix("_label")
+                && prefix.starts_with("temp")
+                && read_string(&file).await.as_deref() == Some(label)
+            {
+                return Some(dir.join(format!("{prefix}_input")));
+            }
+        }
+    }
+
+    for dir in entries(thermal).await {
+        if dir
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with("thermal_zone"))
+            && read_string(&dir.join("type")).await.as_deref() == Some(sensor)
+        {
+            return Some(dir.join("temp"));
+        }
+    }
+
+    None
+}
+
+/// Helper function to read the file at `path` without the trailing newline
+async fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .await
+        .ok()
+        .map(|s| s.trim_end().to_string())
+}
+
+/// Helper function to read a temperature in m°C from the file at `path` and convert it to °C
+async fn read_temperature(path: Option<&Path>) -> Option<f64> {
+    read_string(path?)
+        .await?
+        .parse::<i32>()
+        .ok()
+        .map(|millidegrees| f64::from(millidegrees) / 1000.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_sensor, read_temperature};
+    use crate::util::sysfs_dir;
+
+    #[tokio::test]
+    async fn finds_sensors() {
+        let dir = sysfs_dir(&[
+            ("hwmon/hwmon0/name", "nvme\n"),
+            ("hwmon/hwmon0/temp1_input", "38850\n"),
+            ("hwmon/hwmon1/name", "coretemp\n"),
+            ("hwmon/hwmon1/temp1_input", "45000\n"),
+            ("hwmon/hwmon1/temp2_label", "Core 0\n"),
+            ("hwmon/hwmon1/temp2_input", "43000\n"),
+            ("thermal/thermal_zone0/type", "x86_pkg_temp\n"),
+            ("thermal/thermal_zone0/temp", "45000\n"),
+        ]);
+        let hwmon = dir.path().join("hwmon");
+        let thermal = dir.path().join("thermal");
+
+        assert_eq!(
+            find_sensor(&hwmon, &thermal, "coretemp").await,
+            Some(hwmon.join("hwmon1/temp1_input"))
+        );
+        assert_eq!(
+            find_sensor(&hwmon, &thermal, "coretemp/Core 0").await,
+            Some(hwmon.join("hwmon1/temp2_input"))
+        );
+        assert_eq!(
+            find_sensor(&hwmon, &thermal, "x86_pkg_temp").await,
+            Some(thermal.join("thermal_zone0/temp"))
+        );
+        assert_eq!(find_sensor(&hwmon, &thermal, "amdgpu").await, None);
+    }
+
+    #[tokio::test]
+    async fn missing_label_is_not_found() {
+        let dir = sysfs_dir(&[
+            ("hwmon/hwmon0/name", "coretemp\n"),
+            ("hwmon/hwmon0/temp1_input", "45000\n"),
+            ("hwmon/hwmon0/temp2_label", "Core 0\n"),
+        ]);
+
+        assert_eq!(
+            find_sensor(
+                &dir.path().join("hwmon"),
+                &dir.path().join("thermal"),
+                "coretemp/Core 1"
+            )
+            .await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_device_without_temperature() {
+        let dir = sysfs_dir(&[
+            ("hwmon/hwmon0/name", "amdgpu\n"),
+            ("hwmon/hwmon1/name", "amdgpu\n"),
+            ("hwmon/hwmon1/temp1_input", "52000\n"),
+        ]);
+        let hwmon = dir.path().join("hwmon");
+
+        assert_eq!(
+            find_sensor(&hwmon, &dir.path().join("thermal"), "amdgpu").await,
+            Some(hwmon.join("hwmon1/temp1_input"))
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_temperature() {
+        let dir = sysfs_dir(&[("temp1_input", "38850\n"), ("temp2_input", "N/A\n")]);
+
+        assert_eq!(
+            read_temperature(Some(&dir.path().join("temp1_input"))).await,
+            Some(38.85)
+        );
+        assert_eq!(
+            read_temperature(Some(&dir.path().join("temp2_input"))).await,
+            None
+        );
+        assert_eq!(read_temperature(None).await, None);
+    }
+}
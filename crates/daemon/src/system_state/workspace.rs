@@ -0,0 +1,76 @@
+//! Collector for the active Hyprland workspace
+//!
+//! Instead of polling, this listens to the events Hyprland sends over its socket.
+//! See: <https://wiki.hypr.land/IPC/>
+use std::{env, path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result};
+use hyprland::shared::HyprDataActive;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::UnixStream,
+};
+use zbus::object_server::InterfaceRef;
+
+use super::{SystemState, replace, update_data};
+
+/// How long to wait before reconnecting to the Hyprland socket after an error
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Hyprland events after which the active workspace might have changed
+const WORKSPACE_EVENTS: [&str; 4] = ["workspace", "workspacev2", "focusedmon", "focusedmonv2"];
+
+/// Keep [``super::SystemStateData::workspace``] up to date
+pub(super) async fn run(iface: InterfaceRef<SystemState>) {
+    loop {
+        if let Err(e) = listen(&iface).await {
+            log::error!("Failed to listen for Hyprland events: {e}");
+        }
+
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Update the workspace every time Hyprland signals a change
+///
+/// Only returns if the connection to Hyprland was lost.
+async fn listen(iface: &InterfaceRef<SystemState>) -> Result<()> {
+    let stream = UnixStream::connect(socket_path()?).await?;
+    let mut lines = BufReader::new(stream).lines();
+
+    // Events are only sent on change, so the initial value has to be fetched
+    update_workspace(iface).await;
+
+    while let Some(line) = lines.next_line().await? {
+        let event = line
+            .split_once(">>")
+            .map_or(line.as_str(), |(event, _)| event);
+
+        if WORKSPACE_EVENTS.contains(&event) {
+            update_workspace(iface).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Helper function to set [``super::SystemStateData::workspace``] to the active workspace
+async fn update_workspace(iface: &InterfaceRef<SystemState>) {
+    let workspace = hyprland::data::Workspace::get_active()
+        .inspect_err(|e| log::error!("Failed to get an active workspace: {e}"))
+        .map_or(0, |w| w.id);
+
+    update_data(iface, |data| replace(&mut data.workspace, workspace)).await;
+}
+
+/// Path to the socket Hyprland sends events on
+fn socket_path() -> Result<PathBuf> {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").context("XDG_RUNTIME_DIR isn't set")?;
+    let instance = env::var_os("HYPRLAND_INSTANCE_SIGNATURE")
+        .context("HYPRLAND_INSTANCE_SIGNATURE isn't set")?;
+
+    Ok(PathBuf::from(runtime_dir)
+        .join("hypr")
+        .join(instance)
+        .join(".socket2.sock"))
+}
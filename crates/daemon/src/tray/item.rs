@@ -0,0 +1,461 @@
+//! Tracking a single `StatusNotifierItem`
+//!
+//! Every registered item is tracked by its own task (see [``spawn``]), which keeps the item in
+//! [``super::Tray``] up to date until the application owning it disappears.
+//!
+//! Items signal which of their properties changed (e.g. `NewToolTip`), so only those are read
+//! again (see [``changed_properties``]).
+use std::collections::HashMap;
+
+use futures_util::{StreamExt, stream};
+use zbus::{
+    fdo,
+    names::{InterfaceName, MemberName},
+    object_server::InterfaceRef,
+    proxy,
+    proxy::CacheProperties,
+    zvariant::OwnedValue,
+};
+
+use super::{
+    ItemStatus, MenuEntry, Pixmap, Tray, TrayItem, items_changed, menu::DBusMenuProxy, watcher,
+};
+
+/// Name of the interface implemented by all tray items
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+
+/// Properties of an item which make up a [``TrayItem``]
+const PROPERTIES: &[&str] = &[
+    "Title",
+    "Status",
+    "IconName",
+    "IconThemePath",
+    "IconPixmap",
+    "AttentionIconName",
+    "AttentionIconPixmap",
+    "ToolTip",
+    "ItemIsMenu",
+];
+
+/// Proxy for the `org.kde.StatusNotifierItem` interface of a tray item
+///
+/// Properties are read using `org.freedesktop.DBus.Properties`, since items only signal that they
+/// have changed, without the new value.
+#[proxy(interface = "org.kde.StatusNotifierItem", gen_blocking = false)]
+pub trait StatusNotifierItem {
+    /// Activate the item, e.g. open the main window of the application
+    fn activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    /// Secondary activate the item
+    fn secondary_activate(&self, x: i32, y: i32) -> zbus::Result<()>;
+
+    /// Ask the item to show its own context menu
+    fn context_menu(&self, x: i32, y: i32) -> zbus::Result<()>;
+}
+
+/// Start tracking the item owned by `destination` at `path`
+///
+/// Does nothing if the item is already tracked. Once the item has been read, the watcher signals
+/// that it was registered.
+pub fn spawn(tray: InterfaceRef<Tray>, destination: String, path: String) {
+    tokio::spawn(async move {
+        let id = format!("{destination}{path}");
+
+        if !tray.get_mut().await.start_tracking(&id) {
+            return;
+        }
+
+        log::info!("Tracking tray item {id}");
+
+        if let Err(e) = track(&tray, &id, &destination, &path).await {
+            log::warn!("Stopped tracking tray item {id}: {e}");
+        }
+
+        let mut items = tray.get_mut().await;
+
+        // The item might not have been read
+        items.pending.remove(&id);
+
+        if !items.remove(&id) {
+            return;
+        }
+
+        items_changed(&items, tray.signal_emitter()).await;
+
+        // The watcher reads the items when signaling the change
+        drop(items);
+
+        if let Err(e) = watcher::unregistered(tray.signal_emitter(), &id).await {
+            log::error!("Failed to signal tray item {id} unregistered: {e}");
+        }
+    });
+}
+
+/// Helper function to keep the item `id` up to date until the owner of `destination` disappears
+///
+/// # Errors
+///
+/// Errors if the item can't be read or a dbus error occurs.
+async fn track(
+    tray: &InterfaceRef<Tray>,
+    id: &str,
+    destination: &str,
+    path: &str,
+) -> zbus::Result<()> {
+    let connection = tray.signal_emitter().connection();
+
+    let properties = fdo::PropertiesProxy::builder(connection)
+        .destination(destination.to_string())?
+        .path(path.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let item = StatusNotifierItemProxy::builder(connection)
+        .destination(destination.to_string())?
+        .path(path.to_string())?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await?;
+
+    let mut owner_changed = fdo::DBusProxy::new(connection)
+        .await?
+        .receive_name_owner_changed_with_args(&[(0, destination)])
+        .await?;
+
+    let (mut data, menu_path) = read_item(&properties, id).await?;
+
+    let menu = match &menu_path {
+        Some(menu_path) => Some(
+            DBusMenuProxy::builder(connection)
+                .destination(destination.to_string())?
+                .path(menu_path.clone())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?,
+        ),
+        None => None,
+    };
+
+    let mut item_signals = item.inner().receive_all_signals().await?;
+    let mut menu_signals = match &menu {
+        Some(menu) => menu.inner().receive_all_signals().await?.boxed(),
+        None => stream::pending().boxed(),
+    };
+
+    data.menu = read_menu(menu.as_ref(), id).await;
+
+    update(tray, data.clone(), destination, path, menu_path.as_ref()).await;
+
+    loop {
+        tokio::select! {
+            signal = item_signals.next() => {
+                let Some(signal) = signal else {
+                    return Ok(());
+                };
+
+                let header = signal.header();
+                let member = header.member().map(MemberName::as_str).unwrap_or_default();
+
+                if let Some(names) = changed_properties(member) {
+                    read_properties(&properties, &mut data, names).await?;
+                } else {
+                    // The menu path can't change, only the menu itself
+                    let menu = std::mem::take(&mut data.menu);
+                    data = read_item(&properties, id).await?.0;
+                    data.menu = menu;
+                }
+            }
+            signal = menu_signals.next() => {
+                if signal.is_none() {
+                    return Ok(());
+                }
+
+                data.menu = read_menu(menu.as_ref(), id).await;
+            }
+            changed = owner_changed.next() => {
+                let owned = changed
+                    .and_then(|changed| changed.args().ok().map(|args| args.new_owner().is_some()))
+                    .unwrap_or_default();
+
+                if !owned {
+                    return Ok(());
+                }
+
+                continue;
+            }
+        }
+
+        update(tray, data.clone(), destination, path, menu_path.as_ref()).await;
+    }
+}
+
+/// Helper function to store `data` in `tray` and emit that it has changed
+///
+/// Nothing is emitted if `data` is unchanged. The first time the item is stored, the items have
+/// changed and the watcher also signals that it was registered. Afterwards only
+/// [``Tray::ITEM_CHANGED``] is emitted.
+async fn update(
+    tray: &InterfaceRef<Tray>,
+    data: TrayItem,
+    destination: &str,
+    path: &str,
+    menu: Option<&String>,
+) {
+    let mut items = tray.get_mut().await;
+
+    let id = data.id.clone();
+
+    if items.item(&id).is_ok_and(|item| item.data == data) {
+        return;
+    }
+
+    let registered = items.pending.remove(&id);
+
+    if items.insert(data.clone(), destination, path, menu.cloned()) {
+        items_changed(&items, tray.signal_emitter()).await;
+    } else if let Err(e) = Tray::item_changed(tray.signal_emitter(), &data).await {
+        log::error!("Failed to signal tray item {id} changed: {e}");
+    }
+
+    // The watcher reads the items when signaling the change
+    drop(items);
+
+    if registered && let Err(e) = watcher::registered(tray.signal_emitter(), &id).await {
+        log::error!("Failed to signal tray item {id} registered: {e}");
+    }
+}
+
+/// Helper function to get the properties which changed when the item sent the signal `member`
+///
+/// Returns [``None``] for unknown signals, after which all properties are read again.
+fn changed_properties(member: &str) -> Option<&'static [&'static str]> {
+    Some(match member {
+        "NewTitle" => &["Title"],
+        "NewStatus" => &["Status"],
+        "NewIcon" => &["IconName", "IconPixmap"],
+        "NewIconThemePath" => &["IconThemePath"],
+        "NewAttentionIcon" => &["AttentionIconName", "AttentionIconPixmap"],
+        "NewToolTip" => &["ToolTip"],
+        // Overlay icons aren't shown
+        "NewOverlayIcon" => &[],
+        _ => return None,
+    })
+}
+
+/// Helper function to read the properties `names` of an item into `item`
+///
+/// Properties the item doesn't have are left empty.
+///
+/// # Errors
+///
+/// Errors if a dbus error other than a missing property occurs.
+async fn read_properties(
+    properties: &fdo::PropertiesProxy<'_>,
+    item: &mut TrayItem,
+    names: &[&str],
+) -> zbus::Result<()> {
+    for name in names {
+        let value = match properties
+            .get(
+                InterfaceName::from_static_str_unchecked(ITEM_INTERFACE),
+                name,
+            )
+            .await
+        {
+            Ok(value) => Some(value),
+            Err(fdo::Error::UnknownProperty(_) | fdo::Error::InvalidArgs(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        set_property(item, name, value.as_ref());
+    }
+
+    Ok(())
+}
+
+/// Helper function to read all properties of the item `id`
+///
+/// Returns the item, without its menu, and the path of its menu.
+///
+/// # Errors
+///
+/// Errors if the properties can't be read.
+async fn read_item(
+    properties: &fdo::PropertiesProxy<'_>,
+    id: &str,
+) -> zbus::Result<(TrayItem, Option<String>)> {
+    let properties = properties
+        .get_all(InterfaceName::from_static_str_unchecked(ITEM_INTERFACE))
+        .await?;
+
+    Ok(parse_item(id, &properties))
+}
+
+/// Helper function to read the menu of the item `id`
+///
+/// Returns an empty menu if it can't be read, since many items work without one.
+async fn read_menu(menu: Option<&DBusMenuProxy<'_>>, id: &str) -> Vec<MenuEntry> {
+    let Some(menu) = menu else {
+        return Vec::new();
+    };
+
+    match menu.get_layout(0, -1, &[]).await {
+        Ok((_, layout)) => layout.entries(),
+        Err(e) => {
+            log::warn!("Failed to read menu of tray item {id}: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// Helper function to create the item `id` from its `properties`
+///
+/// Missing or invalid properties are left empty, since many applications only set some of them.
+fn parse_item(id: &str, properties: &HashMap<String, OwnedValue>) -> (TrayItem, Option<String>) {
+    let mut item = TrayItem {
+        id: id.to_string(),
+        ..Default::default()
+    };
+
+    for name in PROPERTIES {
+        set_property(&mut item, name, properties.get(*name));
+    }
+
+    let menu = properties
+        .get("Menu")
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| zbus::zvariant::OwnedObjectPath::try_from(v).ok())
+        .map(|p| p.to_string())
+        .filter(|p| p != "/");
+
+    (item, menu)
+}
+
+/// Helper function to set the field of `item` for the property `name` to `value`
+///
+/// Missing or invalid values are set as empty.
+fn set_property(item: &mut TrayItem, name: &str, value: Option<&OwnedValue>) {
+    let string = || {
+        value
+            .and_then(|v| <&str>::try_from(v).ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let pixmaps = || {
+        value
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| Vec::<Pixmap>::try_from(v).ok())
+            .unwrap_or_default()
+    };
+
+    match name {
+        "Title" => item.title = string(),
+        "Status" => item.status = ItemStatus::from(string().as_str()),
+        "IconName" => item.icon_name = string(),
+        "IconThemePath" => item.icon_theme_path = string(),
+        "IconPixmap" => item.icon = pixmaps(),
+        "AttentionIconName" => item.attention_icon_name = string(),
+        "AttentionIconPixmap" => item.attention_icon = pixmaps(),
+        "ToolTip" => {
+            // (icon name, icon pixmaps, title, body)
+            (item.tooltip_title, item.tooltip_body) = value
+                .and_then(|v| v.try_clone().ok())
+                .and_then(|v| <(String, Vec<Pixmap>, String, String)>::try_from(v).ok())
+                .map(|(_, _, title, body)| (title, body))
+                .unwrap_or_default();
+        }
+        "ItemIsMenu" => {
+            item.item_is_menu = value
+                .and_then(|v| bool::try_from(v).ok())
+                .unwrap_or_default();
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+    use super::{changed_properties, parse_item, set_property};
+    use crate::tray::{ItemStatus, Pixmap, TrayItem};
+
+    #[test]
+    fn parses_item() {
+        let pixmap = Pixmap {
+            width: 1,
+            height: 1,
+            data: vec![255, 255, 0, 0],
+        };
+
+        let properties: HashMap<String, OwnedValue> = HashMap::from([
+            (
+                "Title".to_string(),
+                Value::from("Nextcloud").try_into().unwrap(),
+            ),
+            (
+                "Status".to_string(),
+                Value::from("NeedsAttention").try_into().unwrap(),
+            ),
+            (
+                "IconPixmap".to_string(),
+                Value::from(vec![pixmap.clone()]).try_into().unwrap(),
+            ),
+            (
+                "ToolTip".to_string(),
+                Value::from((
+                    "",
+                    Vec::<Pixmap>::new(),
+                    "Synced",
+                    "All files are up to date",
+                ))
+                .try_into()
+                .unwrap(),
+            ),
+            (
+                "Menu".to_string(),
+                Value::from(ObjectPath::from_static_str_unchecked("/MenuBar"))
+                    .try_into()
+                    .unwrap(),
+            ),
+        ]);
+
+        let (item, menu) = parse_item(":1.42/StatusNotifierItem", &properties);
+
+        assert_eq!(item.id, ":1.42/StatusNotifierItem");
+        assert_eq!(item.title, "Nextcloud");
+        assert_eq!(item.status, ItemStatus::NeedsAttention);
+        assert_eq!(item.icon, [pixmap]);
+        assert!(item.icon_name.is_empty());
+        assert_eq!(item.tooltip_title, "Synced");
+        assert_eq!(item.tooltip_body, "All files are up to date");
+        assert!(!item.item_is_menu);
+        assert_eq!(menu.as_deref(), Some("/MenuBar"));
+    }
+
+    #[test]
+    fn updates_changed_properties() {
+        let mut item = TrayItem {
+            icon_name: "nextcloud".to_string(),
+            tooltip_title: "Syncing".to_string(),
+            ..Default::default()
+        };
+
+        let names = changed_properties("NewToolTip").unwrap();
+        let tooltip: OwnedValue = Value::from(("", Vec::<Pixmap>::new(), "Synced", ""))
+            .try_into()
+            .unwrap();
+
+        for name in names {
+            set_property(&mut item, name, Some(&tooltip));
+        }
+
+        assert_eq!(item.tooltip_title, "Synced");
+        assert_eq!(item.icon_name, "nextcloud");
+        assert!(changed_properties("NewMenu").is_none());
+    }
+}
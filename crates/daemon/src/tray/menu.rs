@@ -0,0 +1,222 @@
+//! Reading the menus of tray items, which are exported using `DBusMenu`
+//!
+//! See: <https://github.com/AyatanaIndicators/libdbusmenu/blob/master/libdbusmenu-glib/dbus-menu.xml>
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use zbus::{
+    proxy,
+    zvariant::{self, OwnedValue, Value},
+};
+
+use super::{MenuEntry, MenuToggle};
+
+/// Proxy for the `com.canonical.dbusmenu` interface of a tray item's menu
+#[proxy(interface = "com.canonical.dbusmenu", gen_blocking = false)]
+pub trait DBusMenu {
+    /// Get the layout of the menu below `parent_id`
+    ///
+    /// A `recursion_depth` of -1 gets all levels and empty `property_names` gets all properties.
+    fn get_layout(
+        &self,
+        parent_id: i32,
+        recursion_depth: i32,
+        property_names: &[&str],
+    ) -> zbus::Result<(u32, Layout)>;
+
+    /// Send an event (e.g. `clicked`) to the entry `id`
+    fn event(
+        &self,
+        id: i32,
+        event_id: &str,
+        data: &zvariant::Value<'_>,
+        timestamp: u32,
+    ) -> zbus::Result<()>;
+
+    /// Tell the application the menu below `id` is about to be shown
+    ///
+    /// Returns if the menu should be read again.
+    fn about_to_show(&self, id: i32) -> zbus::Result<bool>;
+}
+
+/// The root of a menu as returned by [``DBusMenuProxy::get_layout``]
+///
+/// Its children have the same layout, but are sent as variants.
+#[derive(Debug, Deserialize, zvariant::Type)]
+pub struct Layout {
+    /// Id of the entry
+    id: i32,
+    /// Properties of the entry
+    #[allow(
+        dead_code,
+        reason = "Needed to deserialize the layout, the root has no useful properties."
+    )]
+    properties: HashMap<String, OwnedValue>,
+    /// The children of the entry
+    children: Vec<OwnedValue>,
+}
+
+impl Layout {
+    /// Flatten the layout into a list of entries, parents before their children
+    ///
+    /// Invisible entries and their children are left out.
+    #[must_use]
+    pub fn entries(&self) -> Vec<MenuEntry> {
+        let mut entries = Vec::new();
+
+        for child in &self.children {
+            flatten(child, self.id, &mut entries);
+        }
+
+        entries
+    }
+}
+
+/// Helper function to add the entry `value` and all of its children to `entries`
+fn flatten(value: &Value<'_>, parent: i32, entries: &mut Vec<MenuEntry>) {
+    let value = match value {
+        Value::Value(inner) => inner,
+        value => value,
+    };
+
+    let Value::Structure(structure) = value else {
+        return;
+    };
+
+    let [
+        Value::I32(id),
+        Value::Dict(properties),
+        Value::Array(children),
+    ] = structure.fields()
+    else {
+        return;
+    };
+
+    let mut entry = MenuEntry {
+        id: *id,
+        parent,
+        enabled: true,
+        ..Default::default()
+    };
+
+    for (key, value) in properties.iter() {
+        let Value::Str(key) = key else {
+            continue;
+        };
+
+        let value = match value {
+            Value::Value(inner) => inner,
+            value => value,
+        };
+
+        match (key.as_str(), value) {
+            ("label", Value::Str(label)) => entry.label = strip_mnemonics(label),
+            ("enabled", Value::Bool(enabled)) => entry.enabled = *enabled,
+            ("visible", Value::Bool(false)) => return,
+            ("type", Value::Str(kind)) => entry.separator = kind.as_str() == "separator",
+            ("toggle-type", Value::Str(toggle)) => {
+                entry.toggle = match toggle.as_str() {
+                    "checkmark" => MenuToggle::Checkmark,
+                    "radio" => MenuToggle::Radio,
+                    _ => MenuToggle::None,
+                };
+            }
+            ("toggle-state", Value::I32(state)) => entry.checked = *state == 1,
+            _ => (),
+        }
+    }
+
+    entries.push(entry);
+
+    for child in children.inner() {
+        flatten(child, *id, entries);
+    }
+}
+
+/// Helper function to remove the underscores marking access keys from `label`
+///
+/// Two underscores are a literal underscore.
+fn strip_mnemonics(label: &str) -> String {
+    let mut stripped = String::with_capacity(label.len());
+    let mut chars = label.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '_' {
+            if let Some(next) = chars.next() {
+                stripped.push(next);
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::Layout;
+    use crate::tray::MenuToggle;
+
+    /// Helper function to create the variant of a menu entry
+    fn entry(
+        id: i32,
+        properties: &[(&str, Value<'static>)],
+        children: Vec<Value<'static>>,
+    ) -> Value<'static> {
+        let properties: HashMap<String, Value<'static>> = properties
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), v.try_clone().unwrap()))
+            .collect();
+
+        Value::new((id, properties, children))
+    }
+
+    #[test]
+    fn flattens_layout() {
+        let layout = Layout {
+            id: 0,
+            properties: HashMap::new(),
+            children: vec![
+                entry(1, &[("label", Value::from("_Open Window"))], vec![]),
+                entry(2, &[("type", Value::from("separator"))], vec![]),
+                entry(
+                    3,
+                    &[("label", Value::from("Settings"))],
+                    vec![entry(
+                        4,
+                        &[
+                            ("label", Value::from("Start__up")),
+                            ("toggle-type", Value::from("checkmark")),
+                            ("toggle-state", Value::from(1)),
+                            ("enabled", Value::from(false)),
+                        ],
+                        vec![],
+                    )],
+                ),
+                entry(5, &[("visible", Value::from(false))], vec![]),
+            ]
+            .into_iter()
+            .map(|v| OwnedValue::try_from(v).unwrap())
+            .collect(),
+        };
+
+        let entries = layout.entries();
+
+        assert_eq!(
+            entries.iter().map(|e| (e.id, e.parent)).collect::<Vec<_>>(),
+            [(1, 0), (2, 0), (3, 0), (4, 3)]
+        );
+
+        assert_eq!(entries[0].label, "Open Window");
+        assert!(entries[1].separator);
+        assert_eq!(entries[3].label, "Start_up");
+        assert_eq!(entries[3].toggle, MenuToggle::Checkmark);
+        assert!(entries[3].checked);
+        assert!(!entries[3].enabled);
+    }
+}
@@ -0,0 +1,461 @@
+//! This module contains items relating to the system tray
+//!
+//! Applications (e.g. Nextcloud or nm-applet) register `StatusNotifierItem`s with the
+//! [``watcher::Watcher``], which is implemented by the daemon itself. Each item is then tracked
+//! by its own task (see [``item``]) and shown by the bar.
+//!
+//! [``Tray::items``] only changes when items are added or removed. Changes to a single item are
+//! signaled using [``Tray::ITEM_CHANGED``], so e.g. a changed tooltip doesn't resend the icons of
+//! all items.
+//!
+//! The main type is [``Tray``]
+//!
+//! See: <https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/>
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use zbus::{
+    fdo, interface,
+    object_server::{Interface, SignalEmitter},
+    zvariant::{self, Value},
+};
+
+use item::StatusNotifierItemProxy;
+use menu::DBusMenuProxy;
+
+pub mod item;
+pub mod menu;
+pub mod watcher;
+
+/// All items currently registered with the [``watcher::Watcher``]
+///
+/// The items are kept in the order they were registered in.
+///
+/// ## Dbus
+///
+/// This struct implements [``zbus::object_server::Interface``], which means it acts as a dbus
+/// interface. For available zbus methods and properties see [``TrayProxy``]
+#[derive(Debug, Default)]
+pub struct Tray {
+    /// The tracked items
+    items: Vec<Item>,
+    /// Ids of items which are being tracked, but haven't been read yet
+    ///
+    /// See [``Self::start_tracking``]
+    pending: HashSet<String>,
+}
+
+/// A [``TrayItem``] together with where it can be reached
+#[derive(Debug)]
+struct Item {
+    /// The data exposed over dbus
+    data: TrayItem,
+    /// Bus name of the application owning the item
+    destination: String,
+    /// Path of the item
+    path: String,
+    /// Path of the menu of the item, if it has one
+    menu: Option<String>,
+}
+
+/// A tray item, as registered by an application
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub struct TrayItem {
+    /// Bus name and path of the item, which uniquely identify it
+    pub id: String,
+    /// Name of the application
+    pub title: String,
+    /// If the item should be shown and if it needs attention
+    pub status: ItemStatus,
+    /// Name of the icon
+    pub icon_name: String,
+    /// Additional directory to search for [``Self::icon_name``] in
+    pub icon_theme_path: String,
+    /// The icon in multiple sizes, used if there is no [``Self::icon_name``]
+    pub icon: Vec<Pixmap>,
+    /// Name of the icon used while the item needs attention
+    pub attention_icon_name: String,
+    /// The icon used while the item needs attention in multiple sizes
+    pub attention_icon: Vec<Pixmap>,
+    /// Title of the tooltip
+    pub tooltip_title: String,
+    /// Text of the tooltip
+    pub tooltip_body: String,
+    /// If the item only supports showing its menu, instead of being activated
+    pub item_is_menu: bool,
+    /// All entries of the menu of the item, parents before their children
+    ///
+    /// Empty if the item has no menu.
+    pub menu: Vec<MenuEntry>,
+}
+
+/// Status of a [``TrayItem``]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub enum ItemStatus {
+    /// The item doesn't convey important information and may be hidden
+    Passive,
+    /// The item is active
+    #[default]
+    Active,
+    /// The item needs the attention of the user
+    NeedsAttention,
+}
+
+impl From<&str> for ItemStatus {
+    fn from(value: &str) -> Self {
+        match value {
+            "Passive" => Self::Passive,
+            "NeedsAttention" => Self::NeedsAttention,
+            _ => Self::Active,
+        }
+    }
+}
+
+/// An icon of a [``TrayItem``] in one size
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub struct Pixmap {
+    /// Width of the icon in pixels
+    pub width: i32,
+    /// Height of the icon in pixels
+    pub height: i32,
+    /// The pixels in ARGB byte order
+    pub data: Vec<u8>,
+}
+
+/// An entry of the menu of a [``TrayItem``]
+///
+/// See [``menu``]
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    PartialEq,
+    Eq,
+    zvariant::Value,
+    zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub struct MenuEntry {
+    /// Id of the entry, used when it is clicked
+    pub id: i32,
+    /// Id of the parent entry, the root of the menu has the id 0
+    pub parent: i32,
+    /// Text of the entry
+    pub label: String,
+    /// If the entry can be clicked
+    pub enabled: bool,
+    /// If the entry is a separator
+    pub separator: bool,
+    /// If the entry can be toggled
+    pub toggle: MenuToggle,
+    /// If the entry is toggled on
+    pub checked: bool,
+}
+
+/// How a [``MenuEntry``] can be toggled
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    zbus::zvariant::Value,
+    zbus::zvariant::OwnedValue,
+    zvariant::Type,
+    Serialize,
+    Deserialize,
+)]
+pub enum MenuToggle {
+    /// The entry can't be toggled
+    #[default]
+    None,
+    /// The entry is a checkbox
+    Checkmark,
+    /// The entry is one of multiple radio buttons
+    Radio,
+}
+
+impl Tray {
+    /// Name of the dbus signal emitted when a tracked item has changed
+    ///
+    /// The signal has the changed [``TrayItem``] as argument.
+    pub const ITEM_CHANGED: &str = "ItemChanged";
+
+    /// Emit the [``Self::ITEM_CHANGED``] dbus signal
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn item_changed(emitter: &SignalEmitter<'_>, item: &TrayItem) -> zbus::Result<()> {
+        emitter
+            .emit(Self::name(), Self::ITEM_CHANGED, &(item,))
+            .await
+    }
+
+    /// Add the item `data` owned by `destination` at `path`, or replace it if it is already
+    /// tracked
+    ///
+    /// Returns if the item was added.
+    fn insert(
+        &mut self,
+        data: TrayItem,
+        destination: &str,
+        path: &str,
+        menu: Option<String>,
+    ) -> bool {
+        let item = Item {
+            data,
+            destination: destination.to_string(),
+            path: path.to_string(),
+            menu,
+        };
+
+        if let Some(existing) = self.items.iter_mut().find(|i| i.data.id == item.data.id) {
+            *existing = item;
+
+            false
+        } else {
+            self.items.push(item);
+
+            true
+        }
+    }
+
+    /// Remove the item `id`
+    ///
+    /// Returns if the item was tracked.
+    fn remove(&mut self, id: &str) -> bool {
+        let len = self.items.len();
+
+        self.items.retain(|i| i.data.id != id);
+
+        len != self.items.len()
+    }
+
+    /// Mark the item `id` as pending until it is inserted, if it isn't tracked yet
+    ///
+    /// Returns if the item should be tracked. This way an item registered multiple times is only
+    /// tracked once, even if it hasn't been read yet.
+    fn start_tracking(&mut self, id: &str) -> bool {
+        !self.items.iter().any(|i| i.data.id == id) && self.pending.insert(id.to_string())
+    }
+
+    /// The ids of all tracked items
+    fn ids(&self) -> Vec<String> {
+        self.items.iter().map(|i| i.data.id.clone()).collect()
+    }
+
+    /// Helper function to get the item `id`
+    fn item(&self, id: &str) -> fdo::Result<&Item> {
+        self.items
+            .iter()
+            .find(|i| i.data.id == id)
+            .ok_or_else(|| fdo::Error::InvalidArgs(format!("There is no tray item {id}")))
+    }
+
+    /// Helper function to get a proxy for the item `id`
+    async fn item_proxy(
+        &self,
+        id: &str,
+        connection: &zbus::Connection,
+    ) -> fdo::Result<StatusNotifierItemProxy<'static>> {
+        let item = self.item(id)?;
+
+        Ok(StatusNotifierItemProxy::builder(connection)
+            .destination(item.destination.clone())?
+            .path(item.path.clone())?
+            .build()
+            .await?)
+    }
+
+    /// Helper function to get a proxy for the menu of the item `id`
+    async fn menu_proxy(
+        &self,
+        id: &str,
+        connection: &zbus::Connection,
+    ) -> fdo::Result<DBusMenuProxy<'static>> {
+        let item = self.item(id)?;
+
+        let Some(menu) = &item.menu else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Tray item {id} has no menu"
+            )));
+        };
+
+        Ok(DBusMenuProxy::builder(connection)
+            .destination(item.destination.clone())?
+            .path(menu.clone())?
+            .build()
+            .await?)
+    }
+}
+
+#[interface(
+    name = "dod.shell.Daemon.Tray",
+    proxy(
+        gen_blocking = false,
+        default_path = "/dod/shell/Daemon",
+        default_service = "dod.shell.Daemon"
+    )
+)]
+impl Tray {
+    /// Dbus property to get all items, in the order they were registered in
+    ///
+    /// Changes are only emitted when items are added or removed, see [``Self::ITEM_CHANGED``].
+    #[zbus(property)]
+    fn items(&self) -> Vec<TrayItem> {
+        self.items.iter().map(|i| i.data.clone()).collect()
+    }
+
+    /// Activate the item `id` (usually by left clicking it)
+    ///
+    /// `x` and `y` are the position of the click on the screen, if known.
+    async fn activate(
+        &self,
+        id: &str,
+        x: i32,
+        y: i32,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        Ok(self
+            .item_proxy(id, connection)
+            .await?
+            .activate(x, y)
+            .await?)
+    }
+
+    /// Secondary activate the item `id` (usually by middle clicking it)
+    async fn secondary_activate(
+        &self,
+        id: &str,
+        x: i32,
+        y: i32,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        Ok(self
+            .item_proxy(id, connection)
+            .await?
+            .secondary_activate(x, y)
+            .await?)
+    }
+
+    /// Ask the item `id` to show its own context menu
+    ///
+    /// Only used for items without a [``TrayItem::menu``].
+    async fn context_menu(
+        &self,
+        id: &str,
+        x: i32,
+        y: i32,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        Ok(self
+            .item_proxy(id, connection)
+            .await?
+            .context_menu(x, y)
+            .await?)
+    }
+
+    /// Tell the item `id` its menu is about to be shown
+    ///
+    /// Some applications only fill their menu once this is called.
+    async fn menu_about_to_show(
+        &self,
+        id: &str,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        let menu = self.menu_proxy(id, connection).await?;
+
+        menu.about_to_show(0).await?;
+        menu.event(0, "opened", &Value::from(0), 0).await?;
+
+        Ok(())
+    }
+
+    /// Click the menu entry `entry` of the item `id`
+    async fn menu_clicked(
+        &self,
+        id: &str,
+        entry: i32,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> fdo::Result<()> {
+        Ok(self
+            .menu_proxy(id, connection)
+            .await?
+            .event(entry, "clicked", &Value::from(0), 0)
+            .await?)
+    }
+}
+
+/// Helper function to emit that [``Tray::items``] has changed
+async fn items_changed(tray: &Tray, emitter: &SignalEmitter<'_>) {
+    if let Err(e) = tray.items_changed(emitter).await {
+        log::error!("Failed to signal tray items changed: {e}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Tray, TrayItem};
+
+    #[test]
+    fn tracks_items_once() {
+        let mut tray = Tray::default();
+        let id = ":1.42/StatusNotifierItem";
+
+        assert!(tray.start_tracking(id));
+        // Registered again before it was read
+        assert!(!tray.start_tracking(id));
+
+        let item = TrayItem {
+            id: id.to_string(),
+            ..Default::default()
+        };
+
+        tray.pending.remove(id);
+        assert!(tray.insert(item.clone(), ":1.42", "/StatusNotifierItem", None));
+        // Updated, not added
+        assert!(!tray.insert(item, ":1.42", "/StatusNotifierItem", None));
+
+        assert!(!tray.start_tracking(id));
+        assert!(tray.remove(id));
+        assert!(tray.start_tracking(id));
+    }
+}
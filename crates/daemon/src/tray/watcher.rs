@@ -0,0 +1,179 @@
+//! The tray watcher, implementing the `org.kde.StatusNotifierWatcher` dbus interface
+//!
+//! See: <https://www.freedesktop.org/wiki/Specifications/StatusNotifierItem/StatusNotifierWatcher/>
+use zbus::{
+    fdo, interface,
+    message::Header,
+    object_server::{Interface, InterfaceRef, SignalEmitter},
+};
+
+use super::{Tray, item};
+
+/// Well known name of the watcher
+pub const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+
+/// Path the [``Watcher``] is served at
+pub const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+
+/// Path of an item if an application only registers its bus name
+const DEFAULT_ITEM_PATH: &str = "/StatusNotifierItem";
+
+/// Receives tray items from applications
+///
+/// All items are kept by [``Tray``], this only translates between it and the spec. It is served
+/// at [``WATCHER_PATH``] under the name [``WATCHER_NAME``]. The daemon is the only tray host, so
+/// hosts registering themselves are ignored.
+pub struct Watcher {
+    /// Where the items are kept
+    tray: InterfaceRef<Tray>,
+}
+
+impl Watcher {
+    /// Name of the dbus signal emitted when an item is registered
+    ///
+    /// The signal has the id of the item as argument.
+    pub const ITEM_REGISTERED: &str = "StatusNotifierItemRegistered";
+
+    /// Name of the dbus signal emitted when an item is unregistered
+    ///
+    /// The signal has the id of the item as argument.
+    pub const ITEM_UNREGISTERED: &str = "StatusNotifierItemUnregistered";
+
+    /// Create a new [``Watcher``]
+    #[must_use]
+    pub const fn new(tray: InterfaceRef<Tray>) -> Self {
+        Self { tray }
+    }
+
+    /// Emit the [``Self::ITEM_REGISTERED``] dbus signal
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn item_registered(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()> {
+        emitter
+            .emit(Self::name(), Self::ITEM_REGISTERED, &(id,))
+            .await
+    }
+
+    /// Emit the [``Self::ITEM_UNREGISTERED``] dbus signal
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if sending the signal fails.
+    pub async fn item_unregistered(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()> {
+        emitter
+            .emit(Self::name(), Self::ITEM_UNREGISTERED, &(id,))
+            .await
+    }
+}
+
+#[interface(name = "org.kde.StatusNotifierWatcher")]
+impl Watcher {
+    /// Register a tray item
+    ///
+    /// `service` is either the bus name of the application, in which case the item is at
+    /// [``DEFAULT_ITEM_PATH``], or the path of the item on the bus of the sender.
+    #[allow(
+        clippy::needless_pass_by_value,
+        reason = "zbus passes the header by value."
+    )]
+    fn register_status_notifier_item(
+        &self,
+        service: &str,
+        #[zbus(header)] header: Header<'_>,
+    ) -> fdo::Result<()> {
+        let (destination, path) = if service.starts_with('/') {
+            let sender = header
+                .sender()
+                .ok_or_else(|| fdo::Error::InvalidArgs("Unknown sender".to_string()))?;
+
+            (sender.to_string(), service.to_string())
+        } else {
+            (service.to_string(), DEFAULT_ITEM_PATH.to_string())
+        };
+
+        log::debug!("Registering tray item {destination}{path}");
+
+        // The item is only signaled as registered once it has been read
+        item::spawn(self.tray.clone(), destination, path);
+
+        Ok(())
+    }
+
+    /// Register a tray host
+    ///
+    /// The daemon is the only host, so this does nothing.
+    #[allow(
+        clippy::unused_self,
+        reason = "Hosts are ignored, but zbus methods need self."
+    )]
+    fn register_status_notifier_host(&self, service: &str) {
+        log::debug!("Ignoring tray host {service}");
+    }
+
+    /// Dbus property to get the ids of all registered items
+    #[zbus(property)]
+    async fn registered_status_notifier_items(&self) -> Vec<String> {
+        self.tray.get().await.ids()
+    }
+
+    /// Dbus property to get if a tray host is registered, which is always the case
+    #[zbus(property)]
+    #[allow(
+        clippy::unused_self,
+        reason = "The daemon is always the host, but zbus properties need self."
+    )]
+    const fn is_status_notifier_host_registered(&self) -> bool {
+        true
+    }
+
+    /// Dbus property to get the version of the protocol
+    #[zbus(property)]
+    #[allow(
+        clippy::unused_self,
+        reason = "The version is constant, but zbus properties need self."
+    )]
+    const fn protocol_version(&self) -> i32 {
+        0
+    }
+}
+
+/// Helper function to emit the [``Watcher::ITEM_REGISTERED``] dbus signal
+///
+/// `emitter` can be any emitter on the same connection as the watcher. The registered items are
+/// signaled as changed as well, so the [``Tray``] must not be locked.
+pub(super) async fn registered(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()> {
+    let watcher = watcher(emitter).await?;
+
+    Watcher::item_registered(watcher.signal_emitter(), id).await?;
+    items_changed(&watcher).await
+}
+
+/// Helper function to emit the [``Watcher::ITEM_UNREGISTERED``] dbus signal
+///
+/// Same as [``registered``].
+pub(super) async fn unregistered(emitter: &SignalEmitter<'_>, id: &str) -> zbus::Result<()> {
+    let watcher = watcher(emitter).await?;
+
+    Watcher::item_unregistered(watcher.signal_emitter(), id).await?;
+    items_changed(&watcher).await
+}
+
+/// Helper function to get the watcher served on the connection of `emitter`
+async fn watcher(emitter: &SignalEmitter<'_>) -> zbus::Result<InterfaceRef<Watcher>> {
+    emitter
+        .connection()
+        .object_server()
+        .interface(WATCHER_PATH)
+        .await
+}
+
+/// Helper function to emit that [``Watcher::registered_status_notifier_items``] has changed
+async fn items_changed(watcher: &InterfaceRef<Watcher>) -> zbus::Result<()> {
+    watcher
+        .get()
+        .await
+        .registered_status_notifier_items_changed(watcher.signal_emitter())
+        .await
+}
@@ -0,0 +1,104 @@
+//! Helpers shared by multiple parts of the daemon
+//!
+//! Most of these are for reading devices from sysfs (e.g. [``crate::brightness::sysfs``]).
+//!
+//! See: <https://www.kernel.org/doc/html/latest/filesystems/sysfs.html>
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use tokio::fs;
+use zbus::fdo;
+
+/// Get the paths of all entries of the directory at `path`, sorted by name
+///
+/// Returns an empty list if the directory can't be read.
+pub async fn entries(path: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+
+    if let Ok(mut entries) = fs::read_dir(path).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            paths.push(entry.path());
+        }
+    }
+
+    paths.sort_unstable();
+
+    paths
+}
+
+/// Read the value of the attribute `name` of the device at `path`
+///
+/// Returns [``None``] if the attribute doesn't exist or can't be parsed.
+pub async fn read_value<T: FromStr>(path: &Path, name: &str) -> Option<T> {
+    fs::read_to_string(path.join(name))
+        .await
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Reject values passed over dbus which are `NaN` or infinite
+///
+/// `what` is the kind of value (e.g. `volume`), which is used in the error.
+pub fn finite(value: f64, what: &str) -> fdo::Result<f64> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(fdo::Error::InvalidArgs(format!("Invalid {what}: {value}")))
+    }
+}
+
+/// Create a temporary directory containing `files`, to test reading from sysfs
+///
+/// Each file is given as its path relative to the directory and its content. Missing parent
+/// directories are created.
+#[cfg(test)]
+pub fn sysfs_dir(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    dir
+}
+
+#[cfg(test)]
+mod test {
+    use super::{entries, read_value, sysfs_dir};
+
+    #[tokio::test]
+    async fn reads_value() {
+        let dir = sysfs_dir(&[("brightness", "42\n"), ("type", "raw\n")]);
+
+        assert_eq!(read_value::<u32>(dir.path(), "brightness").await, Some(42));
+        assert_eq!(
+            read_value::<String>(dir.path(), "type").await.as_deref(),
+            Some("raw")
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_or_unparsable_value_is_none() {
+        let dir = sysfs_dir(&[("brightness", "unknown\n")]);
+
+        assert_eq!(read_value::<u32>(dir.path(), "brightness").await, None);
+        assert_eq!(read_value::<u32>(dir.path(), "max_brightness").await, None);
+    }
+
+    #[tokio::test]
+    async fn lists_entries_sorted() {
+        let dir = sysfs_dir(&[("hwmon1/name", ""), ("hwmon0/name", "")]);
+
+        assert_eq!(
+            entries(dir.path()).await,
+            [dir.path().join("hwmon0"), dir.path().join("hwmon1")]
+        );
+        assert!(entries(&dir.path().join("missing")).await.is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+//! The notifications component of the shell
+//!
+//! Shows the notifications received by the daemon as popups in the top right corner of the
+//! screen. The daemon acts as the notification server, so no other notification daemon (e.g.
+//! mako) is needed.
+//!
+//! Clicking a popup invokes its default action, or hides it if it has none. How long popups are
+//! shown and how many at once can be changed in
+//! [`common::config::notifications::NotificationsConfig`].
+use std::process::exit;
+
+use futures_util::StreamExt;
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::{
+    component::{AsyncComponentParts, AsyncComponentSender},
+    gtk::{self, prelude::*},
+    prelude::*,
+};
+
+use common::{config::notifications::NotificationsConfig, css::Class};
+use daemon::{
+    config::ConfigProxy,
+    notifications::{Notification, NotificationsProxy},
+};
+
+use crate::popup::{Popup, PopupOutput};
+
+#[allow(
+    dead_code,
+    reason = "Some symbols are not used by the notifications component. This is ok since all components share the same icon dir."
+)]
+#[allow(
+    clippy::doc_markdown,
+    reason = "Upstream issue. Already fixed. Remove this when relm4-icons 10.0.1 is released."
+)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "Upstream missing docs."
+)]
+mod icon {
+    //! Auto generated icons module
+    //!
+    //! See `build.rs` for more information.
+    include!(concat!(env!("OUT_DIR"), "/icon_names.rs"));
+
+    pub use self::custom::*;
+}
+
+mod popup;
+
+/// Distance of the popups to the edges of the screen in pixels
+const MARGIN: i32 = 10;
+
+/// The main [``relm4::Component``] for the notifications component
+///
+/// For more information see module level docs
+#[derive(Debug)]
+struct App {
+    /// The current config received from the daemon
+    config: NotificationsConfig,
+    /// All notifications received from the daemon, newest first
+    notifications: Vec<Notification>,
+    /// The popups currently shown
+    popups: FactoryVecDeque<Popup>,
+    /// Proxy for communication with the daemon
+    notifications_proxy: NotificationsProxy<'static>,
+}
+
+/// Helper trait for exiting if something fails during [`App::init`]
+trait AppErrExt<T> {
+    /// If self is an [`Err`] logs it and exits, otherwise returns the [`Ok`] value
+    fn abort_on_err(self) -> T;
+}
+
+impl<T, E: std::error::Error> AppErrExt<T> for Result<T, E> {
+    fn abort_on_err(self) -> T {
+        match self {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to init app: {e}");
+
+                exit(1)
+            }
+        }
+    }
+}
+
+impl App {
+    /// Updates [`Self::popups`] according to [`Self::notifications`]
+    ///
+    /// At most [`NotificationsConfig::max_popups`] of the newest popups are shown.
+    fn update_popups(&mut self) {
+        let mut guard = self.popups.guard();
+
+        guard.clear();
+
+        for notification in self
+            .notifications
+            .iter()
+            .filter(|n| n.popup)
+            .take(self.config.max_popups)
+        {
+            guard.push_back(notification.clone());
+        }
+    }
+}
+
+/// Input messages for [`AppWidgets`]
+#[derive(Debug)]
+enum AppMsg {
+    /// The css has changed
+    CssUpdated(String),
+    /// The config has changed
+    ConfigUpdated(NotificationsConfig),
+    /// The notifications have changed
+    NotificationsUpdated(Vec<Notification>),
+    /// Sent by a [`Popup`]
+    Popup(PopupOutput),
+}
+
+/// Auto-generated widget for [`App`]
+#[relm4::component(async)]
+impl SimpleAsyncComponent for App {
+    type Init = ();
+    type Input = AppMsg;
+    type Output = ();
+
+    view! {
+        /// Main window of the notifications component
+        gtk::Window {
+            init_layer_shell: (),
+            #[watch]
+            set_visible: !model.popups.is_empty(),
+            add_css_class: Class::NotificationsMainWindow.as_ref(),
+            set_namespace: Some("dod-shell-notifications"),
+            set_layer: Layer::Overlay,
+            set_anchor: (Edge::Top, true),
+            set_anchor: (Edge::Right, true),
+            set_margin: (Edge::Top, MARGIN),
+            set_margin: (Edge::Right, MARGIN),
+
+            #[local_ref]
+            popups -> gtk::Box {
+                add_css_class: Class::NotificationsBox.as_ref(),
+                set_orientation: gtk::Orientation::Vertical,
+            },
+        }
+    }
+
+    async fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let connection = zbus::Connection::session().await.abort_on_err();
+
+        let config_proxy = ConfigProxy::new(&connection).await.abort_on_err();
+        let notifications_proxy = NotificationsProxy::new(&connection).await.abort_on_err();
+
+        let mut config_stream = config_proxy.receive_config_changed().await.fuse();
+        let mut css_stream = config_proxy.receive_css_changed().await.fuse();
+        let mut notifications_stream = notifications_proxy
+            .receive_notifications_changed()
+            .await
+            .fuse();
+
+        let model = Self {
+            config: NotificationsConfig::default(),
+            notifications: Vec::new(),
+            popups: FactoryVecDeque::builder()
+                .launch_default()
+                .forward(sender.input_sender(), AppMsg::Popup),
+            notifications_proxy,
+        };
+
+        let popups = model.popups.widget();
+
+        let widgets = view_output!();
+
+        let update_sender = sender.input_sender().clone();
+
+        relm4::spawn(async move {
+            loop {
+                if futures_util::select! {
+                    config = config_stream.select_next_some() => {
+                        let config = toml::from_str::<common::config::Config>(&config.get().await?)
+                            .expect("Config string returned by daemon should always be valid.");
+
+                        update_sender.send(AppMsg::ConfigUpdated(config.notifications))
+                    }
+                    css = css_stream.select_next_some() => {
+                        update_sender.send(AppMsg::CssUpdated(css.get().await?))
+                    }
+                    notifications = notifications_stream.select_next_some() => {
+                        update_sender.send(AppMsg::NotificationsUpdated(notifications.get().await?))
+                    }
+                }
+                .is_err()
+                {
+                    log::error!("Failed processing update from daemon");
+                }
+            }
+
+            #[allow(unreachable_code, reason = "Needed for type inference")]
+            Ok::<(), zbus::Error>(())
+        });
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, _sender: AsyncComponentSender<Self>) {
+        match msg {
+            AppMsg::CssUpdated(css) => relm4::set_global_css(&css),
+            AppMsg::ConfigUpdated(config) => {
+                self.config = config;
+                self.update_popups();
+            }
+            AppMsg::NotificationsUpdated(notifications) => {
+                self.notifications = notifications;
+                self.update_popups();
+            }
+            AppMsg::Popup(output) => {
+                let result = match &output {
+                    PopupOutput::Dismiss(id) => self.notifications_proxy.dismiss(*id).await,
+                    PopupOutput::Hide(id) => self.notifications_proxy.hide_popup(*id).await,
+                    PopupOutput::Action(id, key) => {
+                        self.notifications_proxy.invoke_action(*id, key).await
+                    }
+                };
+
+                if let Err(e) = result {
+                    log::error!("Failed to handle {output:?}: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Main entry point for launching the notifications component
+///
+/// ## Errors
+///
+/// This function errors if there is are any problems with:
+///
+/// 1. Creating a tokio runtime
+///
+/// 2. Getting the needed state from the daemon
+pub fn launch() {
+    let app = RelmApp::new("dod-shell.notifications");
+    relm4_icons::initialize_icons(icon::GRESOURCE_BYTES, icon::RESOURCE_PREFIX);
+
+    app.run_async::<App>(());
+}
@@ -0,0 +1,8 @@
+//! Binary for the notifications component. See lib for more information
+use common::logger;
+
+fn main() {
+    logger!();
+
+    notifications::launch();
+}
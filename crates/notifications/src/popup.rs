@@ -0,0 +1,240 @@
+//! See [`Popup`]
+use relm4::{
+    gtk::{self, gdk, glib, prelude::*},
+    prelude::*,
+};
+
+use common::{classes, css::Class};
+use daemon::notifications::{Action, Image, Notification, Urgency};
+
+use crate::icon;
+
+/// A single notification shown as a popup
+#[derive(Debug)]
+pub struct Popup {
+    /// The notification shown
+    notification: Notification,
+}
+
+/// Input messages for [`Popup`]
+#[derive(Debug)]
+pub enum PopupInput {
+    /// The popup was clicked
+    Clicked,
+    /// The close button was pressed
+    Dismiss,
+    /// The button of the action with this key was pressed
+    Action(String),
+}
+
+/// Output messages of [`Popup`]
+///
+/// All of these contain the id of the notification.
+#[derive(Debug)]
+pub enum PopupOutput {
+    /// Dismiss the notification
+    Dismiss(u32),
+    /// Hide the popup, keeping the notification in the history
+    Hide(u32),
+    /// Invoke the action with the key
+    Action(u32, String),
+}
+
+/// Auto-generated widget for [`Popup`]
+#[relm4::factory(pub)]
+impl FactoryComponent for Popup {
+    type Init = Notification;
+    type Input = PopupInput;
+    type Output = PopupOutput;
+    type CommandOutput = ();
+    type ParentWidget = gtk::Box;
+
+    view! {
+        #[root]
+        /// Outer container of the popup
+        gtk::Box {
+            add_css_class: Class::NotificationPopup.as_ref(),
+            set_class_active: (Class::UrgencyLow.as_ref(), self.notification.urgency == Urgency::Low),
+            set_class_active: (Class::UrgencyCritical.as_ref(), self.notification.urgency == Urgency::Critical),
+            set_orientation: gtk::Orientation::Vertical,
+
+            add_controller = gtk::GestureClick {
+                connect_released[sender] => move |_, _, _, _| {
+                    sender.input(PopupInput::Clicked);
+                },
+            },
+
+            gtk::Box {
+                add_css_class: Class::NotificationHeader.as_ref(),
+                set_orientation: gtk::Orientation::Horizontal,
+
+                /// The image or app icon of the notification
+                #[name(image)]
+                gtk::Image {
+                    set_css_classes: &classes!(Icon, NotificationIcon),
+                    set_valign: gtk::Align::Start,
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_hexpand: true,
+
+                    gtk::Label {
+                        set_css_classes: &classes!(Label, NotificationAppName),
+                        set_visible: !self.notification.app_name.is_empty(),
+                        set_label: &self.notification.app_name,
+                        set_xalign: 0.0,
+                    },
+
+                    gtk::Label {
+                        set_css_classes: &classes!(Label, NotificationSummary),
+                        set_label: &self.notification.summary,
+                        set_xalign: 0.0,
+                        set_wrap: true,
+                    },
+                },
+
+                gtk::Button {
+                    set_css_classes: &classes!(Icon, NotificationCloseButton),
+                    set_valign: gtk::Align::Start,
+                    set_icon_name: icon::DISMISS_FILLED_SYMBOLIC,
+                    connect_clicked => PopupInput::Dismiss,
+                },
+            },
+
+            gtk::Label {
+                set_css_classes: &classes!(Label, NotificationBody),
+                set_visible: !self.notification.body.is_empty(),
+                set_label: &self.notification.body,
+                set_xalign: 0.0,
+                set_wrap: true,
+            },
+
+            /// Buttons for the actions of the notification
+            #[name(actions)]
+            gtk::Box {
+                add_css_class: Class::NotificationActions.as_ref(),
+                set_orientation: gtk::Orientation::Horizontal,
+                set_homogeneous: true,
+                set_visible: self.notification.actions.iter().any(|a| a.key != Action::DEFAULT),
+            },
+        }
+    }
+
+    fn init_widgets(
+        &mut self,
+        _index: &Self::Index,
+        root: Self::Root,
+        _returned_widget: &<Self::ParentWidget as relm4::factory::FactoryView>::ReturnedWidget,
+        sender: FactorySender<Self>,
+    ) -> Self::Widgets {
+        let widgets = view_output!();
+
+        set_image(&widgets.image, &self.notification);
+
+        for action in self
+            .notification
+            .actions
+            .iter()
+            .filter(|a| a.key != Action::DEFAULT)
+        {
+            let button = gtk::Button::with_label(&action.label);
+            button.add_css_class(Class::NotificationAction.as_ref());
+
+            let key = action.key.clone();
+            let sender = sender.clone();
+            button.connect_clicked(move |_| sender.input(PopupInput::Action(key.clone())));
+
+            widgets.actions.append(&button);
+        }
+
+        widgets
+    }
+
+    fn init_model(init: Self::Init, _index: &Self::Index, _sender: FactorySender<Self>) -> Self {
+        Self { notification: init }
+    }
+
+    fn update(&mut self, message: Self::Input, sender: FactorySender<Self>) {
+        let id = self.notification.id;
+
+        let output = match message {
+            PopupInput::Clicked
+                if self
+                    .notification
+                    .actions
+                    .iter()
+                    .any(|a| a.key == Action::DEFAULT) =>
+            {
+                PopupOutput::Action(id, Action::DEFAULT.to_string())
+            }
+            PopupInput::Clicked => PopupOutput::Hide(id),
+            PopupInput::Dismiss => PopupOutput::Dismiss(id),
+            PopupInput::Action(key) => PopupOutput::Action(id, key),
+        };
+
+        if sender.output(output).is_err() {
+            log::error!("Failed to send output of notification {id}");
+        }
+    }
+}
+
+/// Helper function to show the image of `notification` in `image`
+///
+/// The image data is preferred over the image path, which is preferred over the app icon.
+/// Paths can be given as `file://` URIs or absolute paths, everything else is an icon name.
+fn set_image(image: &gtk::Image, notification: &Notification) {
+    if let Some(texture) = (*notification.image).as_ref().and_then(texture) {
+        image.set_paintable(Some(&texture));
+        return;
+    }
+
+    let Some(name) = [&notification.image_path, &notification.app_icon]
+        .into_iter()
+        .find(|name| !name.is_empty())
+    else {
+        image.set_visible(false);
+        return;
+    };
+
+    if let Some(path) = name.strip_prefix("file://") {
+        image.set_from_file(Some(path));
+    } else if name.starts_with('/') {
+        image.set_from_file(Some(name));
+    } else {
+        image.set_icon_name(Some(name));
+    }
+}
+
+/// Helper function to convert the raw `image` into a texture
+///
+/// Returns [`None`] if `image` isn't 8 bit RGB(A) or doesn't contain enough data.
+fn texture(image: &Image) -> Option<gdk::MemoryTexture> {
+    let format = match (image.has_alpha, image.channels, image.bits_per_sample) {
+        (true, 4, 8) => gdk::MemoryFormat::R8g8b8a8,
+        (false, 3, 8) => gdk::MemoryFormat::R8g8b8,
+        _ => return None,
+    };
+
+    let width = usize::try_from(image.width).ok()?;
+    let height = usize::try_from(image.height).ok()?;
+    let rowstride = usize::try_from(image.rowstride).ok()?;
+    let channels = usize::try_from(image.channels).ok()?;
+
+    // The last row doesn't have to be padded to the full rowstride
+    let needed = rowstride
+        .checked_mul(height.checked_sub(1)?)?
+        .checked_add(width.checked_mul(channels)?)?;
+
+    if width == 0 || image.data.len() < needed {
+        return None;
+    }
+
+    Some(gdk::MemoryTexture::new(
+        image.width,
+        image.height,
+        format,
+        &glib::Bytes::from(image.data.as_slice()),
+        rowstride,
+    ))
+}
@@ -0,0 +1,26 @@
+//! Build script to bundle icons
+
+fn main() {
+    relm4_icons_build::bundle_icons(
+        // Name of the file that will be generated at `OUT_DIR`
+        "icon_names.rs",
+        // Optional app ID
+        Some("dod-shell.osd"),
+        // Custom base resource path:
+        // * defaults to `/com/example/myapp` in this case if not specified explicitly
+        // * or `/org/relm4` if app ID was not specified either
+        None::<&str>,
+        // Directory with custom icons
+        Some("../../icons"),
+        // List of icons to include
+        [
+            "keyboard-caps-lock",
+            "keyboard-filled",
+            "speaker-0-filled",
+            "speaker-1-filled",
+            "speaker-2-filled",
+            "speaker-off-filled",
+            "speaker-mute-filled",
+        ],
+    );
+}
@@ -0,0 +1,281 @@
+//! The osd (on-screen display) component of the shell
+//!
+//! The osd briefly shows a popup when the volume, the brightness, caps lock / num lock or the
+//! default audio output change, e.g. after pressing a media key.
+//!
+//! Where and how long the popup is shown can be changed in
+//! [`common::config::osd::OsdConfig`].
+use std::{process::exit, time::Duration};
+
+use futures_util::StreamExt;
+use gtk4_layer_shell::{Edge, Layer, LayerShell};
+use relm4::{
+    component::{AsyncComponentParts, AsyncComponentSender},
+    gtk::{self, glib, prelude::*},
+    prelude::*,
+};
+
+use common::{
+    classes,
+    config::osd::{OsdConfig, OsdPosition},
+    css::Class,
+};
+use daemon::{
+    config::ConfigProxy,
+    system_state::{SystemStateData, SystemStateProxy},
+};
+
+use crate::popup::{Popup, Snapshot};
+
+#[allow(
+    dead_code,
+    reason = "Some symbols are not used by the osd. This is ok since all components share the same icon dir."
+)]
+#[allow(
+    clippy::doc_markdown,
+    reason = "Upstream issue. Already fixed. Remove this when relm4-icons 10.0.1 is released."
+)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "Upstream missing docs."
+)]
+mod icon {
+    //! Auto generated icons module
+    //!
+    //! See `build.rs` for more information.
+    include!(concat!(env!("OUT_DIR"), "/icon_names.rs"));
+
+    pub use self::custom::*;
+    pub use self::shipped::*;
+}
+
+mod popup;
+
+/// The main [``relm4::Component``] for the osd
+///
+/// For more information see module level docs
+#[derive(Debug)]
+struct App {
+    /// The current config received from the daemon
+    config: OsdConfig,
+    /// What is currently shown
+    popup: Popup,
+    /// If the osd is currently shown
+    visible: bool,
+    /// The state the last update was compared against
+    ///
+    /// This is [`None`] until the first update, so the initial state isn't shown.
+    previous: Option<Snapshot>,
+    /// Incremented every time the osd is shown
+    ///
+    /// Used to ignore [`AppMsg::Hide`] if the osd was shown again since it was sent.
+    generation: u64,
+}
+
+/// Helper trait for exiting if something fails during [`App::init`]
+trait AppErrExt<T> {
+    /// If self is an [`Err`] logs it and exits, otherwise returns the [`Ok`] value
+    fn abort_on_err(self) -> T;
+}
+
+impl<T, E: std::error::Error> AppErrExt<T> for Result<T, E> {
+    fn abort_on_err(self) -> T {
+        match self {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Failed to init app: {e}");
+
+                exit(1)
+            }
+        }
+    }
+}
+
+impl App {
+    /// Show `popup` and hide it again after [`OsdConfig::timeout`]
+    fn show(&mut self, popup: Popup, sender: &AsyncComponentSender<Self>) {
+        self.popup = popup;
+        self.visible = true;
+        self.generation = self.generation.wrapping_add(1);
+
+        let generation = self.generation;
+        let sender = sender.clone();
+
+        glib::timeout_add_local_once(Duration::from_millis(self.config.timeout), move || {
+            sender.input(AppMsg::Hide(generation));
+        });
+    }
+}
+
+/// Input messages for [`AppWidgets`]
+#[derive(Debug)]
+enum AppMsg {
+    /// The css has changed
+    CssUpdated(String),
+    /// The config has changed
+    ConfigUpdated(OsdConfig),
+    /// The system state has changed
+    StateUpdated(Box<SystemStateData>),
+    /// Hide the osd, if it wasn't shown again since (see [`App::generation`])
+    Hide(u64),
+}
+
+/// Auto-generated widget for [`App`]
+#[relm4::component(async)]
+impl SimpleAsyncComponent for App {
+    type Init = ();
+    type Input = AppMsg;
+    type Output = ();
+
+    view! {
+        /// Main window of the osd
+        gtk::Window {
+            init_layer_shell: (),
+            #[watch]
+            set_visible: model.visible,
+            add_css_class: Class::OsdMainWindow.as_ref(),
+            set_namespace: Some("dod-shell-osd"),
+            set_layer: Layer::Overlay,
+            #[watch]
+            set_anchor: (Edge::Top, model.config.position == OsdPosition::Top),
+            #[watch]
+            set_anchor: (Edge::Bottom, model.config.position == OsdPosition::Bottom),
+            #[watch]
+            set_margin: (Edge::Top, model.config.margin),
+            #[watch]
+            set_margin: (Edge::Bottom, model.config.margin),
+
+            gtk::Box {
+                add_css_class: Class::OsdBox.as_ref(),
+                set_orientation: gtk::Orientation::Horizontal,
+
+                gtk::Image {
+                    set_css_classes: &classes!(Icon, OsdIcon),
+                    #[watch]
+                    set_icon_name: Some(model.popup.icon),
+                    #[watch]
+                    set_class_active: (Class::Muted.as_ref(), model.popup.muted),
+                    #[watch]
+                    set_class_active: (Class::Active.as_ref(), model.popup.active),
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_valign: gtk::Align::Center,
+
+                    gtk::Label {
+                        set_css_classes: &classes!(Label, OsdLabel),
+                        #[watch]
+                        set_label: &model.popup.label,
+                    },
+
+                    gtk::ProgressBar {
+                        add_css_class: Class::OsdProgressbar.as_ref(),
+                        #[watch]
+                        set_visible: model.popup.level.is_some(),
+                        #[watch]
+                        set_fraction: model.popup.level.unwrap_or_default(),
+                        #[watch]
+                        set_class_active: (Class::Muted.as_ref(), model.popup.muted),
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        _init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let connection = zbus::Connection::session().await.abort_on_err();
+
+        let config_proxy = ConfigProxy::new(&connection).await.abort_on_err();
+        let state_proxy = SystemStateProxy::new(&connection).await.abort_on_err();
+
+        let mut config_stream = config_proxy.receive_config_changed().await.fuse();
+        let mut css_stream = config_proxy.receive_css_changed().await.fuse();
+        let mut state_stream = state_proxy.receive_state_data_changed().await.fuse();
+
+        let model = Self {
+            config: OsdConfig::default(),
+            popup: Popup::default(),
+            visible: false,
+            previous: None,
+            generation: 0,
+        };
+
+        let widgets = view_output!();
+
+        let update_sender = sender.input_sender().clone();
+
+        relm4::spawn(async move {
+            loop {
+                if futures_util::select! {
+                    config = config_stream.select_next_some() => {
+                        let config = toml::from_str::<common::config::Config>(&config.get().await?)
+                            .expect("Config string returned by daemon should always be valid.");
+
+                        update_sender.send(AppMsg::ConfigUpdated(config.osd))
+                    }
+                    css = css_stream.select_next_some() => {
+                        update_sender.send(AppMsg::CssUpdated(css.get().await?))
+                    }
+                    state = state_stream.select_next_some() => {
+                        update_sender.send(AppMsg::StateUpdated(Box::new(state.get().await?)))
+                    }
+                }
+                .is_err()
+                {
+                    log::error!("Failed processing update from daemon");
+                }
+            }
+
+            #[allow(unreachable_code, reason = "Needed for type inference")]
+            Ok::<(), zbus::Error>(())
+        });
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
+        match msg {
+            AppMsg::CssUpdated(css) => relm4::set_global_css(&css),
+            AppMsg::ConfigUpdated(config) => self.config = config,
+            AppMsg::StateUpdated(state) => {
+                let snapshot = Snapshot::from(&*state);
+                let popup = self
+                    .previous
+                    .as_ref()
+                    .and_then(|previous| snapshot.changes(previous));
+
+                self.previous = Some(snapshot);
+
+                if let Some(popup) = popup {
+                    self.show(popup, &sender);
+                }
+            }
+            AppMsg::Hide(generation) => {
+                if generation == self.generation {
+                    self.visible = false;
+                }
+            }
+        }
+    }
+}
+
+/// Main entry point for launching the osd
+///
+/// ## Errors
+///
+/// This function errors if there is are any problems with:
+///
+/// 1. Creating a tokio runtime
+///
+/// 2. Getting the needed state from the daemon
+pub fn launch() {
+    let app = RelmApp::new("dod-shell.osd");
+    relm4_icons::initialize_icons(icon::GRESOURCE_BYTES, icon::RESOURCE_PREFIX);
+
+    app.run_async::<App>(());
+}
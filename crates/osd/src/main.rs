@@ -0,0 +1,8 @@
+//! Binary for the osd. See lib for more information
+use common::logger;
+
+fn main() {
+    logger!();
+
+    osd::launch();
+}
@@ -0,0 +1,157 @@
+//! Finding out what the osd should show
+//!
+//! See [`Snapshot::changes`]
+use common::types::Percentage;
+use daemon::system_state::{SystemStateData, VolumeState};
+
+use crate::icon;
+
+/// The parts of the [`SystemStateData`] the osd shows changes of
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    /// See [`SystemStateData::volume`]
+    volume: VolumeState,
+    /// Description of the default audio output
+    sink: Option<String>,
+    /// Level of the screen backlight
+    backlight: Option<Percentage>,
+    /// Level of the keyboard backlight
+    keyboard: Option<Percentage>,
+    /// See [`SystemStateData::capslock`]
+    capslock: bool,
+    /// See [`SystemStateData::numlock`]
+    numlock: bool,
+}
+
+impl From<&SystemStateData> for Snapshot {
+    fn from(value: &SystemStateData) -> Self {
+        Self {
+            volume: value.volume,
+            sink: value
+                .audio
+                .default_sink()
+                .map(|sink| sink.description.clone()),
+            backlight: (*value.brightness.backlight).as_ref().map(|b| b.level),
+            keyboard: (*value.brightness.keyboard).as_ref().map(|b| b.level),
+            capslock: value.capslock,
+            numlock: value.numlock,
+        }
+    }
+}
+
+impl Snapshot {
+    /// The popup for the change from `previous` to `self`
+    ///
+    /// If multiple things changed at once, only the first of the default audio output, the
+    /// volume, the brightness of the screen and keyboard and caps lock / num lock is shown.
+    ///
+    /// Audio changes are only shown if there already was a default audio output before. This way
+    /// the state republished after (re)connecting to `PulseAudio` isn't shown as a change.
+    pub fn changes(&self, previous: &Self) -> Option<Popup> {
+        if self.volume.available
+            && previous.volume.available
+            && (self.sink != previous.sink || self.volume != previous.volume)
+        {
+            let label = if self.sink == previous.sink {
+                "Volume".to_string()
+            } else {
+                self.sink.clone().unwrap_or_default()
+            };
+
+            return Some(Popup {
+                icon: volume_icon(self.volume),
+                label,
+                level: Some(*self.volume.level),
+                muted: self.volume.muted,
+                active: false,
+            });
+        }
+
+        if let Some(level) = self.backlight
+            && self.backlight != previous.backlight
+        {
+            return Some(Popup::level(
+                icon::BRIGHTNESS_FILLED_SYMBOLIC,
+                "Brightness",
+                level,
+            ));
+        }
+
+        if let Some(level) = self.keyboard
+            && self.keyboard != previous.keyboard
+        {
+            return Some(Popup::level(icon::KEYBOARD_FILLED, "Keyboard", level));
+        }
+
+        if self.capslock != previous.capslock {
+            return Some(Popup::toggle(
+                icon::KEYBOARD_CAPS_LOCK,
+                "Caps Lock",
+                self.capslock,
+            ));
+        }
+
+        if self.numlock != previous.numlock {
+            return Some(Popup::toggle(
+                icon::DOCUMENT_PAGE_NUMBER_FILLED_SYMBOLIC,
+                "Num Lock",
+                self.numlock,
+            ));
+        }
+
+        None
+    }
+}
+
+/// What the osd shows
+#[derive(Debug, Clone, Default)]
+pub struct Popup {
+    /// Name of the icon
+    pub icon: &'static str,
+    /// What changed
+    pub label: String,
+    /// The new level (0.0 - 1.0), if the change has one
+    pub level: Option<f64>,
+    /// If the audio output is muted
+    pub muted: bool,
+    /// If the key (e.g. caps lock) is now active
+    pub active: bool,
+}
+
+impl Popup {
+    /// Create a [`Popup`] showing `level`
+    fn level(icon: &'static str, label: &str, level: Percentage) -> Self {
+        Self {
+            icon,
+            label: label.to_string(),
+            level: Some(*level),
+            ..Default::default()
+        }
+    }
+
+    /// Create a [`Popup`] for a key which was turned on or off
+    fn toggle(icon: &'static str, label: &str, active: bool) -> Self {
+        Self {
+            icon,
+            label: format!("{label} {}", if active { "on" } else { "off" }),
+            active,
+            ..Default::default()
+        }
+    }
+}
+
+/// Helper function to get the icon for `volume`
+///
+/// Matches the icon shown in the bar.
+fn volume_icon(volume: VolumeState) -> &'static str {
+    match volume {
+        VolumeState { muted: true, .. } => icon::SPEAKER_OFF_FILLED,
+        VolumeState {
+            bluetooth: true, ..
+        } => icon::SPEAKER_BLUETOOTH_FILLED_SYMBOLIC,
+        VolumeState { level, .. } if *level >= 0.66 => icon::SPEAKER_2_FILLED,
+        VolumeState { level, .. } if *level >= 0.33 => icon::SPEAKER_1_FILLED,
+        VolumeState { level, .. } if *level > 0.0 => icon::SPEAKER_0_FILLED,
+        VolumeState { .. } => icon::SPEAKER_MUTE_FILLED,
+    }
+}
@@ -131,6 +131,26 @@
         );
         osk-release = make-release osk;
 
+        osd = craneLib.buildPackage (
+          individualCrateArgs
+          // {
+            pname = "dod-shell-osd";
+            cargoExtraArgs = "-p osd";
+            src = fileSetForCrate ./crates/osd;
+          }
+        );
+        osd-release = make-release osd;
+
+        notifications = craneLib.buildPackage (
+          individualCrateArgs
+          // {
+            pname = "dod-shell-notifications";
+            cargoExtraArgs = "-p notifications";
+            src = fileSetForCrate ./crates/notifications;
+          }
+        );
+        notifications-release = make-release notifications;
+
         daemon = craneLib.buildPackage (
           individualCrateArgs
           // {
@@ -146,15 +166,7 @@
           // {
             pname = "dod-shell-cli";
             cargoExtraArgs = "-p cli";
-            src = lib.fileset.toSource {
-              root = ./.;
-              fileset = lib.fileset.unions [
-                ./Cargo.toml
-                ./Cargo.toml
-                (craneLib.fileset.commonCargoSources ./crates/common)
-                (craneLib.fileset.commonCargoSources ./crates/cli)
-              ];
-            };
+            src = fileSetForCrate ./crates/cli;
           }
         );
         cli-release = make-release cli;
@@ -173,6 +185,10 @@
             bar-release
             osk
             osk-release
+            osd
+            osd-release
+            notifications
+            notifications-release
             daemon
             daemon-release
             cli
@@ -29,6 +29,40 @@ let
     else
       component;
 
+  style-scss =
+    if cfg.scss.source != null then cfg.scss.source else pkgs.writeText "style.scss" cfg.scss.text;
+
+  config-toml =
+    if cfg.config.config != { } then
+      tomlFormat.generate "config.toml" cfg.config.config
+    else
+      cfg.config.source;
+
+  layouts-json =
+    if cfg.layouts.config != { } then
+      jsonFormat.generate "layouts.json" cfg.layouts.config
+    else
+      cfg.layouts.source;
+
+  # All config files in one directory, checked using `dod-shell-cli check-config`
+  checked-config =
+    let
+      cli = self.packages.${pkgs.stdenv.hostPlatform.system}.cli-release;
+    in
+    pkgs.runCommand "dod-shell-config" { } (
+      ''
+        mkdir $out
+        cp ${style-scss} $out/style.scss
+        cp ${layouts-json} $out/layouts.json
+      ''
+      + lib.optionalString (config-toml != null) ''
+        cp ${config-toml} $out/config.toml
+      ''
+      + lib.optionalString cfg.check-config ''
+        ${cli}/bin/dod-shell-cli check-config --skip-system-checks --only-dir $out
+      ''
+    );
+
   final_components = builtins.filter (
     component:
     !(lib.lists.any (removed_component: component == removed_component) cfg.removed-components)
@@ -128,6 +162,24 @@ in
       };
     };
 
+    check-config = lib.mkOption {
+      type = lib.types.bool;
+      default = true;
+      description = ''
+        Check the config files using `dod-shell-cli check-config` when
+        building the configuration.
+
+        Checks depending on the current system (e.g. if the disks in
+        `bar.disks` are mounted) are skipped, since they can't be done at
+        build time.
+      '';
+    };
+
+    log-file = lib.mkEnableOption ''
+      writing logs to {file}`$XDG_STATE_HOME/dod-shell/<component>.log`
+      in addition to the journal
+    '';
+
     systemd-services = lib.mkOption {
       type = with lib.types; listOf str;
       default = map (p: p.pname) final_components;
@@ -147,25 +199,9 @@ in
   config = lib.mkIf cfg.enable {
     home.packages = final_components;
     xdg.configFile = {
-      "dod-shell/style.scss" = {
-        inherit (cfg.scss) text;
-
-        source = lib.mkIf (cfg.scss.source != null) cfg.scss.source;
-      };
-      "dod-shell/config.toml" = {
-        source =
-          if cfg.config.config != { } then
-            tomlFormat.generate "config.toml" cfg.config.config
-          else
-            cfg.config.source;
-      };
-      "dod-shell/layouts.json" = {
-        source =
-          if cfg.layouts.config != { } then
-            jsonFormat.generate "layouts.json" cfg.layouts.config
-          else
-            cfg.layouts.source;
-      };
+      "dod-shell/style.scss".source = "${checked-config}/style.scss";
+      "dod-shell/config.toml".source = lib.mkIf (config-toml != null) "${checked-config}/config.toml";
+      "dod-shell/layouts.json".source = "${checked-config}/layouts.json";
     };
 
     systemd.user = {
@@ -197,13 +233,14 @@ in
                 Type = "exec";
                 Restart = "on-failure";
                 RestartSec = 3;
+                Environment = lib.optional cfg.log-file "DOD_SHELL_LOG_FILE=1";
               };
               Install = {
                 WantedBy = [ "dod-shell.target" ];
               };
             };
         in
-        (lib.genAttrs' [ "bar" "osk" ] (c: lib.nameValuePair ("dod-shell-" + c) (mkComponentService c)))
+        (lib.genAttrs' [ "bar" "osk" "osd" "notifications" ] (c: lib.nameValuePair ("dod-shell-" + c) (mkComponentService c)))
         // {
           dod-shell-daemon = if_in_services "dod-shell-daemon" {
             Unit = {
@@ -216,6 +253,7 @@ in
               BusName = "dod.shell.Daemon";
               Restart = "on-failure";
               RestartSec = 3;
+              Environment = lib.optional cfg.log-file "DOD_SHELL_LOG_FILE=1";
             };
             Install = {
               WantedBy = [ "dod-shell.target" ];